@@ -0,0 +1,47 @@
+//! Compares serial and parallel directory walks on a synthetic tree.
+//!
+//! Run with `cargo bench --bench scan`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use ignore::{WalkBuilder, WalkState};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Creates 10 000 empty files spread over 100 directories.
+fn create_tree(root: &Path) {
+    for dir in 0..100 {
+        let dir = root.join(format!("dir{dir:03}"));
+        fs::create_dir(&dir).unwrap();
+        for file in 0..100 {
+            fs::File::create(dir.join(format!("file{file:03}.txt"))).unwrap();
+        }
+    }
+}
+
+fn bench_scan(c: &mut Criterion) {
+    let temp_dir = tempfile::tempdir().unwrap();
+    create_tree(temp_dir.path());
+    let builder = WalkBuilder::new(temp_dir.path());
+
+    let mut group = c.benchmark_group("scan_10k_files");
+    group.bench_function("serial", |b| b.iter(|| builder.build().filter_map(Result::ok).count()));
+    group.bench_function("parallel", |b| {
+        b.iter(|| {
+            let count = AtomicUsize::new(0);
+            builder.build_parallel().run(|| {
+                Box::new(|result| {
+                    if result.is_ok() {
+                        count.fetch_add(1, Ordering::Relaxed);
+                    }
+                    WalkState::Continue
+                })
+            });
+            count.into_inner()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_scan);
+criterion_main!(benches);
@@ -1,15 +1,31 @@
 //! Defines the command-line interface for the fstree application.
 
+use crate::git;
+use crate::icons;
+use crate::output::OutputFormat;
 use crate::sort;
+use crate::utils;
 use clap::{Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+use sha2::Digest;
+use std::env;
 use std::fmt;
-use std::path::PathBuf;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
 
 /// A blazingly fast, minimalist directory tree viewer, written in Rust.
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
-#[command(override_usage = "fstree [OPTIONS] [PATH]\n    fstree interactive [OPTIONS] [PATH]")]
+#[command(override_usage = "fstree [OPTIONS] [PATH]
+    fstree interactive [OPTIONS] [PATH]
+    fstree large-dirs [OPTIONS] [PATH]
+    fstree list-extensions [OPTIONS] [PATH]
+    fstree color-test [--ascii]
+    fstree print-config [--format <FORMAT>]")]
 pub struct Args {
     /// The subcommand to run. If no subcommand is specified, the classic tree view is displayed.
     #[command(subcommand)]
@@ -21,11 +37,21 @@ pub struct Args {
 }
 
 /// Defines the available subcommands for the application.
+// Parsed once at startup, so the size of the largest variant does not matter.
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Start the interactive TUI explorer.
     #[command(visible_alias = "i")]
     Interactive(InteractiveArgs),
+    /// Report the directories consuming the most disk space.
+    LargeDirs(LargeDirsArgs),
+    /// List the file extensions found in the tree, most frequent first.
+    ListExtensions(ListExtArgs),
+    /// Print a color palette to check which colors the terminal supports.
+    ColorTest(ColorTestArgs),
+    /// Show the effective settings and whether they come from defaults, the config file or flags.
+    PrintConfig(PrintConfigArgs),
 }
 
 /// Arguments for the classic `view` command.
@@ -37,18 +63,132 @@ pub struct ViewArgs {
     /// Specify when to use colorized output.
     #[arg(long, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
     pub color: ColorChoice,
+    /// Color depth of the output; `auto` detects it from COLORTERM and TERM.
+    #[arg(long, value_name = "MODE", default_value_t = ColorMode::Auto)]
+    pub color_mode: ColorMode,
     /// Maximum depth to descend in the directory tree.
     #[arg(short = 'L', long)]
     pub level: Option<usize>,
+    /// Show only the top-level entries, with totals for the whole tree in the header.
+    #[arg(long, conflicts_with = "level")]
+    pub top_level_only: bool,
+    /// Show only the header with totals for the whole tree, like `du -s`.
+    #[arg(long)]
+    pub top_level_summary_only: bool,
+    /// Print only the summary line, counting the entries the filters keep.
+    #[arg(long = "count")]
+    pub count_only: bool,
+    /// Stop the walk after this many files.
+    #[arg(long, value_name = "N")]
+    pub max_files: Option<usize>,
+    /// Stop the walk after this many directories.
+    #[arg(long, value_name = "N")]
+    pub max_dirs: Option<usize>,
+    /// Hide entries shallower than this depth, while still descending into them.
+    #[arg(long, value_name = "N")]
+    pub min_depth: Option<usize>,
+    /// Hide entries matching the gitignore-style patterns in this file (`-` for stdin).
+    #[arg(long, value_name = "FILE")]
+    pub exclude_from: Vec<PathBuf>,
+    /// Print the tree as `text`, as `json` or `csv` for scripts, as a `markdown` code block or as an `html` page.
+    #[arg(long, value_name = "FORMAT", default_value = "text")]
+    pub format: OutputFormat,
+    /// Also write the tree, without colors, to this file.
+    #[arg(short = 'o', long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+    /// Show only files whose path relative to the root matches this glob (repeatable).
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+    /// Skip entries whose path relative to the root matches this glob (repeatable).
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+    /// Show only files of at least this size, e.g. `1M` (binary suffixes K, M, G, T).
+    #[arg(long, value_name = "SIZE", value_parser = utils::parse_size_string)]
+    pub min_size: Option<u64>,
+    /// Show only files of at most this size, e.g. `100M` (binary suffixes K, M, G, T).
+    #[arg(long, value_name = "SIZE", value_parser = utils::parse_size_string)]
+    pub max_size: Option<u64>,
+    /// Show only files whose name matches this regular expression.
+    #[arg(long, value_name = "PATTERN")]
+    pub filter_regex: Option<String>,
+    /// Show only files modified after this date (`YYYY-MM-DD`, UTC).
+    #[arg(long, value_name = "DATE", value_parser = utils::parse_date)]
+    pub newer_than: Option<SystemTime>,
+    /// Show only files modified before this date (`YYYY-MM-DD`, UTC).
+    #[arg(long, value_name = "DATE", value_parser = utils::parse_date)]
+    pub older_than: Option<SystemTime>,
+    /// Show only files with these extensions, e.g. `rs,toml` (case-insensitive).
+    #[arg(long, value_name = "EXT", value_delimiter = ',', conflicts_with = "exclude_ext")]
+    pub filter_ext: Vec<String>,
+    /// Hide files with these extensions, e.g. `lock,log` (case-insensitive).
+    #[arg(long, value_name = "EXT", value_delimiter = ',')]
+    pub exclude_ext: Vec<String>,
+    /// Print one absolute path per line instead of the tree, for piping into other tools.
+    #[arg(long)]
+    pub no_tree: bool,
+    /// Like `--no-tree`, but end each path with a NUL byte, for `xargs -0`.
+    #[arg(long)]
+    pub print0: bool,
+    /// Number of spaces each tree level is indented by.
+    #[arg(long, value_name = "N", default_value_t = 4, value_parser = parse_indent)]
+    pub indent: usize,
     /// Display directories only.
     #[arg(short = 'd', long)]
     pub dirs_only: bool,
+    /// Display files only, hiding every directory line.
+    #[arg(long, conflicts_with = "dirs_only")]
+    pub files_only: bool,
+    /// Show the target of each symlink after its name, as `name -> target`.
+    #[arg(long)]
+    pub dereference: bool,
+    /// Descend into symlinked directories; links back into their own ancestors are skipped.
+    #[arg(long)]
+    pub follow_links: bool,
+    /// Display the owner and group of each entry as `user:group` (Unix only).
+    #[arg(long)]
+    pub owner: bool,
+    /// Display the inode number of each entry (always 0 on Windows).
+    #[arg(long)]
+    pub inode: bool,
+    /// Display the number of hard links to each entry; shared files are highlighted.
+    #[arg(long)]
+    pub nlinks: bool,
+    /// Display when each entry was last accessed, e.g. `3 hours ago`.
+    #[arg(long)]
+    pub atime: bool,
+    /// Display the MIME type of each file, e.g. `(text/x-rust)`.
+    #[arg(long)]
+    pub mime: bool,
+    /// Display the number of lines of each text file, or `(binary)`.
+    #[arg(long)]
+    pub line_count: bool,
+    /// Display a hash of each file's contents.
+    #[arg(long, value_name = "ALGO")]
+    pub checksum: Option<ChecksumAlgo>,
+    /// With `--checksum`, skip files larger than this, e.g. `100M`.
+    #[arg(
+        long,
+        value_name = "SIZE",
+        default_value = "10M",
+        value_parser = utils::parse_size_string,
+        requires = "checksum"
+    )]
+    pub checksum_max_size: u64,
     /// Display the size of files.
     #[arg(short = 's', long)]
     pub size: bool,
+    /// Display the total size of the visible files below each directory.
+    #[arg(long)]
+    pub du: bool,
     /// Display file permissions.
     #[arg(short = 'p', long)]
     pub permissions: bool,
+    /// With `--permissions`, also show the mode in octal, e.g. `(0755)`.
+    #[arg(long, requires = "permissions")]
+    pub octal: bool,
+    /// Highlight world-writable, SUID and SGID files with a background color.
+    #[arg(long)]
+    pub highlight_special: bool,
     /// Show all files, including hidden ones.
     #[arg(short = 'a', long, help = "Show all files, including hidden ones")]
     pub all: bool,
@@ -61,18 +201,39 @@ pub struct ViewArgs {
     /// Display file-specific icons (requires a Nerd Font).
     #[arg(long, help = "Display file-specific icons (requires a Nerd Font)")]
     pub icons: bool,
+    /// Never display icons, even when the config file turns them on.
+    #[arg(long, overrides_with = "icons")]
+    pub no_icons: bool,
+    /// Draw an only child with a plain `──` connector instead of `└──`.
+    #[arg(long)]
+    pub no_branch_for_single_child: bool,
+    /// Show icons for files only, not for directories.
+    #[arg(long, requires = "icons")]
+    pub no_icons_for_dirs: bool,
+    /// Show icons for directories only, not for files.
+    #[arg(long, requires = "icons", conflicts_with = "no_icons_for_dirs")]
+    pub no_icons_for_files: bool,
+    /// Show icons only for files with this extension (repeatable).
+    #[arg(long, value_name = "EXT", requires = "icons")]
+    pub icons_only_for_ext: Vec<String>,
     /// Render file paths as clickable hyperlinks.
     #[arg(long)]
     pub hyperlinks: bool,
     /// Sort entries by the specified criteria.
     #[arg(long, default_value_t = SortType::Name)]
     pub sort: SortType,
+    /// Break ties left by `--sort` with this criterion.
+    #[arg(long, value_name = "SORT", default_value_t = SortType::Name)]
+    pub sort2: SortType,
     /// Sort directories before files.
     #[arg(long)]
     pub dirs_first: bool,
     /// Use case-sensitive sorting.
     #[arg(long)]
     pub case_sensitive: bool,
+    /// Sort case-insensitively, unless a directory contains uppercase names.
+    #[arg(long, conflicts_with = "case_sensitive")]
+    pub smart_case: bool,
     /// Use natural/version sorting (e.g., file1 < file10).
     #[arg(long)]
     pub natural_sort: bool,
@@ -82,10 +243,181 @@ pub struct ViewArgs {
     /// Sort dotfiles and dotfolders first.
     #[arg(long)]
     pub dotfiles_first: bool,
+    /// List the contents of ZIP and tar archives as virtual subdirectories.
+    #[arg(long)]
+    pub include_archives: bool,
+    /// Show the number of commits that touched each file.
+    #[arg(long, requires = "git_status")]
+    pub count_commits: bool,
+    /// Show the most recent tag reachable from the last commit of each changed file.
+    #[arg(long, requires = "git_status")]
+    pub git_tag: bool,
+    /// With `--git-tag`, show tags for every committed file, not only changed ones.
+    #[arg(long, requires = "git_tag")]
+    pub git_tag_all: bool,
+    /// How to display tags: `name` or `describe` (with the distance from the tag).
+    #[arg(long, value_name = "FORMAT", default_value = "name")]
+    pub git_tag_format: git::GitTagFormat,
+    /// Show only files with a Git status; directories are always kept.
+    #[arg(long, requires = "git_status")]
+    pub only_git_changed: bool,
+    /// Show only untracked files; directories are always kept.
+    #[arg(long, requires = "git_status", conflicts_with = "only_git_changed")]
+    pub only_untracked: bool,
+    /// Flag files containing Git conflict markers (`<<<<<<< `) with `[CONFLICT]`.
+    #[arg(long)]
+    pub git_conflict_markers: bool,
+    /// Silently skip files and directories that cannot be read due to permissions.
+    #[arg(long)]
+    pub ignore_permission_errors: bool,
+    /// Print additional details in the summary.
+    #[arg(long)]
+    pub verbose: bool,
+    /// Show files as a grid ordered by inode number to visualize fragmentation (Unix only).
+    #[arg(long)]
+    pub inode_map: bool,
+    /// Number of files represented by each cell of the inode map.
+    #[arg(long, value_name = "FILES_PER_CELL", default_value_t = 1, requires = "inode_map")]
+    pub inode_map_scale: usize,
+    /// Show a bar chart of the most common file extensions after the tree.
+    #[arg(long)]
+    pub extension_stats: bool,
+    /// Rank the extension chart by total bytes instead of file count.
+    #[arg(long)]
+    pub extension_stats_by_size: bool,
+    /// Show only the extension chart, without the tree.
+    #[arg(long)]
+    pub extension_stats_only: bool,
+    /// Report the most recently modified file after the summary.
+    #[arg(long)]
+    pub last_modified: bool,
+    /// Report the N most recently modified files after the summary.
+    #[arg(long, value_name = "N")]
+    pub last_modified_n: Option<usize>,
+    /// Display the root as a custom label (e.g. `myproject/`) instead of its path.
+    #[arg(long = "virtual-root", value_name = "NAME")]
+    pub display_root: Option<String>,
+    /// Flag files sharing a stem with a file of another extension in the same directory.
+    #[arg(long)]
+    pub report_duplicate_extensions: bool,
+    /// Show only files with duplicate-extension conflicts (and their parent directories).
+    #[arg(long)]
+    pub report_duplicate_extensions_only: bool,
+    /// Flag sparse files, which use less than 90% of their apparent size on disk (Unix only).
+    #[arg(long)]
+    pub report_sparse_files: bool,
+    /// Show only sparse files (and their parent directories).
+    #[arg(long)]
+    pub report_sparse_only: bool,
+    /// Flag files sharing a stem with a file of another extension anywhere in the tree.
+    #[arg(long)]
+    pub report_stem_conflicts: bool,
+    /// Print the path of the parent directory above each block of nested entries.
+    #[arg(long)]
+    pub breadcrumb: bool,
+    /// Show only entries of the given type, like `find -type` (directories are always kept).
+    #[arg(long = "type", value_name = "TYPE")]
+    pub file_type: Option<utils::FileTypeFilter>,
+    /// Show the number of lines added and removed in each changed file.
+    #[arg(long, requires = "git_status")]
+    pub git_diff_stat: bool,
+    /// Show the diff stat as a `++--` bar instead of numbers.
+    #[arg(long, requires = "git_diff_stat")]
+    pub git_diff_stat_bar: bool,
+    /// Highlight the entries a `.gitignore` pattern would hide, without changing any file.
+    #[arg(long, value_name = "PATTERN")]
+    pub dry_run_gitignore: Option<String>,
+    /// Normalize file names to the given Unicode form before sorting them.
+    #[arg(long, value_name = "FORM")]
+    pub unicode_normalize: Option<utils::NormalizationForm>,
+    /// Sort names using the collation rules of a locale, e.g. `de_DE.UTF-8` (`C` for byte order).
+    #[arg(long, value_name = "LOCALE", value_parser = parse_sort_locale)]
+    pub sort_locale: Option<String>,
+    /// Stay on the filesystem of the root, skipping mount points below it, like `find -xdev`.
+    ///
+    /// Useful to keep network shares or bind mounts out of the tree.
+    #[arg(short = 'x', long)]
+    pub one_filesystem: bool,
+    /// Scan directories on several threads; the output is the same as a serial scan.
+    #[arg(short = 'P', long)]
+    pub parallel: bool,
+    /// Show a spinner with the number of scanned entries on stderr while scanning.
+    /// Ignored when stdout is not a terminal.
+    #[arg(long)]
+    pub progress: bool,
+    /// Never show the scanning spinner, even with `--progress`.
+    #[arg(long)]
+    pub no_progress: bool,
+    /// Redraw the tree whenever a file below the root is created, removed, renamed or modified.
+    #[arg(long, conflicts_with_all = ["watch_persistent", "watch_diff_only"])]
+    pub watch: bool,
+    /// In the watch modes, wait until no change was seen for this many milliseconds before redrawing.
+    #[arg(long, value_name = "MS", default_value_t = 200)]
+    pub watch_interval: u64,
+    /// Keep running and update the changed lines in place whenever the tree changes.
+    #[arg(long, conflicts_with = "watch_diff_only")]
+    pub watch_persistent: bool,
+    /// Keep running and print only the lines that changed since the previous scan.
+    #[arg(long)]
+    pub watch_diff_only: bool,
+    /// Group entries under the user that owns them (Unix only).
+    #[arg(long)]
+    pub by_owner: bool,
+    /// Group entries under the group that owns them (Unix only).
+    #[arg(long, conflicts_with = "by_owner")]
+    pub by_owner_gid: bool,
+    /// List only symlinks, each with the chain of targets it resolves through.
+    #[arg(long)]
+    pub scan_symlinks_only: bool,
+    /// Suggest `chmod` commands for permission issues (dry run, nothing is changed).
+    #[arg(long, value_name = "ISSUES", value_delimiter = ',')]
+    pub permission_fix: Vec<utils::PermissionIssue>,
+    /// Show only the suggested `chmod` commands, without the tree.
+    #[arg(long, requires = "permission_fix")]
+    pub permission_fix_only: bool,
+    /// Also write the suggested `chmod` commands to a shell script.
+    #[arg(long, value_name = "FILE", requires = "permission_fix")]
+    pub permission_fix_script: Option<PathBuf>,
+    /// Try to open every file for reading and flag the ones that fail.
+    #[arg(long)]
+    pub access_check: bool,
+    /// Stop with an error at the first unreadable file.
+    #[arg(long, requires = "access_check")]
+    pub access_check_fail_fast: bool,
+    /// Try to open every file for writing (without truncating) and flag the ones that fail.
+    #[arg(long)]
+    pub write_check: bool,
+    /// Show how many gitignored entries each directory contains.
+    #[arg(long, requires = "gitignore")]
+    pub git_show_ignored_count: bool,
+    /// Order of post-processing passes; `bottomup` adds directory totals to `--size`.
+    #[arg(long, value_name = "STRATEGY", default_value = "topdown")]
+    pub walk_strategy: utils::WalkStrategy,
+    /// Show a one-line summary instead of the contents of directories matching this glob.
+    #[arg(long, value_name = "GLOB")]
+    pub summarize_dir: Vec<String>,
+    /// Show a one-line summary instead of the contents of directories at this depth or deeper.
+    #[arg(long, value_name = "N")]
+    pub summarize_depth: Option<usize>,
+    /// Render a tree from a list of paths, one per line, instead of scanning (`-` for stdin).
+    #[arg(long, value_name = "FILE", conflicts_with = "path")]
+    pub input: Option<PathBuf>,
+    /// Read `--input` paths separated by NUL bytes, as printed by `git ls-files -z`.
+    #[arg(long, requires = "input")]
+    pub input_null: bool,
+    /// Read the config file from this path instead of the default location.
+    #[arg(long, value_name = "PATH", conflicts_with = "no_config")]
+    pub config: Option<PathBuf>,
+    /// Apply the settings of a `[profile.NAME]` section of the config file.
+    #[arg(long, value_name = "NAME", conflicts_with = "no_config")]
+    pub profile: Option<String>,
+    /// Ignore the config file and use the built-in defaults.
+    #[arg(long)]
+    pub no_config: bool,
 }
 
 /// Arguments for the `interactive` command.
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Default)]
 pub struct InteractiveArgs {
     /// The path to the directory to explore. Defaults to the current directory.
     #[arg(default_value = ".")]
@@ -102,24 +434,102 @@ pub struct InteractiveArgs {
     /// Display file-specific icons (requires a Nerd Font).
     #[arg(long)]
     pub icons: bool,
+    /// Never display icons, even when the config file turns them on.
+    #[arg(long, overrides_with = "icons")]
+    pub no_icons: bool,
+    /// Draw directories with a single child with `─` instead of `▼`/`▶`.
+    #[arg(long)]
+    pub no_branch_for_single_child: bool,
+    /// Show icons for files only, not for directories.
+    #[arg(long, requires = "icons")]
+    pub no_icons_for_dirs: bool,
+    /// Show icons for directories only, not for files.
+    #[arg(long, requires = "icons", conflicts_with = "no_icons_for_dirs")]
+    pub no_icons_for_files: bool,
+    /// Show icons only for files with this extension (repeatable).
+    #[arg(long, value_name = "EXT", requires = "icons")]
+    pub icons_only_for_ext: Vec<String>,
+    /// Color depth of the output; `auto` detects it from COLORTERM and TERM.
+    #[arg(long, value_name = "MODE", default_value_t = ColorMode::Auto)]
+    pub color_mode: ColorMode,
     /// Display the size of files.
     #[arg(short = 's', long)]
     pub size: bool,
     /// Display file permissions.
     #[arg(short = 'p', long)]
     pub permissions: bool,
+    /// Highlight world-writable, SUID and SGID files with a background color.
+    #[arg(long)]
+    pub highlight_special: bool,
+    /// Show a custom label (e.g. `myproject/`) for the root in the breadcrumb header.
+    #[arg(long = "virtual-root", value_name = "NAME")]
+    pub display_root: Option<String>,
     /// Initial depth to expand the directory tree.
     #[arg(long, value_name = "LEVEL")]
     pub expand_level: Option<usize>,
+    /// Number of spaces each tree level is indented by.
+    #[arg(long, value_name = "N", default_value_t = 4, value_parser = parse_indent)]
+    pub indent: usize,
+    /// Display files only, hiding every directory line.
+    #[arg(long)]
+    pub files_only: bool,
+    /// Show the target of each symlink after its name, as `name -> target`.
+    #[arg(long)]
+    pub dereference: bool,
+    /// Descend into symlinked directories; links back into their own ancestors are skipped.
+    #[arg(long)]
+    pub follow_links: bool,
+    /// Display the owner and group of each entry as `user:group` (Unix only).
+    #[arg(long)]
+    pub owner: bool,
+    /// Display the inode number of each entry (always 0 on Windows).
+    #[arg(long)]
+    pub inode: bool,
+    /// Display the number of hard links to each entry; shared files are highlighted.
+    #[arg(long)]
+    pub nlinks: bool,
+    /// Hide entries shallower than this depth, while still descending into them.
+    #[arg(long, value_name = "N")]
+    pub min_depth: Option<usize>,
+    /// Hide entries matching the gitignore-style patterns in this file (`-` for stdin).
+    #[arg(long, value_name = "FILE")]
+    pub exclude_from: Vec<PathBuf>,
+    /// Show only files whose path relative to the root matches this glob (repeatable).
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+    /// Skip entries whose path relative to the root matches this glob (repeatable).
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+    /// Show only files whose name matches this regular expression.
+    #[arg(long, value_name = "PATTERN")]
+    pub filter_regex: Option<String>,
+    /// Show only files modified after this date (`YYYY-MM-DD`, UTC).
+    #[arg(long, value_name = "DATE", value_parser = utils::parse_date)]
+    pub newer_than: Option<SystemTime>,
+    /// Show only files modified before this date (`YYYY-MM-DD`, UTC).
+    #[arg(long, value_name = "DATE", value_parser = utils::parse_date)]
+    pub older_than: Option<SystemTime>,
+    /// Show only files with these extensions, e.g. `rs,toml` (case-insensitive).
+    #[arg(long, value_name = "EXT", value_delimiter = ',', conflicts_with = "exclude_ext")]
+    pub filter_ext: Vec<String>,
+    /// Hide files with these extensions, e.g. `lock,log` (case-insensitive).
+    #[arg(long, value_name = "EXT", value_delimiter = ',')]
+    pub exclude_ext: Vec<String>,
     /// Sort entries by the specified criteria.
     #[arg(long, default_value_t = SortType::Name)]
     pub sort: SortType,
+    /// Break ties left by `--sort` with this criterion.
+    #[arg(long, value_name = "SORT", default_value_t = SortType::Name)]
+    pub sort2: SortType,
     /// Sort directories before files.
     #[arg(long)]
     pub dirs_first: bool,
     /// Use case-sensitive sorting.
     #[arg(long)]
     pub case_sensitive: bool,
+    /// Sort case-insensitively, unless a directory contains uppercase names.
+    #[arg(long, conflicts_with = "case_sensitive")]
+    pub smart_case: bool,
     /// Use natural/version sorting (e.g., file1 < file10).
     #[arg(long)]
     pub natural_sort: bool,
@@ -129,10 +539,147 @@ pub struct InteractiveArgs {
     /// Sort dotfiles and dotfolders first.
     #[arg(long)]
     pub dotfiles_first: bool,
+    /// Show the number of commits that touched each file.
+    #[arg(long, requires = "git_status")]
+    pub count_commits: bool,
+    /// Show the most recent tag reachable from the last commit of each changed file.
+    #[arg(long, requires = "git_status")]
+    pub git_tag: bool,
+    /// With `--git-tag`, show tags for every committed file, not only changed ones.
+    #[arg(long, requires = "git_tag")]
+    pub git_tag_all: bool,
+    /// How to display tags: `name` or `describe` (with the distance from the tag).
+    #[arg(long, value_name = "FORMAT", default_value = "name")]
+    pub git_tag_format: git::GitTagFormat,
+    /// Show only files with a Git status; directories are always kept.
+    #[arg(long, requires = "git_status")]
+    pub only_git_changed: bool,
+    /// Flag files containing Git conflict markers (`<<<<<<< `) with `[CONFLICT]`.
+    #[arg(long)]
+    pub git_conflict_markers: bool,
+    /// Print the initial TUI layout as plain text instead of starting the TUI.
+    #[arg(long)]
+    pub headless: bool,
+    /// Show a preview of the selected file beside the tree; Tab toggles it.
+    #[arg(long)]
+    pub preview: bool,
+    /// Refresh the tree whenever a file below the root is created, removed, renamed or modified.
+    #[arg(long)]
+    pub watch: bool,
+    /// Show a color palette instead of the tree, to check terminal color support.
+    #[arg(long)]
+    pub color_test: bool,
+    /// With `--color-test`, draw swatches with the letters A-Z instead of blocks.
+    #[arg(long, requires = "color_test")]
+    pub color_test_ascii: bool,
+    /// Show the number of lines added and removed in each changed file.
+    #[arg(long, requires = "git_status")]
+    pub git_diff_stat: bool,
+    /// Show only entries of the given type, like `find -type` (directories are always kept).
+    #[arg(long = "type", value_name = "TYPE")]
+    pub file_type: Option<utils::FileTypeFilter>,
 }
 
-/// Defines the available sorting strategies.
+/// Arguments for the `large-dirs` command.
+#[derive(Parser, Debug, Default)]
+pub struct LargeDirsArgs {
+    /// The path to the directory to analyze. Defaults to the current directory.
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+    /// Number of directories to report.
+    #[arg(short = 'n', long, default_value_t = 10)]
+    pub count: usize,
+    /// Include hidden files and directories.
+    #[arg(short = 'a', long)]
+    pub all: bool,
+    /// Respect .gitignore and other standard ignore files.
+    #[arg(short = 'g', long)]
+    pub gitignore: bool,
+    /// Only report directories up to this depth below the root.
+    #[arg(long, value_name = "N")]
+    pub large_dirs_depth: Option<usize>,
+    /// Print the report as JSON.
+    #[arg(long)]
+    pub large_dirs_json: bool,
+}
+
+/// Arguments for the `list-extensions` command.
+#[derive(Parser, Debug, Default)]
+pub struct ListExtArgs {
+    /// The path to the directory to scan. Defaults to the current directory.
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+    /// Include hidden files and directories.
+    #[arg(short = 'a', long)]
+    pub all: bool,
+    /// Respect .gitignore and other standard ignore files.
+    #[arg(short = 'g', long)]
+    pub gitignore: bool,
+    /// Maximum depth to descend in the directory tree.
+    #[arg(short = 'L', long)]
+    pub level: Option<usize>,
+    /// Count files without an extension as `[none]`.
+    #[arg(long)]
+    pub list_extensions_include_no_ext: bool,
+    /// Also show the total size of the files with each extension.
+    #[arg(long)]
+    pub list_extensions_size: bool,
+    /// Print the extensions as JSON.
+    #[arg(long)]
+    pub list_extensions_json: bool,
+}
+
+/// Arguments for the `color-test` command.
+#[derive(Parser, Debug, Default)]
+pub struct ColorTestArgs {
+    /// Draw swatches with the letters A-Z instead of block characters.
+    #[arg(long, visible_alias = "color-test-ascii")]
+    pub ascii: bool,
+}
+
+/// Arguments for the `print-config` command.
+#[derive(Parser, Debug, Default)]
+pub struct PrintConfigArgs {
+    /// Print the settings as a `text` table or as `json`.
+    #[arg(long, value_name = "FORMAT", default_value = "text")]
+    pub format: PrintConfigFormat,
+}
+
+/// Defines the output formats of `print-config`.
 #[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum PrintConfigFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Defines the hash algorithms of the --checksum option.
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ChecksumAlgo {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl ChecksumAlgo {
+    /// Hashes the whole contents of a file into a lowercase hex digest.
+    pub fn hash_file(self, path: &Path) -> io::Result<String> {
+        fn digest<D: Digest + io::Write>(path: &Path) -> io::Result<String> {
+            let mut hasher = D::new();
+            io::copy(&mut File::open(path)?, &mut hasher)?;
+            Ok(hasher.finalize().iter().map(|byte| format!("{byte:02x}")).collect())
+        }
+        match self {
+            ChecksumAlgo::Md5 => digest::<md5::Md5>(path),
+            ChecksumAlgo::Sha1 => digest::<sha1::Sha1>(path),
+            ChecksumAlgo::Sha256 => digest::<sha2::Sha256>(path),
+        }
+    }
+}
+
+/// Defines the available sorting strategies.
+#[derive(ValueEnum, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
 pub enum SortType {
     /// Sort by name (default)
     #[default]
@@ -141,12 +688,23 @@ pub enum SortType {
     Size,
     /// Sort by modification time
     Modified,
+    /// Sort by creation time (modification time where it is not recorded)
+    Created,
+    /// Sort by last access time (unreliable on noatime mounts)
+    Accessed,
+    /// Shuffle entries into a random order (--reverse has no effect)
+    Random,
+    /// Sort directories by number of immediate children, most first
+    Children,
     /// Sort by file extension
     Extension,
+    /// Sort by number of commits, most first (requires --count-commits)
+    Commits,
 }
 
 /// Defines the choices for the --color option.
-#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq, Default)]
+#[derive(ValueEnum, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
 pub enum ColorChoice {
     Always,
     #[default]
@@ -154,13 +712,62 @@ pub enum ColorChoice {
     Never,
 }
 
+/// Defines the choices for the --color-mode option.
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    /// Detect the color depth from the COLORTERM and TERM variables
+    #[default]
+    Auto,
+    /// The 8 basic ANSI colors
+    #[value(name = "8")]
+    Ansi8,
+    /// The xterm 256-color palette
+    #[value(name = "256")]
+    Ansi256,
+    /// 24-bit RGB colors
+    #[value(name = "truecolor")]
+    TrueColor,
+}
+
+impl ColorMode {
+    /// Resolves `Auto` to the color depth advertised by the terminal.
+    pub fn resolve(self) -> Self {
+        if self != ColorMode::Auto {
+            return self;
+        }
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        let term = env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorMode::TrueColor
+        } else if term.contains("256color") {
+            ColorMode::Ansi256
+        } else {
+            ColorMode::Ansi8
+        }
+    }
+}
+
+/// Fails if `--sort` or `--sort2` is `commits` without `--count-commits`, since
+/// no commit counts would be loaded and every entry would compare as equal.
+pub fn check_commit_sort(sorts: [SortType; 2], count_commits: bool) -> anyhow::Result<()> {
+    if !count_commits && sorts.contains(&SortType::Commits) {
+        anyhow::bail!("--sort commits requires --count-commits.");
+    }
+    Ok(())
+}
+
 impl From<SortType> for sort::SortType {
     fn from(sort_type: SortType) -> Self {
         match sort_type {
             SortType::Name => sort::SortType::Name,
             SortType::Size => sort::SortType::Size,
             SortType::Modified => sort::SortType::Modified,
+            SortType::Created => sort::SortType::Created,
+            SortType::Accessed => sort::SortType::Accessed,
+            SortType::Random => sort::SortType::Random,
+            SortType::Children => sort::SortType::Children,
             SortType::Extension => sort::SortType::Extension,
+            SortType::Commits => sort::SortType::Commits,
         }
     }
 }
@@ -170,13 +777,73 @@ impl ViewArgs {
     pub fn to_sort_options(&self) -> sort::SortOptions {
         sort::SortOptions {
             sort_type: self.sort.into(),
+            sort2: self.sort2.into(),
             directories_first: self.dirs_first,
             case_sensitive: self.case_sensitive,
+            smart_case: self.smart_case,
             natural_sort: self.natural_sort,
             reverse: self.reverse,
             dotfiles_first: self.dotfiles_first,
+            unicode_normalization: self.unicode_normalize,
+            collator: self
+                .sort_locale
+                .as_deref()
+                .and_then(|locale| sort::build_collator(locale).ok())
+                .map(Arc::from),
+            ..Default::default()
+        }
+    }
+
+    /// Creates an ErrorPolicy instance from the ViewArgs.
+    pub fn to_error_policy(&self) -> utils::ErrorPolicy {
+        utils::ErrorPolicy { ignore_permission_denied: self.ignore_permission_errors }
+    }
+
+    /// Returns the maximum depth to display, taking `--top-level-only` into account.
+    pub fn effective_level(&self) -> Option<usize> {
+        if self.top_level_only {
+            Some(1)
+        } else {
+            self.level
         }
     }
+
+    /// Returns the filter built from `--filter-ext` and `--exclude-ext`.
+    pub fn extension_filter(&self) -> utils::ExtensionFilter {
+        utils::ExtensionFilter::new(&self.filter_ext, &self.exclude_ext, self.dirs_only)
+    }
+
+    /// Returns true if `--format markdown --hyperlinks` draws the tree as a
+    /// nested list, since links do not render inside a code block.
+    pub fn prints_markdown_list(&self) -> bool {
+        self.format == OutputFormat::Markdown && self.hyperlinks
+    }
+
+    /// Returns true if the tree is replaced by a flat list of paths.
+    pub fn prints_flat_paths(&self) -> bool {
+        self.no_tree || self.print0
+    }
+
+    /// Returns true if `--dirs-only` or `--files-only` hides entries of this kind.
+    pub fn hides_kind(&self, is_dir: bool) -> bool {
+        if is_dir {
+            self.files_only
+        } else {
+            self.dirs_only
+        }
+    }
+
+    /// Returns true if the entry gets an icon with the current icon flags.
+    pub fn shows_icon(&self, path: &Path, is_dir: bool) -> bool {
+        self.icons
+            && icons::shows_icon(
+                path,
+                is_dir,
+                self.no_icons_for_dirs,
+                self.no_icons_for_files,
+                &self.icons_only_for_ext,
+            )
+    }
 }
 
 impl InteractiveArgs {
@@ -184,13 +851,47 @@ impl InteractiveArgs {
     pub fn to_sort_options(&self) -> sort::SortOptions {
         sort::SortOptions {
             sort_type: self.sort.into(),
+            sort2: self.sort2.into(),
             directories_first: self.dirs_first,
             case_sensitive: self.case_sensitive,
+            smart_case: self.smart_case,
             natural_sort: self.natural_sort,
             reverse: self.reverse,
             dotfiles_first: self.dotfiles_first,
+            ..Default::default()
         }
     }
+
+    /// Returns the filter built from `--filter-ext` and `--exclude-ext`.
+    pub fn extension_filter(&self) -> utils::ExtensionFilter {
+        utils::ExtensionFilter::new(&self.filter_ext, &self.exclude_ext, false)
+    }
+
+    /// Returns true if the entry gets an icon with the current icon flags.
+    pub fn shows_icon(&self, path: &Path, is_dir: bool) -> bool {
+        self.icons
+            && icons::shows_icon(
+                path,
+                is_dir,
+                self.no_icons_for_dirs,
+                self.no_icons_for_files,
+                &self.icons_only_for_ext,
+            )
+    }
+}
+
+/// Parses an `--indent` width, which must be at least 1.
+fn parse_indent(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("the indent must be at least 1".to_string()),
+        Ok(width) => Ok(width),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Validates a `--sort-locale` value by building its collator.
+fn parse_sort_locale(locale: &str) -> Result<String, String> {
+    sort::build_collator(locale).map(|_| locale.to_string()).map_err(|e| e.to_string())
 }
 
 /// Implements the Display trait for SortType to show possible values in help messages.
@@ -200,6 +901,13 @@ impl fmt::Display for SortType {
     }
 }
 
+/// Implements the Display trait for ColorMode to show possible values in help messages.
+impl fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_possible_value().expect("no values are skipped").get_name().fmt(f)
+    }
+}
+
 /// Implements the Display trait for ColorChoice to show possible values in help messages.
 impl fmt::Display for ColorChoice {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -0,0 +1,190 @@
+//! Provides read-only listing of archive contents.
+//!
+//! This module lets the tree view peek inside `.zip`, `.jar`, `.tar`, `.tar.gz`
+//! and `.tgz` files without extracting them. Each archive is flattened into a
+//! list of `ArchiveEntry` values ordered so they can be rendered as a subtree.
+
+use flate2::read::GzDecoder;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// The archive formats that can be listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+/// A single file or directory stored inside an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// The path of the entry relative to the archive root.
+    pub path: PathBuf,
+    /// Whether the entry is a directory.
+    pub is_dir: bool,
+    /// The uncompressed size of the entry in bytes (0 for directories).
+    pub size: u64,
+    /// Whether the entry is synthetic (not backed by a real filesystem path).
+    /// Always `true` for archive entries; kept explicit for the renderer.
+    pub is_virtual: bool,
+}
+
+impl ArchiveEntry {
+    /// Returns the nesting depth of the entry inside the archive, starting at 1.
+    pub fn depth(&self) -> usize {
+        self.path.components().count()
+    }
+}
+
+/// Detects the archive format of a path from its file name.
+pub fn archive_kind(path: &Path) -> Option<ArchiveKind> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    if name.ends_with(".zip") || name.ends_with(".jar") {
+        Some(ArchiveKind::Zip)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(ArchiveKind::TarGz)
+    } else if name.ends_with(".tar") {
+        Some(ArchiveKind::Tar)
+    } else {
+        None
+    }
+}
+
+/// Lists the contents of an archive, dispatching on its format.
+///
+/// Intermediate directories that are not stored explicitly in the archive are
+/// synthesized, and the result is ordered so that every directory precedes its
+/// children.
+pub fn list_archive_contents(path: &Path) -> anyhow::Result<Vec<ArchiveEntry>> {
+    let Some(kind) = archive_kind(path) else {
+        anyhow::bail!("'{}' is not a recognized archive.", path.display());
+    };
+
+    let file = BufReader::new(File::open(path)?);
+    let raw = match kind {
+        ArchiveKind::Zip => list_zip(file)?,
+        ArchiveKind::Tar => list_tar(file)?,
+        ArchiveKind::TarGz => list_tar(GzDecoder::new(file))?,
+    };
+
+    Ok(normalize_entries(raw))
+}
+
+/// Reads the central directory of a ZIP archive.
+fn list_zip<R: Read + std::io::Seek>(reader: R) -> anyhow::Result<Vec<(PathBuf, bool, u64)>> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    let mut entries = Vec::with_capacity(archive.len());
+    for i in 0..archive.len() {
+        let file = archive.by_index(i)?;
+        // Skip entries with unsafe paths (absolute or containing `..`).
+        let Some(name) = file.enclosed_name() else {
+            continue;
+        };
+        entries.push((name, file.is_dir(), file.size()));
+    }
+    Ok(entries)
+}
+
+/// Reads the headers of a (possibly decompressed) tar stream.
+fn list_tar<R: Read>(reader: R) -> anyhow::Result<Vec<(PathBuf, bool, u64)>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let is_dir = entry.header().entry_type().is_dir();
+        let size = if is_dir { 0 } else { entry.size() };
+        entries.push((entry.path()?.into_owned(), is_dir, size));
+    }
+    Ok(entries)
+}
+
+/// Deduplicates raw entries, adds missing parent directories and sorts them in tree order.
+fn normalize_entries(raw: Vec<(PathBuf, bool, u64)>) -> Vec<ArchiveEntry> {
+    // `Path` ordering is component-wise, so a BTreeMap yields a pre-order listing.
+    let mut map: BTreeMap<PathBuf, ArchiveEntry> = BTreeMap::new();
+    for (path, is_dir, size) in raw {
+        // Strip leading `./` components that tar archives commonly contain.
+        let path: PathBuf =
+            path.components().filter(|c| !matches!(c, std::path::Component::CurDir)).collect();
+        if path.as_os_str().is_empty() {
+            continue;
+        }
+        for ancestor in path.ancestors().skip(1) {
+            if ancestor.as_os_str().is_empty() {
+                break;
+            }
+            map.entry(ancestor.to_path_buf()).or_insert_with(|| ArchiveEntry {
+                path: ancestor.to_path_buf(),
+                is_dir: true,
+                size: 0,
+                is_virtual: true,
+            });
+        }
+        map.insert(path.clone(), ArchiveEntry { path, is_dir, size, is_virtual: true });
+    }
+    map.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    fn build_zip(files: &[&str]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        for name in files {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(b"data").unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_archive_kind() {
+        assert_eq!(archive_kind(Path::new("a.zip")), Some(ArchiveKind::Zip));
+        assert_eq!(archive_kind(Path::new("lib.JAR")), Some(ArchiveKind::Zip));
+        assert_eq!(archive_kind(Path::new("src.tar")), Some(ArchiveKind::Tar));
+        assert_eq!(archive_kind(Path::new("src.tar.gz")), Some(ArchiveKind::TarGz));
+        assert_eq!(archive_kind(Path::new("src.tgz")), Some(ArchiveKind::TarGz));
+        assert_eq!(archive_kind(Path::new("notes.txt")), None);
+    }
+
+    #[test]
+    fn test_zip_listing_synthesizes_parents() {
+        let bytes = build_zip(&["docs/guide/intro.md", "README.md"]);
+        let entries = normalize_entries(list_zip(Cursor::new(bytes)).unwrap());
+        let paths: Vec<_> = entries.iter().map(|e| (e.path.clone(), e.depth())).collect();
+        assert_eq!(
+            paths,
+            vec![
+                (PathBuf::from("README.md"), 1),
+                (PathBuf::from("docs"), 1),
+                (PathBuf::from("docs/guide"), 2),
+                (PathBuf::from("docs/guide/intro.md"), 3),
+            ]
+        );
+        assert!(entries[1].is_dir && entries[2].is_dir);
+        assert_eq!(entries[3].size, 4);
+        assert!(entries.iter().all(|e| e.is_virtual));
+    }
+
+    #[test]
+    fn test_tar_listing_strips_current_dir() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(3);
+        header.set_cksum();
+        builder.append_data(&mut header, "./pkg/a.txt", &b"abc"[..]).unwrap();
+        let bytes = builder.into_inner().unwrap();
+
+        let entries = normalize_entries(list_tar(Cursor::new(bytes)).unwrap());
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].path, PathBuf::from("pkg"));
+        assert_eq!(entries[1].path, PathBuf::from("pkg/a.txt"));
+        assert_eq!(entries[1].size, 3);
+    }
+}
@@ -0,0 +1,166 @@
+//! Renders a color palette for checking what the terminal can display.
+//!
+//! The palette is built once as backend-independent cells so the classic
+//! output and the TUI show exactly the same content.
+
+use crate::app::ColorTestArgs;
+use crate::view;
+use colored::{Color, Colorize};
+use std::env;
+
+/// The 16 named colors, in ANSI order.
+const NAMED_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+    Color::BrightBlack,
+    Color::BrightRed,
+    Color::BrightGreen,
+    Color::BrightYellow,
+    Color::BrightBlue,
+    Color::BrightMagenta,
+    Color::BrightCyan,
+    Color::BrightWhite,
+];
+
+/// The environment variables terminals use to advertise their capabilities.
+const TERMINAL_VARS: [&str; 3] = ["COLORTERM", "TERM", "TERM_PROGRAM"];
+
+/// The number of steps in the truecolor gradient.
+const GRADIENT_STEPS: usize = 64;
+
+/// The color of a single swatch, in whichever palette it comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Swatch {
+    /// One of the 16 named ANSI colors.
+    Named(Color),
+    /// An index into the xterm-256 palette.
+    Indexed(u8),
+    /// A 24-bit color.
+    Rgb(u8, u8, u8),
+}
+
+/// A piece of a palette line: either plain text or a colored swatch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cell {
+    Text(String),
+    Swatch(Swatch, String),
+}
+
+/// Builds the palette, one `Vec<Cell>` per line.
+///
+/// Swatches are drawn with block characters, or with the letters A-Z when
+/// `ascii` is set so they stay distinguishable without color.
+pub fn palette_lines(ascii: bool) -> Vec<Vec<Cell>> {
+    let glyph = |n: usize| {
+        if ascii {
+            let letter = char::from(b'A' + (n % 26) as u8);
+            format!("{letter}{letter}")
+        } else {
+            "██".to_string()
+        }
+    };
+    let mut lines = Vec::new();
+
+    for var in TERMINAL_VARS {
+        let value = env::var(var).unwrap_or_else(|_| "(unset)".to_string());
+        lines.push(vec![Cell::Text(format!("{var}: {value}"))]);
+    }
+
+    lines.push(Vec::new());
+    lines.push(vec![Cell::Text("16 named colors:".to_string())]);
+    for (row_index, colors) in NAMED_COLORS.chunks(8).enumerate() {
+        let mut line = Vec::new();
+        for (i, color) in colors.iter().enumerate() {
+            line.push(Cell::Swatch(Swatch::Named(*color), glyph(row_index * 8 + i)));
+            line.push(Cell::Text(" ".to_string()));
+        }
+        lines.push(line);
+    }
+
+    lines.push(Vec::new());
+    lines.push(vec![Cell::Text("xterm-256 palette:".to_string())]);
+    for row in 0..16u8 {
+        let mut line = vec![Cell::Text(format!("{:>3} ", row * 16))];
+        for column in 0..16u8 {
+            let index = row * 16 + column;
+            line.push(Cell::Swatch(Swatch::Indexed(index), glyph(index as usize)));
+        }
+        lines.push(line);
+    }
+
+    lines.push(Vec::new());
+    lines.push(vec![Cell::Text("24-bit gradient (red to blue):".to_string())]);
+    let gradient = (0..GRADIENT_STEPS)
+        .map(|step| {
+            let blue = (step * 255 / (GRADIENT_STEPS - 1)) as u8;
+            let glyph = if ascii { char::from(b'A' + (step % 26) as u8) } else { '█' };
+            Cell::Swatch(Swatch::Rgb(255 - blue, 0, blue), glyph.to_string())
+        })
+        .collect();
+    lines.push(gradient);
+
+    lines
+}
+
+/// Paints a palette line with ANSI escape codes.
+pub fn render_line(cells: &[Cell]) -> String {
+    cells
+        .iter()
+        .map(|cell| match cell {
+            Cell::Text(text) => text.clone(),
+            Cell::Swatch(Swatch::Named(color), text) => text.color(*color).to_string(),
+            Cell::Swatch(Swatch::Indexed(index), text) => {
+                view::paint_indexed(text.clone(), Some(*index))
+            }
+            Cell::Swatch(Swatch::Rgb(r, g, b), text) => text.truecolor(*r, *g, *b).to_string(),
+        })
+        .collect()
+}
+
+/// Prints the palette to stdout.
+pub fn run(args: &ColorTestArgs) -> anyhow::Result<()> {
+    for line in palette_lines(args.ascii) {
+        println!("{}", render_line(&line));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swatches(lines: &[Vec<Cell>]) -> Vec<(Swatch, String)> {
+        lines
+            .iter()
+            .flatten()
+            .filter_map(|cell| match cell {
+                Cell::Swatch(swatch, text) => Some((*swatch, text.clone())),
+                Cell::Text(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_palette_lines() {
+        let all = swatches(&palette_lines(false));
+        let named = all.iter().filter(|(s, _)| matches!(s, Swatch::Named(_))).count();
+        let indexed = all.iter().filter(|(s, _)| matches!(s, Swatch::Indexed(_))).count();
+        let gradient: Vec<_> = all.iter().filter(|(s, _)| matches!(s, Swatch::Rgb(..))).collect();
+        assert_eq!(named, 16);
+        assert_eq!(indexed, 256);
+        assert_eq!(gradient.len(), GRADIENT_STEPS);
+        assert_eq!(gradient[0].0, Swatch::Rgb(255, 0, 0));
+        assert_eq!(gradient[GRADIENT_STEPS - 1].0, Swatch::Rgb(0, 0, 255));
+
+        let ascii = swatches(&palette_lines(true));
+        assert!(ascii.iter().all(|(_, text)| text.chars().all(|c| c.is_ascii_uppercase())));
+        assert_eq!(ascii[0].1, "AA");
+        assert_eq!(ascii[15].1, "PP");
+    }
+}
@@ -0,0 +1,676 @@
+//! Loads the optional configuration file and reports where settings come from.
+//!
+//! Settings are resolved with the precedence: built-in default, environment
+//! variable, config file, the `--profile` section of the config file,
+//! command-line flag. A flag wins whenever it was given, even with its default
+//! value, so `--sort name` overrides `sort = "size"` in the file.
+
+use crate::app::{
+    ColorChoice, InteractiveArgs, PrintConfigArgs, PrintConfigFormat, SortType, ViewArgs,
+};
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// A key and its modifiers, as matched against key presses in the interactive mode.
+pub type KeyBinding = (KeyCode, KeyModifiers);
+
+/// Where the effective value of a setting was taken from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    ConfigFile(PathBuf),
+    /// A `[profile.NAME]` section of the config file, selected with `--profile`.
+    Profile(String),
+    EnvVar(String),
+    Cli,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::ConfigFile(path) => write!(f, "from config file {}", path.display()),
+            ConfigSource::Profile(name) => write!(f, "from profile {name}"),
+            ConfigSource::EnvVar(name) => write!(f, "from ${name}"),
+            ConfigSource::Cli => write!(f, "from CLI"),
+        }
+    }
+}
+
+/// The settings of `config.toml`. Keys that are not set keep their defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FstreeConfig {
+    /// The file the settings were read from, if any.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    pub color: Option<ColorChoice>,
+    pub sort: Option<SortType>,
+    pub dirs_first: Option<bool>,
+    pub icons: Option<bool>,
+    pub all: Option<bool>,
+    pub gitignore: Option<bool>,
+    pub git_status: Option<bool>,
+    pub size: Option<bool>,
+    pub permissions: Option<bool>,
+    pub level: Option<usize>,
+    /// Named presets from `[profile.NAME]` sections, selected with `--profile`.
+    #[serde(rename = "profile")]
+    pub profiles: HashMap<String, ProfileConfig>,
+    /// The profile overlaid by `apply_profile`, if any.
+    #[serde(skip)]
+    pub profile: Option<String>,
+    /// Interactive-mode actions remapped in `[keybindings]`, by name, to key descriptions.
+    pub keybindings: HashMap<String, String>,
+}
+
+/// The settings of a `[profile.NAME]` section, overriding the base settings.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ProfileConfig {
+    pub color: Option<ColorChoice>,
+    pub sort: Option<SortType>,
+    pub dirs_first: Option<bool>,
+    pub icons: Option<bool>,
+    pub all: Option<bool>,
+    pub gitignore: Option<bool>,
+    pub git_status: Option<bool>,
+    pub size: Option<bool>,
+    pub permissions: Option<bool>,
+    pub level: Option<usize>,
+}
+
+impl ProfileConfig {
+    /// Returns true if the profile sets the setting with this config-file key.
+    fn sets(&self, key: &str) -> bool {
+        match key {
+            "color" => self.color.is_some(),
+            "sort" => self.sort.is_some(),
+            "dirs-first" => self.dirs_first.is_some(),
+            "icons" => self.icons.is_some(),
+            "all" => self.all.is_some(),
+            "gitignore" => self.gitignore.is_some(),
+            "git-status" => self.git_status.is_some(),
+            "size" => self.size.is_some(),
+            "permissions" => self.permissions.is_some(),
+            "level" => self.level.is_some(),
+            _ => false,
+        }
+    }
+}
+
+impl FstreeConfig {
+    /// Returns `$XDG_CONFIG_HOME/fstree/config.toml`, or `~/.config/fstree/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_home.join("fstree").join("config.toml"))
+    }
+
+    /// Reads and parses a config file.
+    ///
+    /// A missing file, a TOML syntax error and an unknown key are reported
+    /// with distinct messages, each naming the file.
+    pub fn load_from(path: &Path) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(path).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => {
+                anyhow::anyhow!("Config file '{}' not found", path.display())
+            }
+            _ => anyhow::anyhow!("Failed to read '{}': {e}", path.display()),
+        })?;
+        let table: toml::Table = text.parse().map_err(|e| {
+            anyhow::anyhow!("Invalid TOML in config file '{}': {e}", path.display())
+        })?;
+        let mut config = FstreeConfig::deserialize(table).map_err(|e| {
+            if e.message().starts_with("unknown field") {
+                anyhow::anyhow!("Unknown key in config file '{}': {}", path.display(), e.message())
+            } else {
+                anyhow::anyhow!("Invalid config file '{}': {}", path.display(), e.message())
+            }
+        })?;
+        config.path = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    /// Loads the config file at the default location, or an empty config if there is none.
+    pub fn load() -> anyhow::Result<Self> {
+        match Self::default_path() {
+            Some(path) if path.is_file() => Self::load_from(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Loads the config file selected by `--no-config`, `--config` and `--profile`.
+    pub fn load_for(args: &ViewArgs) -> anyhow::Result<Self> {
+        let mut config = if args.no_config {
+            Self::default()
+        } else if let Some(path) = &args.config {
+            Self::load_from(path)?
+        } else {
+            Self::load()?
+        };
+        if let Some(profile) = &args.profile {
+            config.apply_profile(profile)?;
+        }
+        Ok(config)
+    }
+
+    /// Overlays the settings of the named profile on top of the base settings.
+    pub fn apply_profile(&mut self, name: &str) -> anyhow::Result<()> {
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            return Err(match &self.path {
+                Some(path) => {
+                    anyhow::anyhow!("Unknown profile '{name}' in config file '{}'", path.display())
+                }
+                None => anyhow::anyhow!("Unknown profile '{name}': no config file was loaded"),
+            });
+        };
+        self.color = profile.color.or(self.color);
+        self.sort = profile.sort.or(self.sort);
+        self.dirs_first = profile.dirs_first.or(self.dirs_first);
+        self.icons = profile.icons.or(self.icons);
+        self.all = profile.all.or(self.all);
+        self.gitignore = profile.gitignore.or(self.gitignore);
+        self.git_status = profile.git_status.or(self.git_status);
+        self.size = profile.size.or(self.size);
+        self.permissions = profile.permissions.or(self.permissions);
+        self.level = profile.level.or(self.level);
+        self.profile = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Fills in the settings that were not given on the command line.
+    ///
+    /// `matches` must be the matches `args` were parsed from.
+    pub fn apply_to(&self, args: &mut ViewArgs, matches: &ArgMatches) {
+        let given = |id| is_given(matches, id);
+        fill(&mut args.color, self.color, given("color"));
+        fill(&mut args.sort, self.sort, given("sort"));
+        fill(&mut args.dirs_first, self.dirs_first, given("dirs_first"));
+        fill(&mut args.icons, self.icons, given("icons") || given("no_icons"));
+        fill(&mut args.all, self.all, given("all"));
+        fill(&mut args.gitignore, self.gitignore, given("gitignore"));
+        fill(&mut args.git_status, self.git_status, given("git_status"));
+        fill(&mut args.size, self.size, given("size"));
+        fill(&mut args.permissions, self.permissions, given("permissions"));
+        fill(&mut args.level, self.level.map(Some), given("level"));
+    }
+
+    /// Fills in the settings of the interactive mode that were not given on the
+    /// command line. It has no `color` or `level`, so those keys do not apply.
+    ///
+    /// `matches` must be the matches of the `interactive` subcommand.
+    pub fn apply_to_interactive(&self, args: &mut InteractiveArgs, matches: &ArgMatches) {
+        let given = |id| is_given(matches, id);
+        fill(&mut args.sort, self.sort, given("sort"));
+        fill(&mut args.dirs_first, self.dirs_first, given("dirs_first"));
+        fill(&mut args.icons, self.icons, given("icons") || given("no_icons"));
+        fill(&mut args.all, self.all, given("all"));
+        fill(&mut args.gitignore, self.gitignore, given("gitignore"));
+        fill(&mut args.git_status, self.git_status, given("git_status"));
+        fill(&mut args.size, self.size, given("size"));
+        fill(&mut args.permissions, self.permissions, given("permissions"));
+    }
+}
+
+/// Parses a key description such as `j`, `S`, `ctrl+n`, `shift+tab` or `f5`.
+///
+/// Modifier and key names ignore case, but a single character is taken as is,
+/// so `S` is the same key as `shift+s`.
+pub fn parse_key(description: &str) -> anyhow::Result<KeyBinding> {
+    let invalid = || anyhow::anyhow!("Invalid key '{description}'");
+    let mut modifiers = KeyModifiers::NONE;
+    let mut key = description;
+    // A trailing `+` is the key itself, as in `ctrl++`.
+    while let Some((modifier, rest)) = key.split_once('+').filter(|(_, rest)| !rest.is_empty()) {
+        modifiers |= match modifier.to_lowercase().as_str() {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return Err(invalid()),
+        };
+        key = rest;
+    }
+    let mut chars = key.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match key.to_lowercase().as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                Some(n @ 1..=12) => KeyCode::F(n),
+                _ => return Err(invalid()),
+            },
+        },
+    };
+    Ok(normalize_key(code, modifiers))
+}
+
+/// Describes a key the way `parse_key` reads it, such as `ctrl+s` or `space`.
+pub fn describe_key((code, modifiers): KeyBinding) -> String {
+    let mut description = String::new();
+    for (modifier, prefix) in [
+        (KeyModifiers::CONTROL, "ctrl+"),
+        (KeyModifiers::ALT, "alt+"),
+        (KeyModifiers::SHIFT, "shift+"),
+    ] {
+        if modifiers.contains(modifier) {
+            description.push_str(prefix);
+        }
+    }
+    match code {
+        KeyCode::Char(' ') => description.push_str("space"),
+        KeyCode::Char(c) => description.push(c),
+        KeyCode::F(n) => description.push_str(&format!("f{n}")),
+        KeyCode::BackTab => description.push_str("shift+tab"),
+        KeyCode::PageUp => description.push_str("pageup"),
+        KeyCode::PageDown => description.push_str("pagedown"),
+        code => description.push_str(&format!("{code:?}").to_lowercase()),
+    }
+    description
+}
+
+/// Folds Shift into the key where terminals do: `shift+a` arrives as `A` and
+/// `shift+tab` as back-tab, with or without the Shift modifier set.
+pub fn normalize_key(code: KeyCode, modifiers: KeyModifiers) -> KeyBinding {
+    if !modifiers.contains(KeyModifiers::SHIFT) {
+        return (code, modifiers);
+    }
+    match code {
+        KeyCode::Char(c) => {
+            (KeyCode::Char(c.to_ascii_uppercase()), modifiers - KeyModifiers::SHIFT)
+        }
+        KeyCode::Tab | KeyCode::BackTab => (KeyCode::BackTab, modifiers - KeyModifiers::SHIFT),
+        _ => (code, modifiers),
+    }
+}
+
+/// Returns true if the argument with this id was given on the command line.
+fn is_given(matches: &ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+/// Replaces `value` with the config file's setting unless the flag was given.
+fn fill<T>(value: &mut T, configured: Option<T>, given: bool) {
+    if let Some(configured) = configured.filter(|_| !given) {
+        *value = configured;
+    }
+}
+
+/// The effective value of one setting and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigEntry {
+    pub key: &'static str,
+    pub value: String,
+    pub source: ConfigSource,
+}
+
+/// Resolves each configurable setting from the config file and the command line.
+///
+/// `cli_args` must be the arguments as parsed from `matches`, before
+/// `FstreeConfig::apply_to`.
+pub fn merge_config_with_source(
+    file_config: &FstreeConfig,
+    cli_args: &ViewArgs,
+    matches: &ArgMatches,
+) -> Vec<ConfigEntry> {
+    let file_source = ConfigSource::ConfigFile(file_config.path.clone().unwrap_or_default());
+    let profile =
+        file_config.profile.as_ref().and_then(|name| Some((name, file_config.profiles.get(name)?)));
+    // Arguments that were not given still hold their default value.
+    let resolve = |key, given: bool, cli: String, configured: Option<String>| {
+        let (value, source) = if given {
+            (cli, ConfigSource::Cli)
+        } else if let Some(configured) = configured {
+            let source = match profile.filter(|(_, settings)| settings.sets(key)) {
+                Some((name, _)) => ConfigSource::Profile(name.clone()),
+                None => file_source.clone(),
+            };
+            (configured, source)
+        } else {
+            (cli, ConfigSource::Default)
+        };
+        ConfigEntry { key, value, source }
+    };
+    let given = |id| is_given(matches, id);
+    let level = |level: Option<usize>| level.map_or("none".to_string(), |l| l.to_string());
+
+    let mut color = resolve(
+        "color",
+        given("color"),
+        cli_args.color.to_string(),
+        file_config.color.map(|c| c.to_string()),
+    );
+    if color.source == ConfigSource::Default {
+        if let Some((value, var)) = color_from_env() {
+            color.value = value.to_string();
+            color.source = ConfigSource::EnvVar(var.to_string());
+        }
+    }
+
+    vec![
+        color,
+        resolve(
+            "sort",
+            given("sort"),
+            cli_args.sort.to_string(),
+            file_config.sort.map(|s| s.to_string()),
+        ),
+        resolve(
+            "dirs-first",
+            given("dirs_first"),
+            cli_args.dirs_first.to_string(),
+            file_config.dirs_first.map(|b| b.to_string()),
+        ),
+        resolve(
+            "icons",
+            given("icons") || given("no_icons"),
+            cli_args.icons.to_string(),
+            file_config.icons.map(|b| b.to_string()),
+        ),
+        resolve(
+            "all",
+            given("all"),
+            cli_args.all.to_string(),
+            file_config.all.map(|b| b.to_string()),
+        ),
+        resolve(
+            "gitignore",
+            given("gitignore"),
+            cli_args.gitignore.to_string(),
+            file_config.gitignore.map(|b| b.to_string()),
+        ),
+        resolve(
+            "git-status",
+            given("git_status"),
+            cli_args.git_status.to_string(),
+            file_config.git_status.map(|b| b.to_string()),
+        ),
+        resolve(
+            "size",
+            given("size"),
+            cli_args.size.to_string(),
+            file_config.size.map(|b| b.to_string()),
+        ),
+        resolve(
+            "permissions",
+            given("permissions"),
+            cli_args.permissions.to_string(),
+            file_config.permissions.map(|b| b.to_string()),
+        ),
+        resolve(
+            "level",
+            given("level"),
+            level(cli_args.level),
+            file_config.level.map(|l| level(Some(l))),
+        ),
+    ]
+}
+
+/// Returns the color choice implied by `NO_COLOR` or `CLICOLOR_FORCE`, if set.
+fn color_from_env() -> Option<(ColorChoice, &'static str)> {
+    let is_set = |var| env::var_os(var).is_some_and(|value| !value.is_empty() && value != "0");
+    if is_set("NO_COLOR") {
+        Some((ColorChoice::Never, "NO_COLOR"))
+    } else if is_set("CLICOLOR_FORCE") {
+        Some((ColorChoice::Always, "CLICOLOR_FORCE"))
+    } else {
+        None
+    }
+}
+
+/// Prints the resolved settings for the `print-config` command.
+pub fn run(
+    args: &PrintConfigArgs,
+    file_config: &FstreeConfig,
+    cli_args: &ViewArgs,
+    matches: &ArgMatches,
+) -> anyhow::Result<()> {
+    let entries = merge_config_with_source(file_config, cli_args, matches);
+    let config_path = file_config.path.clone().or_else(FstreeConfig::default_path);
+    let mut stdout = io::stdout().lock();
+
+    match args.format {
+        PrintConfigFormat::Text => {
+            let path_note = match (&file_config.path, &config_path) {
+                (Some(path), _) => path.display().to_string(),
+                (None, Some(path)) => format!("{} (not found)", path.display()),
+                (None, None) => "(none)".to_string(),
+            };
+            _ = writeln!(stdout, "config file: {path_note}");
+            let key_width = entries.iter().map(|e| e.key.len()).max().unwrap_or(0);
+            for entry in &entries {
+                let source = match &entry.source {
+                    ConfigSource::Default => "default".to_string(),
+                    ConfigSource::ConfigFile(_) => "from config file".to_string(),
+                    source => source.to_string(),
+                };
+                _ = writeln!(
+                    stdout,
+                    "{:<width$} {} ({source})",
+                    format!("{}:", entry.key),
+                    entry.value,
+                    width = key_width + 1
+                );
+            }
+        }
+        PrintConfigFormat::Json => {
+            let settings: Vec<serde_json::Value> = entries
+                .iter()
+                .map(|entry| {
+                    let (source, detail) = match &entry.source {
+                        ConfigSource::Default => ("default", None),
+                        ConfigSource::ConfigFile(path) => {
+                            ("config-file", Some(path.display().to_string()))
+                        }
+                        ConfigSource::Profile(name) => ("profile", Some(name.clone())),
+                        ConfigSource::EnvVar(name) => ("env", Some(name.clone())),
+                        ConfigSource::Cli => ("cli", None),
+                    };
+                    serde_json::json!({
+                        "key": entry.key,
+                        "value": entry.value,
+                        "source": source,
+                        "detail": detail,
+                    })
+                })
+                .collect();
+            let report =
+                serde_json::json!({ "config_file": file_config.path, "settings": settings });
+            _ = writeln!(stdout, "{}", serde_json::to_string_pretty(&report)?);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches};
+
+    /// Parses `argv` like `main` does, keeping the matches for `apply_to`.
+    fn parse(argv: &[&str]) -> (ViewArgs, ArgMatches) {
+        let matches = ViewArgs::command().get_matches_from(argv);
+        (ViewArgs::from_arg_matches(&matches).unwrap(), matches)
+    }
+
+    fn value_of<'a>(entries: &'a [ConfigEntry], key: &str) -> &'a ConfigEntry {
+        entries.iter().find(|e| e.key == key).unwrap()
+    }
+
+    #[test]
+    fn test_merge_config_with_source() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "sort = \"size\"\nicons = true\nlevel = 2\n").unwrap();
+        let file_config = FstreeConfig::load_from(&path).unwrap();
+        assert_eq!(file_config.sort, Some(SortType::Size));
+
+        // The CLI asks for a different sort than the config file.
+        let (cli_args, matches) = parse(&["fstree", "--sort", "extension", "-L", "3"]);
+        let entries = merge_config_with_source(&file_config, &cli_args, &matches);
+
+        let sort = value_of(&entries, "sort");
+        assert_eq!((sort.value.as_str(), &sort.source), ("extension", &ConfigSource::Cli));
+        let level = value_of(&entries, "level");
+        assert_eq!((level.value.as_str(), &level.source), ("3", &ConfigSource::Cli));
+        let icons = value_of(&entries, "icons");
+        assert_eq!(
+            (icons.value.as_str(), &icons.source),
+            ("true", &ConfigSource::ConfigFile(path))
+        );
+        let size = value_of(&entries, "size");
+        assert_eq!((size.value.as_str(), &size.source), ("false", &ConfigSource::Default));
+
+        let mut applied = cli_args;
+        file_config.apply_to(&mut applied, &matches);
+        assert_eq!(applied.sort, SortType::Extension);
+        assert_eq!(applied.level, Some(3));
+        assert!(applied.icons);
+
+        // A flag given with its default value still wins over the config file.
+        let (mut cli_args, matches) = parse(&["fstree", "--sort", "name", "--no-icons"]);
+        let entries = merge_config_with_source(&file_config, &cli_args, &matches);
+        let sort = value_of(&entries, "sort");
+        assert_eq!((sort.value.as_str(), &sort.source), ("name", &ConfigSource::Cli));
+        let icons = value_of(&entries, "icons");
+        assert_eq!((icons.value.as_str(), &icons.source), ("false", &ConfigSource::Cli));
+        file_config.apply_to(&mut cli_args, &matches);
+        assert_eq!(
+            (cli_args.sort, cli_args.icons, cli_args.level),
+            (SortType::Name, false, Some(2))
+        );
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config: FstreeConfig =
+            toml::from_str("color = \"never\"\nicons = true\ndirs-first = false\n").unwrap();
+        assert_eq!(config.color, Some(ColorChoice::Never));
+        assert_eq!(config.icons, Some(true));
+        assert_eq!(config.dirs_first, Some(false));
+        assert_eq!(config.sort, None);
+        assert_eq!(config.path, None);
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "sort = \"size\"\nicons = true\n\n\
+             [profile.git]\ngit-status = true\ngitignore = true\n\n\
+             [profile.plain]\nsort = \"name\"\nicons = false\n",
+        )
+        .unwrap();
+        let file_config = FstreeConfig::load_from(&path).unwrap();
+        assert_eq!(file_config.profiles.len(), 2);
+
+        // Settings missing from the profile keep the base values.
+        let mut git = FstreeConfig::load_from(&path).unwrap();
+        git.apply_profile("git").unwrap();
+        assert_eq!(git.sort, Some(SortType::Size));
+        assert_eq!(
+            (git.icons, git.git_status, git.gitignore),
+            (Some(true), Some(true), Some(true))
+        );
+
+        let (cli_args, matches) = parse(&["fstree"]);
+        let entries = merge_config_with_source(&git, &cli_args, &matches);
+        assert_eq!(value_of(&entries, "git-status").source, ConfigSource::Profile("git".into()));
+        assert_eq!(value_of(&entries, "sort").source, ConfigSource::ConfigFile(path.clone()));
+
+        let mut plain = FstreeConfig::load_from(&path).unwrap();
+        plain.apply_profile("plain").unwrap();
+        assert_eq!((plain.sort, plain.icons), (Some(SortType::Name), Some(false)));
+
+        // Flags on the command line still win over the profile.
+        let (mut args, matches) = parse(&["fstree", "--sort", "extension"]);
+        plain.apply_to(&mut args, &matches);
+        assert_eq!(args.sort, SortType::Extension);
+        assert!(!args.icons);
+
+        // The interactive mode takes the same settings from its own flags.
+        let matches = InteractiveArgs::command().get_matches_from(["interactive", "--no-icons"]);
+        let mut interactive = InteractiveArgs::from_arg_matches(&matches).unwrap();
+        git.apply_to_interactive(&mut interactive, &matches);
+        assert_eq!(interactive.sort, SortType::Size);
+        assert!(interactive.git_status && interactive.gitignore && !interactive.icons);
+
+        let err = plain.apply_profile("missing").unwrap_err().to_string();
+        assert!(err.contains("'missing'") && err.contains("config.toml"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("j").unwrap(), (KeyCode::Char('j'), KeyModifiers::NONE));
+        assert_eq!(parse_key("S").unwrap(), (KeyCode::Char('S'), KeyModifiers::NONE));
+        assert_eq!(parse_key("shift+s").unwrap(), parse_key("S").unwrap());
+        assert_eq!(parse_key("Ctrl+n").unwrap(), (KeyCode::Char('n'), KeyModifiers::CONTROL));
+        assert_eq!(
+            parse_key("ctrl+alt+Enter").unwrap(),
+            (KeyCode::Enter, KeyModifiers::CONTROL | KeyModifiers::ALT)
+        );
+        assert_eq!(parse_key("shift+tab").unwrap(), (KeyCode::BackTab, KeyModifiers::NONE));
+        assert_eq!(parse_key("F5").unwrap(), (KeyCode::F(5), KeyModifiers::NONE));
+        assert_eq!(parse_key("space").unwrap(), (KeyCode::Char(' '), KeyModifiers::NONE));
+        assert_eq!(parse_key("ctrl++").unwrap(), (KeyCode::Char('+'), KeyModifiers::CONTROL));
+        assert_eq!(parse_key("+").unwrap(), (KeyCode::Char('+'), KeyModifiers::NONE));
+
+        for description in ["j", "S", "ctrl+n", "ctrl+alt+enter", "shift+tab", "f5", "space", "esc"]
+        {
+            assert_eq!(describe_key(parse_key(description).unwrap()), description);
+        }
+
+        for invalid in ["", "f13", "hyper+j", "ctrl+", "jk"] {
+            let err = parse_key(invalid).unwrap_err().to_string();
+            assert_eq!(err, format!("Invalid key '{invalid}'"));
+        }
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "sort = \"sideways\"\n").unwrap();
+        let err = FstreeConfig::load_from(&path).unwrap_err().to_string();
+        assert!(err.contains("config.toml"), "{err}");
+
+        fs::write(&path, "colour = \"never\"\n").unwrap();
+        let err = FstreeConfig::load_from(&path).unwrap_err().to_string();
+        assert!(err.starts_with("Unknown key in config file"), "{err}");
+        assert!(err.contains("colour"), "{err}");
+
+        fs::write(&path, "sort = \n").unwrap();
+        let err = FstreeConfig::load_from(&path).unwrap_err().to_string();
+        assert!(err.starts_with("Invalid TOML in config file"), "{err}");
+
+        let missing = temp_dir.path().join("missing.toml");
+        let err = FstreeConfig::load_from(&missing).unwrap_err().to_string();
+        assert!(err.starts_with("Config file") && err.ends_with("not found"), "{err}");
+    }
+}
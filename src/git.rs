@@ -4,6 +4,9 @@
 //! and provide a simplified representation of those statuses for display.
 
 use git2::Repository;
+use ignore::gitignore::GitignoreBuilder;
+use ignore::{DirEntry, WalkBuilder};
+use rayon::prelude::*;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
@@ -17,6 +20,8 @@ pub enum FileStatus {
     Typechange,
     Untracked,
     Conflicted,
+    /// The root directory of a submodule.
+    Submodule,
 }
 
 impl FileStatus {
@@ -30,6 +35,21 @@ impl FileStatus {
             Self::Typechange => 'T',
             Self::Untracked => '?',
             Self::Conflicted => 'C',
+            Self::Submodule => 'S',
+        }
+    }
+
+    /// Returns the lowercase name of the status, as used by `--format`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Modified => "modified",
+            Self::New => "new",
+            Self::Deleted => "deleted",
+            Self::Renamed => "renamed",
+            Self::Typechange => "typechange",
+            Self::Untracked => "untracked",
+            Self::Conflicted => "conflicted",
+            Self::Submodule => "submodule",
         }
     }
 }
@@ -37,11 +57,163 @@ impl FileStatus {
 /// A cache mapping file paths to their Git status.
 pub type StatusCache = HashMap<PathBuf, FileStatus>;
 
+/// The maximum number of commits counted per file. Files with a longer
+/// history are reported as `999+` instead of walking the whole history.
+pub const COMMIT_COUNT_CAP: usize = 999;
+
+/// The width of the diff stat column, excluding its trailing separator.
+pub const DIFF_STAT_WIDTH: usize = 10;
+
+/// The maximum length of a diff stat bar.
+pub const DIFF_STAT_BAR_WIDTH: usize = 8;
+
+/// The number of lines added and removed in a file compared to `HEAD`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffStat {
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl DiffStat {
+    /// Returns the total number of changed lines.
+    pub fn total(&self) -> usize {
+        self.insertions + self.deletions
+    }
+
+    /// Renders the insertion and deletion parts separately so they can be colored.
+    ///
+    /// Without a scale, the parts are counts such as `+12` and `-3`. With a scale
+    /// (the largest total in the tree), they form a `++++---` bar whose length is
+    /// proportional to the total, capped at `DIFF_STAT_BAR_WIDTH`.
+    pub fn parts(&self, bar_scale: Option<usize>) -> (String, String) {
+        let Some(scale) = bar_scale else {
+            return (format!("+{}", self.insertions), format!("-{}", self.deletions));
+        };
+        let total = self.total();
+        if total == 0 || scale == 0 {
+            return (String::new(), String::new());
+        }
+        let width = (total * DIFF_STAT_BAR_WIDTH).div_ceil(scale).clamp(1, DIFF_STAT_BAR_WIDTH);
+        // Keep at least one cell for each non-empty side when there is room.
+        let plus = ((self.insertions * width + total / 2) / total)
+            .max(usize::from(self.insertions > 0))
+            .min(width - usize::from(self.deletions > 0 && width > 1));
+        ("+".repeat(plus), "-".repeat(width - plus))
+    }
+}
+
+/// How `--git-tag` displays the tag describing a file's last commit.
+#[derive(clap::ValueEnum, Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum GitTagFormat {
+    /// Only the tag name, e.g. `v1.2.3`
+    #[default]
+    Name,
+    /// The tag with the distance and commit when not on the tag, e.g. `v1.2.3-5-gabcdef1`
+    Describe,
+}
+
 /// Contains the status cache and the root path of the repository.
 #[derive(Clone)]
 pub struct GitRepoStatus {
     pub cache: StatusCache,
     pub root: PathBuf,
+    /// Number of commits touching each file, keyed by path relative to the root.
+    /// Only populated by `load_commit_counts`.
+    pub commit_counts: HashMap<PathBuf, usize>,
+    /// Lines changed in each file since `HEAD`, keyed by path relative to the root.
+    /// Only populated by `load_diff_stats`.
+    pub diff_stats: HashMap<PathBuf, DiffStat>,
+    /// The most recent tag reachable from each file's last commit, keyed by path
+    /// relative to the root. Only populated by `load_file_tags`.
+    pub file_tags: HashMap<PathBuf, String>,
+}
+
+impl GitRepoStatus {
+    /// Looks up the status of a path below the repository, resolving it first
+    /// since the cache is keyed relative to the canonical root.
+    pub fn status_of(&self, path: &Path) -> Option<FileStatus> {
+        let canonical = path.canonicalize().ok()?;
+        self.cache.get(canonical.strip_prefix(&self.root).ok()?).copied()
+    }
+
+    /// Counts the commits reachable from `HEAD` that touched each of the given paths.
+    ///
+    /// Paths must be relative to the repository root. Counting stops once a file
+    /// exceeds `COMMIT_COUNT_CAP`. The per-file history walks run in parallel, each
+    /// worker opening its own handle since `git2::Repository` is not `Sync`.
+    pub fn load_commit_counts(&mut self, paths: &[PathBuf]) {
+        let root = &self.root;
+        let counts: Vec<(PathBuf, usize)> = paths
+            .par_iter()
+            .map_init(
+                || Repository::open(root).ok(),
+                |repo, path| {
+                    let count = repo
+                        .as_ref()
+                        .and_then(|repo| count_commits_for_path(repo, path).ok())
+                        .unwrap_or(0);
+                    (path.clone(), count)
+                },
+            )
+            .collect();
+        self.commit_counts.extend(counts);
+    }
+
+    /// Finds the most recent tag reachable from the last commit touching each path.
+    ///
+    /// Paths must be relative to the repository root. Files that were never
+    /// committed, or whose last commit has no tag in its history, get no entry.
+    /// Like `load_commit_counts`, the history walks run in parallel.
+    pub fn load_file_tags(&mut self, paths: &[PathBuf], format: GitTagFormat) {
+        let root = &self.root;
+        let tags: Vec<(PathBuf, String)> = paths
+            .par_iter()
+            .map_init(
+                || Repository::open(root).ok(),
+                |repo, path| {
+                    let tag = repo.as_ref().and_then(|repo| describe_path(repo, path, format));
+                    Some((path.clone(), tag?))
+                },
+            )
+            .flatten()
+            .collect();
+        self.file_tags.extend(tags);
+    }
+
+    /// Computes the lines added and removed in each changed file, comparing `HEAD`
+    /// to the working tree (including staged changes and untracked files).
+    ///
+    /// Binary files are skipped since they have no line counts.
+    pub fn load_diff_stats(&mut self) -> anyhow::Result<()> {
+        let repo = Repository::open(&self.root)?;
+        // An unborn `HEAD` (no commits yet) diffs against the empty tree.
+        let head_tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+        let mut opts = git2::DiffOptions::new();
+        opts.include_untracked(true).recurse_untracked_dirs(true).show_untracked_content(true);
+        let diff = repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut opts))?;
+
+        for (index, delta) in diff.deltas().enumerate() {
+            let Some(path) = delta.new_file().path().or_else(|| delta.old_file().path()) else {
+                continue;
+            };
+            let path = path.to_path_buf();
+            let Some(patch) = git2::Patch::from_diff(&diff, index)? else {
+                continue;
+            };
+            let (_, insertions, deletions) = patch.line_stats()?;
+            self.diff_stats.insert(path, DiffStat { insertions, deletions });
+        }
+        Ok(())
+    }
+}
+
+/// Formats a commit count for display, e.g. `42c`, or `999+` above the cap.
+pub fn format_commit_count(count: usize) -> String {
+    if count > COMMIT_COUNT_CAP {
+        format!("{COMMIT_COUNT_CAP}+")
+    } else {
+        format!("{count}c")
+    }
 }
 
 /// Discovers a Git repository from a starting path, scans for file statuses,
@@ -75,8 +247,154 @@ pub fn load_status(start_path: &Path) -> anyhow::Result<Option<GitRepoStatus>> {
         }
     }
 
+    // git2 has no status bit for submodules, so their roots are looked up separately.
+    // This replaces the `M` a submodule with new commits or changes would get.
+    if let Ok(submodules) = repo.submodules() {
+        for submodule in submodules {
+            cache.insert(submodule.path().to_path_buf(), FileStatus::Submodule);
+        }
+    }
+
     // Return the CANONICALIZED workdir path as the root.
-    Ok(Some(GitRepoStatus { cache, root: workdir.canonicalize()? }))
+    Ok(Some(GitRepoStatus {
+        cache,
+        root: workdir.canonicalize()?,
+        commit_counts: HashMap::new(),
+        diff_stats: HashMap::new(),
+        file_tags: HashMap::new(),
+    }))
+}
+
+/// Returns the branch checked out in the repository containing `path`.
+///
+/// With a detached `HEAD`, the short hash of the commit is returned instead.
+/// Returns `None` outside a repository.
+pub fn get_branch_name(path: &Path) -> Option<String> {
+    let repo = Repository::discover(path).ok()?;
+    let head = match repo.head() {
+        Ok(head) => head,
+        // A branch without commits yet has no `HEAD` commit, only its name.
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
+            let head = repo.find_reference("HEAD").ok()?;
+            let target = head.symbolic_target()?;
+            return Some(target.strip_prefix("refs/heads/").unwrap_or(target).to_string());
+        }
+        Err(_) => return None,
+    };
+    if repo.head_detached().unwrap_or(false) {
+        let commit = head.peel_to_commit().ok()?;
+        return commit.as_object().short_id().ok()?.as_str().map(str::to_string);
+    }
+    head.shorthand().map(str::to_string)
+}
+
+/// Checks which entries a single `.gitignore` pattern would hide.
+///
+/// The pattern is interpreted as if it were a line in a `.gitignore` file at
+/// `root`. Entries inside a matching directory are flagged as well, since Git
+/// would hide them too. The result is aligned with `entries`.
+pub fn test_gitignore_pattern(
+    entries: &[DirEntry],
+    pattern: &str,
+    root: &Path,
+) -> anyhow::Result<Vec<bool>> {
+    let mut builder = GitignoreBuilder::new(root);
+    builder.add_line(None, pattern)?;
+    let gitignore = builder.build()?;
+
+    Ok(entries
+        .iter()
+        .map(|entry| {
+            let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+            gitignore.matched_path_or_any_parents(entry.path(), is_dir).is_ignore()
+        })
+        .collect())
+}
+
+/// Counts the immediate children of `dir` hidden by `.gitignore` rules.
+///
+/// Lists the directory once with and once without ignore rules, so nested
+/// `.gitignore` files and global excludes are honored exactly as in the main
+/// walk. Hidden entries are skipped in both listings unless `include_hidden`.
+pub fn count_ignored_children(dir: &Path, include_hidden: bool) -> usize {
+    let count_children = |git_ignore: bool| {
+        WalkBuilder::new(dir)
+            .max_depth(Some(1))
+            .hidden(!include_hidden)
+            .git_ignore(git_ignore)
+            .build()
+            .flatten()
+            .filter(|entry| entry.depth() == 1)
+            .count()
+    };
+    count_children(false).saturating_sub(count_children(true))
+}
+
+/// Returns `path` relative to the repository `root`, resolving symlinks first.
+pub fn relative_to_root(path: &Path, root: &Path) -> Option<PathBuf> {
+    let canonical = path.canonicalize().ok()?;
+    canonical.strip_prefix(root).ok().map(Path::to_path_buf)
+}
+
+/// Walks the history from `HEAD` and counts the commits that changed `path`.
+///
+/// A commit counts as touching the file when the blob at `path` differs from the
+/// one in its first parent (or the file first appears in it).
+fn count_commits_for_path(repo: &Repository, path: &Path) -> Result<usize, git2::Error> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+
+    let mut count = 0;
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        let Ok(current) = commit.tree()?.get_path(path) else {
+            continue;
+        };
+        let parent_id = match commit.parent(0) {
+            Ok(parent) => parent.tree()?.get_path(path).ok().map(|e| e.id()),
+            Err(_) => None,
+        };
+        if parent_id != Some(current.id()) {
+            count += 1;
+            if count > COMMIT_COUNT_CAP {
+                break;
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// Returns the last commit reachable from `HEAD` that changed `path`.
+fn last_commit_for_path<'r>(repo: &'r Repository, path: &Path) -> Option<git2::Commit<'r>> {
+    let mut revwalk = repo.revwalk().ok()?;
+    revwalk.push_head().ok()?;
+    for oid in revwalk {
+        let commit = repo.find_commit(oid.ok()?).ok()?;
+        let Ok(current) = commit.tree().ok()?.get_path(path) else {
+            continue;
+        };
+        let parent_id = match commit.parent(0) {
+            Ok(parent) => parent.tree().ok()?.get_path(path).ok().map(|e| e.id()),
+            Err(_) => None,
+        };
+        if parent_id != Some(current.id()) {
+            return Some(commit);
+        }
+    }
+    None
+}
+
+/// Describes the last commit touching `path` with the nearest tag, as `git describe --tags`.
+fn describe_path(repo: &Repository, path: &Path, format: GitTagFormat) -> Option<String> {
+    let commit = last_commit_for_path(repo, path)?;
+    let describe =
+        commit.as_object().describe(git2::DescribeOptions::new().describe_tags()).ok()?;
+    let mut options = git2::DescribeFormatOptions::new();
+    if format == GitTagFormat::Name {
+        // An abbreviation size of zero drops the distance and commit suffix.
+        options.abbreviated_size(0);
+    }
+    describe.format(Some(&options)).ok()
 }
 
 /// Converts a `git2::Status` bitflag into our simplified `FileStatus` enum.
@@ -116,3 +434,22 @@ fn git_to_file_status(s: git2::Status) -> Option<FileStatus> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_stat_parts() {
+        let stat = DiffStat { insertions: 12, deletions: 3 };
+        assert_eq!(stat.parts(None), ("+12".to_string(), "-3".to_string()));
+
+        // The largest change in the tree fills the whole bar.
+        assert_eq!(stat.parts(Some(15)), ("++++++".to_string(), "--".to_string()));
+        // Smaller changes get a proportionally shorter bar, but never an empty one.
+        assert_eq!(stat.parts(Some(60)), ("+".to_string(), "-".to_string()));
+        let added = DiffStat { insertions: 1, deletions: 0 };
+        assert_eq!(added.parts(Some(1000)), ("+".to_string(), String::new()));
+        assert_eq!(DiffStat::default().parts(Some(10)), (String::new(), String::new()));
+    }
+}
@@ -22,6 +22,9 @@ use std::path::Path;
 /// * `String` - The Nerd Font icon character.
 /// * `Color` - The `colored::Color` to use for displaying the icon.
 pub fn get_icon_for_path(path: &Path, is_dir: bool) -> (String, Color) {
+    if is_dir && path.is_symlink() {
+        return ("".to_string(), Color::Cyan); // Symlinked folder icon
+    }
     if is_dir {
         return ("".to_string(), Color::Blue); // Folder icon
     }
@@ -64,6 +67,38 @@ pub fn get_icon_for_path(path: &Path, is_dir: bool) -> (String, Color) {
     (icon.to_string(), color)
 }
 
+/// Decides whether an entry gets an icon once `--icons` is enabled.
+///
+/// # Arguments
+///
+/// * `path` - The path of the entry.
+/// * `is_dir` - Whether the entry is a directory.
+/// * `no_dirs` - Skip icons for directories (`--no-icons-for-dirs`).
+/// * `no_files` - Skip icons for files (`--no-icons-for-files`).
+/// * `only_for_ext` - If not empty, only files with one of these extensions get an
+///   icon (`--icons-only-for-ext`). Extensions match case-insensitively, with or
+///   without a leading dot.
+pub fn shows_icon(
+    path: &Path,
+    is_dir: bool,
+    no_dirs: bool,
+    no_files: bool,
+    only_for_ext: &[String],
+) -> bool {
+    if is_dir {
+        return !no_dirs && only_for_ext.is_empty();
+    }
+    if no_files {
+        return false;
+    }
+    only_for_ext.is_empty()
+        || path.extension().and_then(|e| e.to_str()).is_some_and(|ext| {
+            only_for_ext
+                .iter()
+                .any(|wanted| wanted.trim_start_matches('.').eq_ignore_ascii_case(ext))
+        })
+}
+
 // Unit tests for the icon logic
 #[cfg(test)]
 mod tests {
@@ -101,4 +136,20 @@ mod tests {
         assert_eq!(icon, "");
         assert_eq!(color, Color::White);
     }
+
+    #[test]
+    fn test_shows_icon() {
+        let dir = Path::new("src");
+        let file = Path::new("main.RS");
+        assert!(shows_icon(dir, true, false, false, &[]));
+        assert!(!shows_icon(dir, true, true, false, &[]));
+        assert!(shows_icon(file, false, true, false, &[]));
+        assert!(!shows_icon(file, false, false, true, &[]));
+        assert!(shows_icon(dir, true, false, true, &[]));
+
+        let only_rs = vec![".rs".to_string()];
+        assert!(shows_icon(file, false, false, false, &only_rs));
+        assert!(!shows_icon(Path::new("README.md"), false, false, false, &only_rs));
+        assert!(!shows_icon(dir, true, false, false, &only_rs));
+    }
 }
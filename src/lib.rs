@@ -0,0 +1,11 @@
+//! fstree as a library: the directory scanning, sorting, Git status and icon
+//! lookups behind the `fstree` command.
+//!
+//! The [`scanner`] module walks a tree the way `fstree` prints it; the other
+//! modules are the building blocks it shares with the command-line tool.
+
+pub mod git;
+pub mod icons;
+pub mod scanner;
+pub mod sort;
+pub mod utils;
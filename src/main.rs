@@ -5,17 +5,20 @@
 
 // Declare the modules that make up the application.
 mod app;
-mod git;
-mod icons;
-mod sort;
+mod archive;
+mod colortest;
+mod config;
+mod output;
+mod stats;
 mod tui;
-mod utils;
 mod view;
 
 use app::{Args, Commands};
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 #[cfg(windows)]
 use colored::control;
+// The scanning building blocks live in the library crate, shared with embedders.
+use fstree::{git, icons, sort, utils};
 use lscolors::LsColors;
 
 /// The main function and entry point of the application.
@@ -35,14 +38,35 @@ fn main() -> anyhow::Result<()> {
     let _ = control::set_virtual_terminal(true);
 
     // Parse the command-line arguments into our Args struct.
-    let args = Args::parse();
+    // The matches are kept to tell flags given on the command line from defaults.
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
     // Create the LsColors instance from the environment
     let ls_colors = LsColors::from_env().unwrap_or_default();
 
     // Check if a subcommand was passed. If not, default to the `view` command.
-    match &args.command {
-        Some(Commands::Interactive(interactive_args)) => tui::run(interactive_args, &ls_colors),
-        None => view::run(&args.view, &ls_colors),
+    // Only the commands that use the config file read it, so a broken file
+    // does not get in the way of the others.
+    match &mut args.command {
+        Some(Commands::Interactive(interactive_args)) => {
+            let file_config = config::FstreeConfig::load_for(&args.view)?;
+            if let Some(interactive_matches) = matches.subcommand_matches("interactive") {
+                file_config.apply_to_interactive(interactive_args, interactive_matches);
+            }
+            tui::run(interactive_args, &ls_colors, &file_config.keybindings)
+        }
+        Some(Commands::LargeDirs(large_dirs_args)) => stats::run_large_dirs(large_dirs_args),
+        Some(Commands::ListExtensions(list_ext_args)) => stats::run_list_extensions(list_ext_args),
+        Some(Commands::ColorTest(color_test_args)) => colortest::run(color_test_args),
+        Some(Commands::PrintConfig(print_config_args)) => {
+            let file_config = config::FstreeConfig::load_for(&args.view)?;
+            config::run(print_config_args, &file_config, &args.view, &matches)
+        }
+        None => {
+            let file_config = config::FstreeConfig::load_for(&args.view)?;
+            file_config.apply_to(&mut args.view, &matches);
+            view::run(&args.view, &ls_colors)
+        }
     }
 }
@@ -0,0 +1,77 @@
+//! Writes the tree as CSV, one row per entry, for spreadsheets and scripts.
+
+use super::TreeEntry;
+use std::io::Write;
+use std::path::Path;
+
+/// The header row, which is always written.
+const HEADER: &str = "path,depth,is_dir,size_bytes,permissions,git_status,extension";
+
+/// Quotes a field if it contains a separator, a quote or a line break.
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes the header and one row per entry; missing values are left empty.
+pub fn write(out: &mut dyn Write, entries: &[TreeEntry]) -> anyhow::Result<()> {
+    writeln!(out, "{HEADER}")?;
+    for entry in entries {
+        let extension = if entry.is_dir {
+            None
+        } else {
+            Path::new(&entry.path).extension().map(|ext| ext.to_string_lossy())
+        };
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            field(&entry.path),
+            entry.depth,
+            entry.is_dir,
+            entry.size.map(|size| size.to_string()).unwrap_or_default(),
+            entry.permissions.as_deref().unwrap_or_default(),
+            entry.git_status.as_deref().unwrap_or_default(),
+            field(extension.as_deref().unwrap_or_default()),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_csv() {
+        let entries = [
+            TreeEntry {
+                path: "docs".to_string(),
+                depth: 1,
+                is_dir: true,
+                size: None,
+                permissions: None,
+                git_status: None,
+            },
+            TreeEntry {
+                path: "docs/a \"b\", c.tar.gz".to_string(),
+                depth: 2,
+                is_dir: false,
+                size: Some(7),
+                permissions: Some("-rw-r--r--".to_string()),
+                git_status: Some("new".to_string()),
+            },
+        ];
+        let mut buffer = Vec::new();
+        write(&mut buffer, &entries).unwrap();
+
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "path,depth,is_dir,size_bytes,permissions,git_status,extension\n\
+             docs,1,true,,,,\n\
+             \"docs/a \"\"b\"\", c.tar.gz\",2,false,7,-rw-r--r--,new,gz\n"
+        );
+    }
+}
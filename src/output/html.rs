@@ -0,0 +1,240 @@
+//! Builds a self-contained HTML page of the tree for `--format html`.
+//!
+//! Colors from `LS_COLORS` and the icon colors are converted to CSS, including
+//! the xterm-256 palette indexes and RGB values that terminals resolve themselves.
+
+use super::Summary;
+use crate::utils;
+use lscolors::Color as LsColor;
+use std::fmt::Write as _;
+
+/// The xterm RGB values of the 16 named colors, in ANSI order.
+const ANSI16_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Fonts tried in order; Nerd Fonts first so the icons render.
+const FONT_STACK: &str = "\"JetBrainsMono Nerd Font\", \"FiraCode Nerd Font\", \
+                          \"Hack Nerd Font\", Menlo, Consolas, \"DejaVu Sans Mono\", monospace";
+
+/// One line of the HTML tree.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HtmlEntry {
+    pub depth: usize,
+    pub name: String,
+    /// CSS color of the name, from `LS_COLORS`.
+    pub color: Option<String>,
+    pub bold: bool,
+    /// The icon and its CSS color, with `--icons`.
+    pub icon: Option<(String, String)>,
+    /// Link target of the name, with `--hyperlinks`.
+    pub href: Option<String>,
+    /// Size shown after the name, with `--size`.
+    pub size: Option<u64>,
+}
+
+/// Returns the RGB value of an xterm-256 palette index.
+pub fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI16_RGB[index as usize],
+        16..=231 => {
+            let cube = index - 16;
+            let level = |i: u8| utils::CUBE_LEVELS[i as usize];
+            (level(cube / 36), level(cube / 6 % 6), level(cube % 6))
+        }
+        _ => {
+            let gray = 8 + 10 * (index - 232);
+            (gray, gray, gray)
+        }
+    }
+}
+
+/// Formats an RGB value as a CSS hex color.
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Converts a color from `LS_COLORS` to CSS.
+pub fn ls_color_css(color: &LsColor) -> String {
+    let rgb = match *color {
+        LsColor::Fixed(index) => indexed_rgb(index),
+        LsColor::RGB(r, g, b) => (r, g, b),
+        LsColor::Black => ANSI16_RGB[0],
+        LsColor::Red => ANSI16_RGB[1],
+        LsColor::Green => ANSI16_RGB[2],
+        LsColor::Yellow => ANSI16_RGB[3],
+        LsColor::Blue => ANSI16_RGB[4],
+        LsColor::Magenta => ANSI16_RGB[5],
+        LsColor::Cyan => ANSI16_RGB[6],
+        LsColor::White => ANSI16_RGB[7],
+        LsColor::BrightBlack => ANSI16_RGB[8],
+        LsColor::BrightRed => ANSI16_RGB[9],
+        LsColor::BrightGreen => ANSI16_RGB[10],
+        LsColor::BrightYellow => ANSI16_RGB[11],
+        LsColor::BrightBlue => ANSI16_RGB[12],
+        LsColor::BrightMagenta => ANSI16_RGB[13],
+        LsColor::BrightCyan => ANSI16_RGB[14],
+        LsColor::BrightWhite => ANSI16_RGB[15],
+    };
+    hex(rgb)
+}
+
+/// Converts an icon color to CSS.
+pub fn colored_css(color: colored::Color) -> String {
+    let rgb = match color {
+        colored::Color::TrueColor { r, g, b } => (r, g, b),
+        colored::Color::Black => ANSI16_RGB[0],
+        colored::Color::Red => ANSI16_RGB[1],
+        colored::Color::Green => ANSI16_RGB[2],
+        colored::Color::Yellow => ANSI16_RGB[3],
+        colored::Color::Blue => ANSI16_RGB[4],
+        colored::Color::Magenta => ANSI16_RGB[5],
+        colored::Color::Cyan => ANSI16_RGB[6],
+        colored::Color::White => ANSI16_RGB[7],
+        colored::Color::BrightBlack => ANSI16_RGB[8],
+        colored::Color::BrightRed => ANSI16_RGB[9],
+        colored::Color::BrightGreen => ANSI16_RGB[10],
+        colored::Color::BrightYellow => ANSI16_RGB[11],
+        colored::Color::BrightBlue => ANSI16_RGB[12],
+        colored::Color::BrightMagenta => ANSI16_RGB[13],
+        colored::Color::BrightCyan => ANSI16_RGB[14],
+        colored::Color::BrightWhite => ANSI16_RGB[15],
+    };
+    hex(rgb)
+}
+
+/// Escapes text for use in HTML content and attribute values.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Builds the HTML page: the tree in a `<pre>` block, followed by the summary.
+///
+/// Each level of the tree is indented by `indent` spaces.
+pub fn render(root: &str, entries: &[HtmlEntry], summary: Summary, indent: usize) -> String {
+    let mut html = String::new();
+    let root = escape(root);
+    _ = writeln!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">");
+    _ = writeln!(html, "<title>{root}</title>\n<style>");
+    _ = writeln!(html, "body {{ background: #1e1e1e; color: #d4d4d4; }}");
+    _ = writeln!(html, "pre {{ font-family: {FONT_STACK}; }}");
+    _ = writeln!(html, "a {{ color: inherit; }}");
+    _ = writeln!(html, ".root {{ color: {}; font-weight: bold; }}", hex(ANSI16_RGB[12]));
+    _ = writeln!(html, ".size, .summary {{ opacity: 0.6; }}");
+    _ = writeln!(html, "</style>\n</head>\n<body>");
+    _ = writeln!(html, "<pre>\n<span class=\"root\">{root}</span>");
+
+    for entry in entries {
+        let indent = " ".repeat(indent * entry.depth.saturating_sub(1));
+        let icon = entry.icon.as_ref().map_or(String::new(), |(icon, color)| {
+            format!("<span style=\"color:{color}\">{}</span> ", escape(icon))
+        });
+        let mut style = String::new();
+        if let Some(color) = &entry.color {
+            _ = write!(style, "color:{color};");
+        }
+        if entry.bold {
+            style.push_str("font-weight:bold;");
+        }
+        let mut name = format!("<span style=\"{style}\">{}</span>", escape(&entry.name));
+        if let Some(href) = &entry.href {
+            name = format!("<a href=\"{}\">{name}</a>", escape(href));
+        }
+        let size = entry.size.map_or(String::new(), |size| {
+            format!(" <span class=\"size\">({})</span>", utils::format_size(size))
+        });
+        _ = writeln!(html, "{indent}└── {icon}{name}{size}");
+    }
+
+    _ = writeln!(html, "</pre>");
+    _ = writeln!(
+        html,
+        "<p class=\"summary\">{} directories, {} files</p>",
+        summary.directories, summary.files
+    );
+    _ = writeln!(html, "</body>\n</html>");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ls_color_css() {
+        assert_eq!(ls_color_css(&LsColor::RGB(255, 128, 0)), "#ff8000");
+        assert_eq!(ls_color_css(&LsColor::Blue), "#0000ee");
+        assert_eq!(ls_color_css(&LsColor::BrightBlue), "#5c5cff");
+        // The first 16 indexes are the named colors.
+        assert_eq!(ls_color_css(&LsColor::Fixed(1)), ls_color_css(&LsColor::Red));
+        // The color cube: index 16 + 36 * r + 6 * g + b.
+        assert_eq!(ls_color_css(&LsColor::Fixed(16)), "#000000");
+        assert_eq!(ls_color_css(&LsColor::Fixed(196)), "#ff0000");
+        assert_eq!(ls_color_css(&LsColor::Fixed(67)), "#5f87af");
+        assert_eq!(ls_color_css(&LsColor::Fixed(231)), "#ffffff");
+        // The grayscale ramp.
+        assert_eq!(ls_color_css(&LsColor::Fixed(232)), "#080808");
+        assert_eq!(ls_color_css(&LsColor::Fixed(255)), "#eeeeee");
+
+        assert_eq!(colored_css(colored::Color::TrueColor { r: 1, g: 2, b: 3 }), "#010203");
+        assert_eq!(colored_css(colored::Color::BrightBlack), "#7f7f7f");
+    }
+
+    #[test]
+    fn test_render_html() {
+        let entries = [
+            HtmlEntry {
+                depth: 1,
+                name: "src".to_string(),
+                color: Some("#0000ee".to_string()),
+                bold: true,
+                ..Default::default()
+            },
+            HtmlEntry {
+                depth: 2,
+                name: "a<b>.rs".to_string(),
+                icon: Some(("\u{e68b}".to_string(), "#cd0000".to_string())),
+                href: Some("file:///tmp/a%3Cb%3E.rs".to_string()),
+                size: Some(2048),
+                ..Default::default()
+            },
+        ];
+        let html = render("project", &entries, Summary { directories: 1, files: 1 }, 4);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<span class=\"root\">project</span>"));
+        assert!(html.contains("└── <span style=\"color:#0000ee;font-weight:bold;\">src</span>\n"));
+        assert!(html.contains(
+            "    └── <span style=\"color:#cd0000\">\u{e68b}</span> \
+             <a href=\"file:///tmp/a%3Cb%3E.rs\"><span style=\"\">a&lt;b&gt;.rs</span></a> \
+             <span class=\"size\">(2.0 KiB)</span>\n"
+        ));
+        assert!(html.contains("<p class=\"summary\">1 directories, 1 files</p>"));
+    }
+}
@@ -0,0 +1,69 @@
+//! Serializes the tree as a single JSON document.
+
+use super::{Summary, TreeEntry};
+use std::io::Write;
+
+/// Writes the tree as a JSON object with `root`, `entries` and `summary` keys.
+///
+/// With `--virtual-root`, the label is added as `virtual_root`; `root` stays the real path.
+pub fn write(
+    out: &mut dyn Write,
+    root: &str,
+    virtual_root: Option<&str>,
+    entries: &[TreeEntry],
+) -> anyhow::Result<()> {
+    let mut document = serde_json::json!({
+        "root": root,
+        "entries": entries,
+        "summary": Summary::of(entries),
+    });
+    if let Some(virtual_root) = virtual_root {
+        document["virtual_root"] = virtual_root.into();
+    }
+    writeln!(out, "{}", serde_json::to_string_pretty(&document)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_json() {
+        let entries = [
+            TreeEntry {
+                path: "src".to_string(),
+                depth: 1,
+                is_dir: true,
+                size: None,
+                permissions: Some("drwxr-xr-x".to_string()),
+                git_status: None,
+            },
+            TreeEntry {
+                path: "src/main.rs".to_string(),
+                depth: 2,
+                is_dir: false,
+                size: Some(42),
+                permissions: None,
+                git_status: Some("modified".to_string()),
+            },
+        ];
+        let mut buffer = Vec::new();
+        write(&mut buffer, ".", None, &entries).unwrap();
+
+        let value: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(value["root"], ".");
+        assert!(value.get("virtual_root").is_none());
+        assert_eq!(value["summary"], serde_json::json!({ "directories": 1, "files": 1 }));
+        assert_eq!(value["entries"][0]["size"], serde_json::Value::Null);
+        assert_eq!(value["entries"][1]["path"], "src/main.rs");
+        assert_eq!(value["entries"][1]["size"], 42);
+        assert_eq!(value["entries"][1]["git_status"], "modified");
+
+        let mut buffer = Vec::new();
+        write(&mut buffer, "/home/user/project", Some("project/"), &entries).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(value["root"], "/home/user/project");
+        assert_eq!(value["virtual_root"], "project/");
+    }
+}
@@ -0,0 +1,37 @@
+//! Helpers for `--format markdown`, which wraps the plain tree in a code fence.
+//!
+//! The tree itself is rendered by the classic view with colors disabled; this
+//! module only supplies the Markdown syntax around and inside it. With
+//! `--hyperlinks`, the tree is drawn as a nested list instead, so the links render.
+
+use url::Url;
+
+/// The code fence written before and after the tree.
+pub const FENCE: &str = "```";
+
+/// Formats a file name as a Markdown link to `url`.
+///
+/// Brackets in the name and parentheses in the URL are escaped so they do
+/// not end the link early.
+pub fn link(name: &str, url: &Url) -> String {
+    let name = name.replace('[', "\\[").replace(']', "\\]");
+    let target = url.as_str().replace('(', "%28").replace(')', "%29");
+    format!("[{name}]({target})")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link() {
+        let url = Url::parse("file:///home/me/src/main.rs").unwrap();
+        assert_eq!(link("main.rs", &url), "[main.rs](file:///home/me/src/main.rs)");
+
+        let url = Url::parse("file:///tmp/notes%20(old)/[draft].md").unwrap();
+        assert_eq!(
+            link("[draft].md", &url),
+            "[\\[draft\\].md](file:///tmp/notes%20%28old%29/[draft].md)"
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! Alternative formats for the classic view, selected with `--format`.
+//!
+//! Machine-readable formats first convert the tree into a list of `TreeEntry`
+//! values, so each format only decides how to serialize them.
+
+pub mod csv;
+pub mod html;
+pub mod json;
+pub mod markdown;
+
+use serde::Serialize;
+
+/// The formats the classic view can be printed in.
+#[derive(clap::ValueEnum, Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// The colored tree (default)
+    #[default]
+    Text,
+    /// A JSON document with every entry and a summary
+    Json,
+    /// The plain tree in a Markdown code block, for pasting into issues
+    Markdown,
+    /// A self-contained HTML page with the colors and icons of the terminal
+    Html,
+    /// One CSV row per entry, with a header row
+    Csv,
+}
+
+impl OutputFormat {
+    /// Returns true for the formats that draw the tree line by line.
+    pub fn is_tree(self) -> bool {
+        matches!(self, OutputFormat::Text | OutputFormat::Markdown)
+    }
+}
+
+/// One entry of the tree, independent of the output format.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TreeEntry {
+    /// Path relative to the root of the tree.
+    pub path: String,
+    pub depth: usize,
+    pub is_dir: bool,
+    /// Size in bytes; not set for directories.
+    pub size: Option<u64>,
+    /// Permissions in `ls -l` notation (Unix only).
+    pub permissions: Option<String>,
+    /// Git status, with `--git-status`, for entries that have one.
+    pub git_status: Option<String>,
+}
+
+/// The number of directories and files in a tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    pub directories: usize,
+    pub files: usize,
+}
+
+impl Summary {
+    /// Counts the directories and files among `entries`.
+    pub fn of(entries: &[TreeEntry]) -> Self {
+        let directories = entries.iter().filter(|e| e.is_dir).count();
+        Self { directories, files: entries.len() - directories }
+    }
+}
@@ -0,0 +1,305 @@
+//! Walks a directory tree and returns its entries in the order `fstree` prints them.
+
+use crate::sort::{self, SortOptions, Sortable};
+use crate::utils;
+#[cfg(feature = "async")]
+use futures_util::Stream;
+use ignore::WalkBuilder;
+use std::ffi::OsStr;
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+
+/// Controls which entries `scan` returns and how they are ordered.
+///
+/// The defaults match `fstree` without flags: hidden files are skipped and
+/// entries are sorted by name.
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// Include hidden files and directories.
+    pub all: bool,
+    /// Skip entries ignored by `.gitignore` files.
+    pub gitignore: bool,
+    /// Follow symbolic links to directories.
+    pub follow_links: bool,
+    /// Descend at most this many levels below the root.
+    pub max_depth: Option<usize>,
+    /// Walk on several threads; the entries are returned in the same order.
+    pub parallel: bool,
+    /// How the entries of each directory are sorted.
+    pub sort: SortOptions,
+}
+
+/// A file or directory found by `scan`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The path, starting with the root passed to `scan`.
+    pub path: PathBuf,
+    /// The level below the root, starting at 1.
+    pub depth: usize,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    /// The size in bytes, for files whose metadata could be read.
+    pub size: Option<u64>,
+}
+
+impl From<ignore::DirEntry> for Entry {
+    fn from(entry: ignore::DirEntry) -> Self {
+        let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+        let size = if is_dir { None } else { entry.metadata().ok().map(|md| md.len()) };
+        Self {
+            depth: entry.depth(),
+            is_dir,
+            is_symlink: entry.path_is_symlink(),
+            size,
+            path: entry.into_path(),
+        }
+    }
+}
+
+impl Sortable for Entry {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn file_name(&self) -> &OsStr {
+        self.path.file_name().unwrap_or(self.path.as_os_str())
+    }
+
+    fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    fn metadata(&self) -> Option<Metadata> {
+        fs::symlink_metadata(&self.path).ok()
+    }
+}
+
+/// Scans the tree below `root`, excluding the root itself.
+///
+/// The whole tree is walked before the first entry is returned, since sorting
+/// needs every sibling. Errors met during the walk, such as unreadable
+/// directories, come first; the entries follow in display order, each
+/// directory directly before its contents.
+///
+/// # Examples
+///
+/// ```
+/// use fstree::scanner::{scan, ScanOptions};
+/// use std::fs;
+///
+/// let dir = tempfile::tempdir()?;
+/// fs::create_dir(dir.path().join("src"))?;
+/// fs::write(dir.path().join("src/main.rs"), "fn main() {}")?;
+/// fs::write(dir.path().join("Cargo.toml"), "")?;
+///
+/// let options = ScanOptions { max_depth: Some(2), ..Default::default() };
+/// let mut lines = Vec::new();
+/// for entry in scan(dir.path(), &options) {
+///     let entry = entry?;
+///     let name = entry.path.file_name().unwrap().to_string_lossy();
+///     lines.push(format!("{}{name}", "  ".repeat(entry.depth - 1)));
+/// }
+/// assert_eq!(lines, ["Cargo.toml", "src", "  main.rs"]);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn scan(root: &Path, options: &ScanOptions) -> impl Iterator<Item = anyhow::Result<Entry>> {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(!options.all)
+        .git_ignore(options.gitignore)
+        .follow_links(options.follow_links)
+        .max_depth(options.max_depth);
+
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    for result in utils::walk(&builder, options.parallel, None) {
+        match result {
+            Ok(entry) if entry.depth() == 0 => {}
+            Ok(entry) => entries.push(entry),
+            Err(err) => errors.push(anyhow::Error::from(err)),
+        }
+    }
+    sort::sort_entries(&mut entries, &options.sort);
+
+    errors.into_iter().map(Err).chain(entries.into_iter().map(|entry| Ok(Entry::from(entry))))
+}
+
+/// Work left for `scan_async`, kept on a stack so the tree is visited depth-first.
+#[cfg(feature = "async")]
+enum Pending {
+    /// A directory whose children at `depth` have not been read yet.
+    Read(PathBuf, usize),
+    /// An entry ready to be yielded.
+    Yield(Entry),
+}
+
+/// Scans the tree below `root` with `tokio::fs`, yielding entries as they are found.
+///
+/// Unlike `scan`, only one directory is read at a time: its children are sorted
+/// and yielded, each directory directly before its contents. A directory that
+/// cannot be read yields an error in its place and the scan continues. Sorting
+/// by metadata reads it synchronously. `gitignore` and `parallel` are not used.
+///
+/// # Examples
+///
+/// ```
+/// use fstree::scanner::{scan_async, ScanOptions};
+/// use futures_util::StreamExt;
+///
+/// # tokio::runtime::Builder::new_current_thread().build()?.block_on(async {
+/// let mut entries = Box::pin(scan_async(".".into(), ScanOptions::default()));
+/// while let Some(entry) = entries.next().await {
+///     println!("{}", entry?.path.display());
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// # })?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[cfg(feature = "async")]
+pub fn scan_async(
+    root: PathBuf,
+    options: ScanOptions,
+) -> impl Stream<Item = anyhow::Result<Entry>> {
+    let stack =
+        if options.max_depth == Some(0) { Vec::new() } else { vec![Pending::Read(root, 1)] };
+    futures_util::stream::unfold((stack, options), |(mut stack, options)| async move {
+        loop {
+            match stack.pop()? {
+                Pending::Read(dir, depth) => match read_children(&dir, depth, &options).await {
+                    Ok(children) => stack.extend(children.into_iter().rev().map(Pending::Yield)),
+                    Err(err) => return Some((Err(err), (stack, options))),
+                },
+                Pending::Yield(entry) => {
+                    let descends = entry.is_dir
+                        && options.max_depth.is_none_or(|max| entry.depth < max)
+                        && !(entry.is_symlink && is_symlink_loop(&entry.path).await);
+                    if descends {
+                        stack.push(Pending::Read(entry.path.clone(), entry.depth + 1));
+                    }
+                    return Some((Ok(entry), (stack, options)));
+                }
+            }
+        }
+    })
+}
+
+/// Reads and sorts the children of `dir`, which sit at `depth` below the root.
+#[cfg(feature = "async")]
+async fn read_children(
+    dir: &Path,
+    depth: usize,
+    options: &ScanOptions,
+) -> anyhow::Result<Vec<Entry>> {
+    let read_error = |e| anyhow::anyhow!("Failed to read '{}': {e}", dir.display());
+    let mut read_dir = tokio::fs::read_dir(dir).await.map_err(read_error)?;
+    let mut children = Vec::new();
+    while let Some(child) = read_dir.next_entry().await.map_err(read_error)? {
+        if !options.all && child.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = child.path();
+        let is_symlink = child.file_type().await.is_ok_and(|ft| ft.is_symlink());
+        let metadata = if is_symlink && options.follow_links {
+            tokio::fs::metadata(&path).await
+        } else {
+            tokio::fs::symlink_metadata(&path).await
+        };
+        let is_dir = metadata.as_ref().is_ok_and(|md| md.is_dir());
+        let size = metadata.ok().filter(|_| !is_dir).map(|md| md.len());
+        children.push(Entry { path, depth, is_dir, is_symlink, size });
+    }
+    sort::sort_entries(&mut children, &options.sort);
+    Ok(children)
+}
+
+/// Returns true if a followed symlink points to one of its own ancestors.
+#[cfg(feature = "async")]
+async fn is_symlink_loop(path: &Path) -> bool {
+    let (Ok(target), Some(parent)) = (tokio::fs::canonicalize(path).await, path.parent()) else {
+        return true;
+    };
+    tokio::fs::canonicalize(parent).await.is_ok_and(|parent| parent.starts_with(target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_scan_options() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".hidden"), "").unwrap();
+        fs::create_dir(dir.path().join("b")).unwrap();
+        fs::write(dir.path().join("b/file.txt"), "12345").unwrap();
+        fs::write(dir.path().join("a.txt"), "").unwrap();
+
+        let names = |options: &ScanOptions| -> Vec<(String, usize, Option<u64>)> {
+            scan(dir.path(), options)
+                .map(Result::unwrap)
+                .map(|entry| {
+                    let name = entry.path.strip_prefix(dir.path()).unwrap();
+                    (name.display().to_string(), entry.depth, entry.size)
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            names(&ScanOptions::default()),
+            [
+                ("a.txt".to_string(), 1, Some(0)),
+                ("b".to_string(), 1, None),
+                ("b/file.txt".to_string(), 2, Some(5)),
+            ]
+        );
+
+        let options = ScanOptions {
+            all: true,
+            max_depth: Some(1),
+            parallel: true,
+            sort: SortOptions { directories_first: true, ..Default::default() },
+            ..Default::default()
+        };
+        assert_eq!(
+            names(&options),
+            [
+                ("b".to_string(), 1, None),
+                (".hidden".to_string(), 1, Some(0)),
+                ("a.txt".to_string(), 1, Some(0)),
+            ]
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_scan_async() {
+        use futures_util::StreamExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("b/c")).unwrap();
+        fs::write(dir.path().join("b/c/deep.txt"), "").unwrap();
+        fs::write(dir.path().join("b/file.txt"), "12345").unwrap();
+        fs::write(dir.path().join("a.txt"), "").unwrap();
+        fs::write(dir.path().join(".hidden"), "").unwrap();
+
+        let options = ScanOptions { max_depth: Some(2), ..Default::default() };
+        let entries: Vec<Entry> =
+            scan_async(dir.path().to_path_buf(), options).map(Result::unwrap).collect().await;
+        let names: Vec<_> = entries
+            .iter()
+            .map(|entry| entry.path.strip_prefix(dir.path()).unwrap().display().to_string())
+            .collect();
+        // The same order as the synchronous scan.
+        assert_eq!(names, ["a.txt", "b", "b/c", "b/file.txt"]);
+        assert_eq!(entries[3].size, Some(5));
+
+        let options = ScanOptions { max_depth: Some(2), ..Default::default() };
+        let expected: Vec<Entry> = scan(dir.path(), &options).map(Result::unwrap).collect();
+        assert_eq!(entries, expected);
+
+        let missing = dir.path().join("missing");
+        let results: Vec<_> = scan_async(missing, ScanOptions::default()).collect().await;
+        assert_eq!(results.len(), 1);
+        assert!(results[0].as_ref().unwrap_err().to_string().starts_with("Failed to read"));
+    }
+}
@@ -3,9 +3,19 @@
 //! This module implements various sorting strategies for file and directory entries,
 //! ensuring consistent behavior across all supported platforms (Windows, macOS, Linux).
 
+use crate::utils::{self, NormalizationForm};
 use ignore::DirEntry;
+use rand::seq::SliceRandom;
+use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::ffi::OsStr;
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
 
 /// Defines the available sorting strategies.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -16,8 +26,18 @@ pub enum SortType {
     Size,
     /// Sort by modification time
     Modified,
+    /// Sort by creation (birth) time
+    Created,
+    /// Sort by last access time
+    Accessed,
+    /// Shuffle entries into a random order
+    Random,
+    /// Sort directories by number of immediate children, most first
+    Children,
     /// Sort by file extension
     Extension,
+    /// Sort by number of commits touching the file, most first
+    Commits,
 }
 
 impl Default for SortType {
@@ -26,21 +46,182 @@ impl Default for SortType {
     }
 }
 
+impl SortType {
+    /// Returns the next sort type in the interactive cycle:
+    /// `Name → Size → Modified → Extension → Name`.
+    pub fn cycle(self) -> Self {
+        match self {
+            Self::Name => Self::Size,
+            Self::Size => Self::Modified,
+            Self::Modified => Self::Extension,
+            Self::Extension
+            | Self::Created
+            | Self::Accessed
+            | Self::Random
+            | Self::Children
+            | Self::Commits => Self::Name,
+        }
+    }
+
+    /// Returns the lowercase name of the sort type, as accepted by `--sort`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Size => "size",
+            Self::Modified => "modified",
+            Self::Created => "created",
+            Self::Accessed => "accessed",
+            Self::Random => "random",
+            Self::Children => "children",
+            Self::Extension => "extension",
+            Self::Commits => "commits",
+        }
+    }
+}
+
+/// An entry that can be sorted: anything with a path, a name and metadata.
+///
+/// This lets the same comparison logic sort both walker results and the
+/// entries held by the interactive mode.
+pub trait Sortable {
+    /// The full path of the entry.
+    fn path(&self) -> &Path;
+    /// The final component of the entry's path.
+    fn file_name(&self) -> &OsStr;
+    /// Whether the entry is a directory.
+    fn is_dir(&self) -> bool;
+    /// The entry's metadata, if it can be read.
+    fn metadata(&self) -> Option<Metadata>;
+}
+
+impl Sortable for DirEntry {
+    fn path(&self) -> &Path {
+        DirEntry::path(self)
+    }
+
+    fn file_name(&self) -> &OsStr {
+        DirEntry::file_name(self)
+    }
+
+    fn is_dir(&self) -> bool {
+        self.file_type().is_some_and(|ft| ft.is_dir())
+    }
+
+    fn metadata(&self) -> Option<Metadata> {
+        DirEntry::metadata(self).ok()
+    }
+}
+
 /// Configuration options for sorting directory entries.
 #[derive(Debug, Clone, Default)]
 pub struct SortOptions {
     /// The primary sorting strategy
     pub sort_type: SortType,
+    /// The strategy that orders entries the primary strategy considers equal
+    pub sort2: SortType,
     /// Whether to sort directories before files
     pub directories_first: bool,
     /// Whether to use case-sensitive name sorting
     pub case_sensitive: bool,
+    /// Whether to sort case-sensitively only the directories that contain uppercase names
+    pub smart_case: bool,
     /// Whether to use natural/version sorting (e.g., file1 < file10)
     pub natural_sort: bool,
     /// Whether to reverse the sort order
     pub reverse: bool,
     /// Whether to sort dotfiles/dotfolders first
     pub dotfiles_first: bool,
+    /// Commit counts keyed by entry path, used by `SortType::Commits`
+    pub commit_counts: HashMap<PathBuf, usize>,
+    /// Total sizes of directories keyed by path, used by `SortType::Size`
+    pub dir_sizes: HashMap<PathBuf, u64>,
+    /// Immediate child counts of directories keyed by path, used by `SortType::Children`
+    pub child_counts: HashMap<PathBuf, usize>,
+    /// Unicode normalization applied to names before comparing them
+    pub unicode_normalization: Option<NormalizationForm>,
+    /// Locale-specific name comparison, taking precedence over the case options
+    pub collator: Option<Arc<dyn Collator>>,
+}
+
+impl SortOptions {
+    /// Returns true if `sort_type` is the primary or the secondary strategy.
+    pub fn uses(&self, sort_type: SortType) -> bool {
+        self.sort_type == sort_type || self.sort2 == sort_type
+    }
+}
+
+/// Compares names according to the rules of a locale.
+pub trait Collator: fmt::Debug + Send + Sync {
+    /// Compares two names, returning their order in the locale.
+    fn compare(&self, a: &str, b: &str) -> Ordering;
+}
+
+/// The `C` (or `POSIX`) locale, which compares names byte by byte.
+#[derive(Debug)]
+struct ByteOrderCollator;
+
+impl Collator for ByteOrderCollator {
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        a.as_bytes().cmp(b.as_bytes())
+    }
+}
+
+/// An approximation of the Unicode collation rules of a language.
+///
+/// Names are first compared by their base letters, ignoring accents and case
+/// (so `Äpfel` sorts with `apfel`), then by accents, then by case. Languages
+/// such as Swedish sort a few accented letters as separate letters after `z`.
+#[derive(Debug)]
+struct LanguageCollator {
+    /// Letters sorted after `z`, in order.
+    letters_after_z: &'static [char],
+}
+
+impl LanguageCollator {
+    /// Returns the weights of the base letters of `s`.
+    fn primary_key(&self, s: &str) -> Vec<u32> {
+        let mut key = Vec::with_capacity(s.len());
+        for c in s.chars().flat_map(char::to_lowercase) {
+            if let Some(index) = self.letters_after_z.iter().position(|&l| l == c) {
+                key.push((u32::from('z') << 8) | (index as u32 + 1));
+            } else if c == 'ß' {
+                key.extend([u32::from('s') << 8, u32::from('s') << 8]);
+            } else {
+                key.extend(c.nfd().filter(|&d| !is_combining_mark(d)).map(|d| u32::from(d) << 8));
+            }
+        }
+        key
+    }
+}
+
+impl Collator for LanguageCollator {
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.primary_key(a)
+            .cmp(&self.primary_key(b))
+            .then_with(|| a.to_lowercase().cmp(&b.to_lowercase()))
+            .then_with(|| a.cmp(b))
+    }
+}
+
+/// Builds the collator for a locale name such as `de_DE.UTF-8`, `sv` or `C`.
+///
+/// Only the language part of the locale affects the order; the territory,
+/// encoding and modifier are accepted but ignored.
+pub fn build_collator(locale: &str) -> anyhow::Result<Box<dyn Collator>> {
+    let name = locale.split(['.', '@']).next().unwrap_or_default();
+    if name == "C" || name == "POSIX" {
+        return Ok(Box::new(ByteOrderCollator));
+    }
+    let language = name.split(['_', '-']).next().unwrap_or_default().to_ascii_lowercase();
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        anyhow::bail!("Unsupported locale '{locale}'");
+    }
+    let letters_after_z: &'static [char] = match language.as_str() {
+        "sv" | "fi" => &['å', 'ä', 'ö'],
+        "da" | "nb" | "nn" | "no" => &['æ', 'ø', 'å'],
+        _ => &[],
+    };
+    Ok(Box::new(LanguageCollator { letters_after_z }))
 }
 
 /// Sorts a vector of directory entries according to the given options.
@@ -49,6 +230,13 @@ pub struct SortOptions {
 /// all platforms. The sorting is stable, preserving the original order for
 /// equal elements.
 ///
+/// Entries are only compared with their siblings, so every directory stays
+/// directly followed by its (sorted) children and the pre-order layout
+/// produced by the walker is preserved.
+///
+/// With `SortType::Random`, siblings are shuffled instead; the directory and
+/// dotfile grouping still applies, while `reverse` has no effect.
+///
 /// # Arguments
 ///
 /// * `entries` - A mutable reference to the vector of entries to sort
@@ -59,7 +247,7 @@ pub struct SortOptions {
 /// ```rust
 /// use fstree::sort::{sort_entries, SortOptions, SortType};
 ///
-/// let mut entries = vec![/* ... */];
+/// let mut entries: Vec<ignore::DirEntry> = vec![/* ... */];
 /// let options = SortOptions {
 ///     sort_type: SortType::Name,
 ///     directories_first: true,
@@ -67,21 +255,72 @@ pub struct SortOptions {
 /// };
 /// sort_entries(&mut entries, &options);
 /// ```
-pub fn sort_entries(entries: &mut [DirEntry], options: &SortOptions) {
-    entries.sort_by(|a, b| {
-        let result = compare_entries(a, b, options);
-        if options.reverse {
-            result.reverse()
-        } else {
-            result
+pub fn sort_entries<T: Sortable>(entries: &mut Vec<T>, options: &SortOptions) {
+    // Group entries by their parent directory.
+    let mut children: HashMap<&Path, Vec<usize>> = HashMap::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let parent = entry.path().parent().unwrap_or(Path::new(""));
+        children.entry(parent).or_default().push(i);
+    }
+
+    // With smart case, directories containing uppercase names sort case-sensitively.
+    let case_sensitive_options = (options.smart_case && !options.case_sensitive)
+        .then(|| SortOptions { case_sensitive: true, ..options.clone() });
+
+    let mut rng = rand::thread_rng();
+    for siblings in children.values_mut() {
+        if options.sort_type == SortType::Random {
+            siblings.shuffle(&mut rng);
+        }
+        let group_options = match &case_sensitive_options {
+            Some(case_sensitive)
+                if has_uppercase_names(siblings.iter().map(|&i| entries[i].file_name())) =>
+            {
+                case_sensitive
+            }
+            _ => options,
+        };
+        siblings.sort_by(|&a, &b| {
+            let result = compare_entries(&entries[a], &entries[b], group_options);
+            if options.reverse && options.sort_type != SortType::Random {
+                result.reverse()
+            } else {
+                result
+            }
+        });
+    }
+
+    // Entries whose parent is not part of the list are the top-level entries.
+    let paths: HashSet<&Path> = entries.iter().map(|e| e.path()).collect();
+    let mut roots: Vec<&Path> =
+        children.keys().copied().filter(|parent| !paths.contains(parent)).collect();
+    roots.sort_by_key(|parent| children[parent][0]);
+
+    let mut order = Vec::with_capacity(entries.len());
+    let mut stack: Vec<usize> = Vec::new();
+    for root in roots {
+        stack.extend(children[root].iter().rev());
+        while let Some(i) = stack.pop() {
+            order.push(i);
+            if let Some(grandchildren) = children.get(entries[i].path()) {
+                stack.extend(grandchildren.iter().rev());
+            }
         }
-    });
+    }
+
+    let mut slots: Vec<Option<T>> = std::mem::take(entries).into_iter().map(Some).collect();
+    *entries = order.into_iter().filter_map(|i| slots[i].take()).collect();
+}
+
+/// Returns true if any of the names contains an uppercase letter.
+pub fn has_uppercase_names<'a>(names: impl IntoIterator<Item = &'a OsStr>) -> bool {
+    names.into_iter().any(|name| name.to_string_lossy().chars().any(char::is_uppercase))
 }
 
 /// Compares two directory entries according to the sorting options.
-fn compare_entries(a: &DirEntry, b: &DirEntry, options: &SortOptions) -> Ordering {
-    let a_is_dir = a.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
-    let b_is_dir = b.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
+fn compare_entries<T: Sortable>(a: &T, b: &T, options: &SortOptions) -> Ordering {
+    let a_is_dir = a.is_dir();
+    let b_is_dir = b.is_dir();
     let a_is_dotfile = is_dotfile(a);
     let b_is_dotfile = is_dotfile(b);
 
@@ -112,21 +351,39 @@ fn compare_entries(a: &DirEntry, b: &DirEntry, options: &SortOptions) -> Orderin
         }
     }
 
-    // Apply the primary sorting strategy
-    match options.sort_type {
+    // Random order is shuffled beforehand, only the grouping above applies
+    if options.sort_type == SortType::Random {
+        return Ordering::Equal;
+    }
+
+    // Apply the primary sorting strategy, then the secondary one to break ties
+    compare_by(options.sort_type, a, b, options)
+        .then_with(|| compare_by(options.sort2, a, b, options))
+}
+
+/// Compares two entries using a single sorting strategy.
+fn compare_by<T: Sortable>(sort_type: SortType, a: &T, b: &T, options: &SortOptions) -> Ordering {
+    match sort_type {
         SortType::Name => compare_by_name(a, b, options),
-        SortType::Size => compare_by_size(a, b),
+        SortType::Size => compare_by_size(a, b, options),
         SortType::Modified => compare_by_modified(a, b),
+        SortType::Created => compare_by_created(a, b),
+        SortType::Accessed => compare_by_accessed(a, b),
+        SortType::Random => Ordering::Equal,
+        SortType::Children => compare_by_children(a, b, options),
         SortType::Extension => compare_by_extension(a, b, options),
+        SortType::Commits => compare_by_commits(a, b, options),
     }
 }
 
 /// Compares entries by name, handling case sensitivity and natural sorting.
-fn compare_by_name(a: &DirEntry, b: &DirEntry, options: &SortOptions) -> Ordering {
-    let name_a = a.file_name();
-    let name_b = b.file_name();
+fn compare_by_name<T: Sortable>(a: &T, b: &T, options: &SortOptions) -> Ordering {
+    let name_a = &*normalized_name(a.file_name(), options);
+    let name_b = &*normalized_name(b.file_name(), options);
 
-    if options.natural_sort {
+    if let Some(collator) = &options.collator {
+        collator.compare(&name_a.to_string_lossy(), &name_b.to_string_lossy())
+    } else if options.natural_sort {
         compare_natural(name_a, name_b)
     } else if options.case_sensitive {
         // Use default order for case-sensitive sorting (numbers, uppercase, lowercase)
@@ -136,17 +393,27 @@ fn compare_by_name(a: &DirEntry, b: &DirEntry, options: &SortOptions) -> Orderin
     }
 }
 
-/// Compares entries by file size, with directories having size 0.
-fn compare_by_size(a: &DirEntry, b: &DirEntry) -> Ordering {
-    let size_a = get_entry_size(a);
-    let size_b = get_entry_size(b);
+/// Applies the configured Unicode normalization to a file name, if any.
+fn normalized_name<'a>(name: &'a OsStr, options: &SortOptions) -> Cow<'a, OsStr> {
+    match options.unicode_normalization {
+        Some(form) => {
+            Cow::Owned(OsString::from(utils::normalize_for_sort(&name.to_string_lossy(), form)))
+        }
+        None => Cow::Borrowed(name),
+    }
+}
+
+/// Compares entries by file size, with directories using their total size if known.
+fn compare_by_size<T: Sortable>(a: &T, b: &T, options: &SortOptions) -> Ordering {
+    let size_a = get_entry_size(a, options);
+    let size_b = get_entry_size(b, options);
     size_a.cmp(&size_b)
 }
 
 /// Compares entries by modification time.
-fn compare_by_modified(a: &DirEntry, b: &DirEntry) -> Ordering {
-    let modified_a = a.metadata().ok().and_then(|m| m.modified().ok());
-    let modified_b = b.metadata().ok().and_then(|m| m.modified().ok());
+fn compare_by_modified<T: Sortable>(a: &T, b: &T) -> Ordering {
+    let modified_a = a.metadata().and_then(|m| m.modified().ok());
+    let modified_b = b.metadata().and_then(|m| m.modified().ok());
 
     match (modified_a, modified_b) {
         (Some(a_time), Some(b_time)) => a_time.cmp(&b_time),
@@ -156,8 +423,56 @@ fn compare_by_modified(a: &DirEntry, b: &DirEntry) -> Ordering {
     }
 }
 
+/// Compares entries by creation time.
+///
+/// Platforms and filesystems that do not record a birth time fall back to the
+/// modification time, so the order stays meaningful instead of arbitrary.
+fn compare_by_created<T: Sortable>(a: &T, b: &T) -> Ordering {
+    let created =
+        |entry: &T| entry.metadata().and_then(|m| m.created().or_else(|_| m.modified()).ok());
+
+    match (created(a), created(b)) {
+        (Some(a_time), Some(b_time)) => a_time.cmp(&b_time),
+        (Some(_), None) => Ordering::Less, // Files with known time sort first
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Compares entries by last access time.
+///
+/// Filesystems mounted with `noatime` never update access times, and `relatime`
+/// only does so once a day, so the order may not reflect recent reads.
+fn compare_by_accessed<T: Sortable>(a: &T, b: &T) -> Ordering {
+    let accessed_a = a.metadata().and_then(|m| m.accessed().ok());
+    let accessed_b = b.metadata().and_then(|m| m.accessed().ok());
+
+    match (accessed_a, accessed_b) {
+        (Some(a_time), Some(b_time)) => a_time.cmp(&b_time),
+        (Some(_), None) => Ordering::Less, // Files with known time sort first
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Compares entries by number of immediate children in descending order.
+///
+/// Files, and directories whose count is unknown, count as having none.
+fn compare_by_children<T: Sortable>(a: &T, b: &T, options: &SortOptions) -> Ordering {
+    let count_a = options.child_counts.get(a.path()).copied().unwrap_or(0);
+    let count_b = options.child_counts.get(b.path()).copied().unwrap_or(0);
+    count_b.cmp(&count_a)
+}
+
+/// Compares entries by commit count in descending order, falling back to name comparison.
+fn compare_by_commits<T: Sortable>(a: &T, b: &T, options: &SortOptions) -> Ordering {
+    let count_a = options.commit_counts.get(a.path()).copied().unwrap_or(0);
+    let count_b = options.commit_counts.get(b.path()).copied().unwrap_or(0);
+    count_b.cmp(&count_a).then_with(|| compare_by_name(a, b, options))
+}
+
 /// Compares entries by file extension, falling back to name comparison.
-fn compare_by_extension(a: &DirEntry, b: &DirEntry, options: &SortOptions) -> Ordering {
+fn compare_by_extension<T: Sortable>(a: &T, b: &T, options: &SortOptions) -> Ordering {
     let ext_a = get_extension(a.file_name());
     let ext_b = get_extension(b.file_name());
 
@@ -232,7 +547,7 @@ fn char_sort_priority(c: char) -> u8 {
 }
 
 /// Checks if a directory entry is a dotfile/dotfolder (starts with '.').
-fn is_dotfile(entry: &DirEntry) -> bool {
+fn is_dotfile<T: Sortable>(entry: &T) -> bool {
     entry.file_name().to_string_lossy().starts_with('.')
 }
 
@@ -250,12 +565,14 @@ fn get_extension(filename: &OsStr) -> String {
         .to_string()
 }
 
-/// Gets the size of a directory entry, returning 0 for directories.
-fn get_entry_size(entry: &DirEntry) -> u64 {
-    if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
-        0 // Directories have size 0 for sorting purposes
+/// Gets the size of a directory entry.
+///
+/// Directories count as 0 unless their total size was computed, as with `--du`.
+fn get_entry_size<T: Sortable>(entry: &T, options: &SortOptions) -> u64 {
+    if entry.is_dir() {
+        options.dir_sizes.get(entry.path()).copied().unwrap_or(0)
     } else {
-        entry.metadata().ok().map(|m| m.len()).unwrap_or(0)
+        entry.metadata().map(|m| m.len()).unwrap_or(0)
     }
 }
 
@@ -263,6 +580,92 @@ fn get_entry_size(entry: &DirEntry) -> u64 {
 mod tests {
     use super::*;
 
+    /// A bare entry for testing comparisons that only depend on the name.
+    struct NamedEntry(PathBuf);
+
+    impl Sortable for NamedEntry {
+        fn path(&self) -> &Path {
+            &self.0
+        }
+
+        fn file_name(&self) -> &OsStr {
+            self.0.file_name().unwrap()
+        }
+
+        fn is_dir(&self) -> bool {
+            false
+        }
+
+        fn metadata(&self) -> Option<Metadata> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_smart_case_sorting() {
+        let names = |entries: &[NamedEntry]| -> Vec<String> {
+            entries.iter().map(|e| e.0.to_string_lossy().to_string()).collect()
+        };
+        let mut entries: Vec<NamedEntry> =
+            ["mixed/Zeta", "mixed/alpha", "mixed/Beta", "lower/beta", "lower/alpha"]
+                .iter()
+                .map(|p| NamedEntry(PathBuf::from(p)))
+                .collect();
+
+        assert!(has_uppercase_names([OsStr::new("alpha"), OsStr::new("Beta")]));
+        assert!(!has_uppercase_names([OsStr::new("alpha"), OsStr::new("beta_2")]));
+
+        let options = SortOptions { smart_case: true, ..Default::default() };
+        sort_entries(&mut entries, &options);
+        assert_eq!(
+            names(&entries),
+            ["mixed/Beta", "mixed/Zeta", "mixed/alpha", "lower/alpha", "lower/beta"]
+        );
+
+        sort_entries(&mut entries, &SortOptions::default());
+        assert_eq!(
+            names(&entries),
+            ["mixed/alpha", "mixed/Beta", "mixed/Zeta", "lower/alpha", "lower/beta"]
+        );
+    }
+
+    #[test]
+    fn test_unicode_normalized_name_sorting() {
+        let nfc = NamedEntry(PathBuf::from("caf\u{e9}"));
+        let nfd = NamedEntry(PathBuf::from("cafe\u{301}"));
+        let mut options = SortOptions::default();
+        assert_ne!(compare_by_name(&nfc, &nfd, &options), Ordering::Equal);
+
+        for form in [NormalizationForm::Nfc, NormalizationForm::Nfd, NormalizationForm::Auto] {
+            options.unicode_normalization = Some(form);
+            assert_eq!(compare_by_name(&nfc, &nfd, &options), Ordering::Equal);
+            options.case_sensitive = true;
+            assert_eq!(compare_by_name(&nfc, &nfd, &options), Ordering::Equal);
+            options.case_sensitive = false;
+        }
+    }
+
+    #[test]
+    fn test_locale_collation() {
+        let sorted = |locale: &str| {
+            let collator = build_collator(locale).unwrap();
+            let mut names = vec!["Zebra", "Öl", "Ofen", "Äpfel", "apfel", "Straße", "Strasse"];
+            names.sort_by(|a, b| collator.compare(a, b));
+            names
+        };
+        assert_eq!(sorted("C"), vec!["Ofen", "Strasse", "Straße", "Zebra", "apfel", "Äpfel", "Öl"]);
+        assert_eq!(
+            sorted("de_DE.UTF-8"),
+            vec!["apfel", "Äpfel", "Ofen", "Öl", "Strasse", "Straße", "Zebra"]
+        );
+        // Swedish sorts å, ä and ö as separate letters after z.
+        assert_eq!(
+            sorted("sv_SE"),
+            vec!["apfel", "Ofen", "Strasse", "Straße", "Zebra", "Äpfel", "Öl"]
+        );
+        assert!(build_collator("not a locale").is_err());
+    }
+
     #[test]
     fn test_case_insensitive_name_sorting() {
         // Test case-insensitive comparison
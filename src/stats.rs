@@ -0,0 +1,517 @@
+//! Provides aggregate views over a scanned tree.
+//!
+//! Unlike the classic view, which prints one line per entry, the functions in
+//! this module summarize many entries at once (e.g. as a grid or a chart).
+
+use crate::app::{LargeDirsArgs, ListExtArgs};
+use crate::utils;
+#[cfg(unix)]
+use colored::{Color, Colorize};
+use ignore::WalkBuilder;
+use rayon::prelude::*;
+use std::cmp::Reverse;
+#[cfg(unix)]
+use std::collections::BTreeMap;
+use std::collections::{BinaryHeap, HashMap};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// The number of extensions shown in the extension chart.
+const TOP_EXTENSIONS: usize = 10;
+
+/// The narrowest bar drawn in the extension chart, regardless of terminal width.
+const MIN_BAR_WIDTH: usize = 10;
+
+/// Per-extension totals: the number of files and their combined size in bytes.
+pub type ExtensionStats = HashMap<String, (usize, u64)>;
+
+/// Tallies file counts and sizes per extension (lowercased, `(none)` when missing).
+pub fn compute_extension_stats<'a>(
+    files: impl IntoIterator<Item = (&'a Path, u64)>,
+) -> ExtensionStats {
+    let mut stats = ExtensionStats::new();
+    for (path, size) in files {
+        let label = extension_of(path).map_or("(none)".to_string(), |ext| format!(".{ext}"));
+        let totals = stats.entry(label).or_default();
+        totals.0 += 1;
+        totals.1 += size;
+    }
+    stats
+}
+
+/// Renders a horizontal bar of `bar_width` cells filled in proportion to `count / max_count`.
+///
+/// Full cells use `█`, a trailing cell that is at least half full uses `▓`, and
+/// the remainder is padded with `░`.
+pub fn render_bar(count: usize, max_count: usize, bar_width: usize) -> String {
+    if max_count == 0 {
+        return "░".repeat(bar_width);
+    }
+    let count = count.min(max_count);
+    let scaled = count * bar_width * 2 / max_count;
+    let full = scaled / 2;
+    let half = usize::from(scaled % 2 == 1);
+    format!("{}{}{}", "█".repeat(full), "▓".repeat(half), "░".repeat(bar_width - full - half))
+}
+
+/// Renders the top extensions as a bar chart fitting in `width` columns.
+///
+/// Extensions are ranked by file count, or by total bytes when `by_size` is set;
+/// the bars are proportional to the same measure.
+pub fn render_extension_chart(stats: &ExtensionStats, by_size: bool, width: usize) -> Vec<String> {
+    let measure = |&(count, bytes): &(usize, u64)| if by_size { bytes as usize } else { count };
+
+    let mut ranked: Vec<(&String, &(usize, u64))> = stats.iter().collect();
+    ranked.sort_by(|a, b| measure(b.1).cmp(&measure(a.1)).then_with(|| a.0.cmp(b.0)));
+    ranked.truncate(TOP_EXTENSIONS);
+
+    let max_value = ranked.first().map_or(0, |(_, totals)| measure(totals));
+    let suffixes: Vec<String> = ranked
+        .iter()
+        .map(|(_, &(count, bytes))| format!(" {count} files ({})", utils::format_size(bytes)))
+        .collect();
+    let label_width = ranked.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+    let suffix_width = suffixes.iter().map(|s| s.chars().count()).max().unwrap_or(0);
+    // Two leading spaces, a space after the label and the two `│` delimiters.
+    let bar_width = width.saturating_sub(label_width + suffix_width + 5).max(MIN_BAR_WIDTH);
+
+    ranked
+        .iter()
+        .zip(suffixes)
+        .map(|((label, totals), suffix)| {
+            let bar = render_bar(measure(totals), max_value, bar_width);
+            format!("  {label:>label_width$} │{bar}│{suffix}")
+        })
+        .collect()
+}
+
+/// Computes the total size of the tree and the `args.count` largest directories in it.
+///
+/// Each directory's size includes everything below it. File sizes are summed
+/// into their ancestors in parallel, and only directories at most
+/// `args.large_dirs_depth` levels below the root are candidates, so a large
+/// subtree is not reported once per nested directory.
+///
+/// Returns the total size alongside the directories, largest first.
+pub fn find_largest_dirs(
+    root: &Path,
+    args: &LargeDirsArgs,
+) -> anyhow::Result<(u64, Vec<(PathBuf, u64)>)> {
+    let mut builder = WalkBuilder::new(root);
+    builder.hidden(!args.all).git_ignore(args.gitignore);
+
+    let mut files: Vec<(PathBuf, u64)> = Vec::new();
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    for entry in builder.build().flatten() {
+        let Some(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            let depth = entry.depth();
+            if depth > 0 && args.large_dirs_depth.is_none_or(|max| depth <= max) {
+                candidates.push(entry.into_path());
+            }
+        } else if file_type.is_file() {
+            let size = entry.metadata().map_or(0, |m| m.len());
+            files.push((entry.into_path(), size));
+        }
+    }
+
+    let total = files.iter().map(|(_, size)| size).sum();
+    let sizes = files
+        .par_iter()
+        .fold(HashMap::new, |mut sizes: HashMap<&Path, u64>, (path, size)| {
+            for ancestor in path.ancestors().skip(1).take_while(|a| a.starts_with(root)) {
+                *sizes.entry(ancestor).or_default() += size;
+            }
+            sizes
+        })
+        .reduce(HashMap::new, |mut a, b| {
+            for (path, size) in b {
+                *a.entry(path).or_default() += size;
+            }
+            a
+        });
+
+    // Keep the `count` largest candidates in a min-heap, preferring the
+    // alphabetically first path among directories of equal size.
+    let mut heap: BinaryHeap<Reverse<(u64, Reverse<PathBuf>)>> = BinaryHeap::new();
+    for dir in candidates {
+        let size = sizes.get(dir.as_path()).copied().unwrap_or(0);
+        heap.push(Reverse((size, Reverse(dir))));
+        if heap.len() > args.count {
+            heap.pop();
+        }
+    }
+    let largest =
+        heap.into_sorted_vec().into_iter().map(|Reverse((size, Reverse(dir)))| (dir, size));
+    Ok((total, largest.collect()))
+}
+
+/// Executes the `large-dirs` command.
+pub fn run_large_dirs(args: &LargeDirsArgs) -> anyhow::Result<()> {
+    if !args.path.is_dir() {
+        anyhow::bail!("'{}' is not a directory.", args.path.display());
+    }
+
+    let (total, largest) = find_largest_dirs(&args.path, args)?;
+    let percent = |size: u64| if total == 0 { 0.0 } else { size as f64 * 100.0 / total as f64 };
+    let relative = |dir: &Path| dir.strip_prefix(&args.path).unwrap_or(dir).display().to_string();
+
+    let mut stdout = io::stdout();
+    if args.large_dirs_json {
+        let dirs: Vec<serde_json::Value> = largest
+            .iter()
+            .map(|(dir, size)| {
+                serde_json::json!({ "path": relative(dir), "size": size, "percent": percent(*size) })
+            })
+            .collect();
+        let report = serde_json::json!({ "root": args.path, "total": total, "dirs": dirs });
+        _ = writeln!(stdout, "{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    _ = writeln!(stdout, "{:>10}  {:>6}  DIRECTORY", "SIZE", "%");
+    for (dir, size) in &largest {
+        let line = format!(
+            "{:>10}  {:>5.1}%  {}",
+            utils::format_size(*size),
+            percent(*size),
+            relative(dir)
+        );
+        if writeln!(stdout, "{line}").is_err() {
+            return Ok(());
+        }
+    }
+    _ = writeln!(stdout, "\nTotal: {}", utils::format_size(total));
+    Ok(())
+}
+
+/// The label `list-extensions` uses for files without an extension.
+const NO_EXTENSION_LABEL: &str = "[none]";
+
+/// Counts the files and bytes per lowercase extension below `root`.
+///
+/// Returns `(label, (files, bytes))` pairs sorted by file count, most frequent
+/// first, with ties in label order. Files without an extension are only
+/// counted with `--list-extensions-include-no-ext`.
+pub fn list_extensions(
+    root: &Path,
+    args: &ListExtArgs,
+) -> anyhow::Result<Vec<(String, (usize, u64))>> {
+    let mut builder = WalkBuilder::new(root);
+    builder.hidden(!args.all).git_ignore(args.gitignore).max_depth(args.level);
+
+    let mut totals: HashMap<String, (usize, u64)> = HashMap::new();
+    for entry in builder.build().flatten() {
+        if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+            continue;
+        }
+        let label = match extension_of(entry.path()) {
+            Some(ext) => format!(".{ext}"),
+            None if args.list_extensions_include_no_ext => NO_EXTENSION_LABEL.to_string(),
+            None => continue,
+        };
+        let size = if args.list_extensions_size || args.list_extensions_json {
+            entry.metadata().map_or(0, |m| m.len())
+        } else {
+            0
+        };
+        let total = totals.entry(label).or_default();
+        total.0 += 1;
+        total.1 += size;
+    }
+
+    let mut extensions: Vec<(String, (usize, u64))> = totals.into_iter().collect();
+    extensions.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then_with(|| a.0.cmp(&b.0)));
+    Ok(extensions)
+}
+
+/// Executes the `list-extensions` command.
+pub fn run_list_extensions(args: &ListExtArgs) -> anyhow::Result<()> {
+    if !args.path.is_dir() {
+        anyhow::bail!("'{}' is not a directory.", args.path.display());
+    }
+
+    let extensions = list_extensions(&args.path, args)?;
+    let mut stdout = io::stdout();
+    if args.list_extensions_json {
+        let report: Vec<serde_json::Value> = extensions
+            .iter()
+            .map(|(label, (count, bytes))| {
+                serde_json::json!({ "extension": label, "count": count, "size": bytes })
+            })
+            .collect();
+        _ = writeln!(stdout, "{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    for line in format_extension_list(&extensions, args.list_extensions_size) {
+        if writeln!(stdout, "{line}").is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Formats the extensions as aligned `  .rs   (47)` lines, optionally with sizes.
+pub fn format_extension_list(
+    extensions: &[(String, (usize, u64))],
+    with_size: bool,
+) -> Vec<String> {
+    let label_width = extensions.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+    let count_width =
+        extensions.iter().map(|(_, (count, _))| count.to_string().len()).max().unwrap_or(0);
+    extensions
+        .iter()
+        .map(|(label, (count, bytes))| {
+            let line = format!("  {label:<label_width$}  ({count:>count_width$})");
+            if with_size {
+                format!("{line}  {:>10}", utils::format_size(*bytes))
+            } else {
+                line
+            }
+        })
+        .collect()
+}
+
+/// The character used for files without an extension.
+#[cfg(unix)]
+const UNKNOWN_CELL: char = '·';
+
+/// The maximum number of blank cells drawn for a single gap between inodes.
+#[cfg(unix)]
+const MAX_GAP_CELLS: u64 = 8;
+
+/// Colors assigned to extensions, picked by a stable hash of the extension.
+#[cfg(unix)]
+const PALETTE: [Color; 6] =
+    [Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::Magenta, Color::Cyan];
+
+/// Renders files as a grid ordered by inode number to visualize fragmentation.
+///
+/// Each cell holds the initial of a file's extension. Runs of consecutive inodes
+/// are drawn next to each other, while gaps in the inode numbering are drawn as
+/// blank cells (capped at `MAX_GAP_CELLS` per gap).
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy)]
+pub struct InodesMap {
+    /// How many files (and inode numbers, for gaps) a single cell represents.
+    pub files_per_cell: usize,
+}
+
+#[cfg(unix)]
+impl Default for InodesMap {
+    fn default() -> Self {
+        Self { files_per_cell: 1 }
+    }
+}
+
+#[cfg(unix)]
+impl InodesMap {
+    /// Renders the map as rows of at most `width` cells.
+    pub fn render(&self, entries: &[(u64, PathBuf)], width: usize) -> Vec<String> {
+        let scale = self.files_per_cell.max(1);
+        let width = width.max(1);
+
+        let mut sorted: Vec<&(u64, PathBuf)> = entries.iter().collect();
+        sorted.sort_by_key(|(inode, _)| *inode);
+
+        let mut cells: Vec<Option<(char, Color)>> = Vec::new();
+        let mut previous: Option<u64> = None;
+        for chunk in sorted.chunks(scale) {
+            let (inode, path) = chunk[0];
+            if let Some(prev) = previous {
+                let gap = inode.saturating_sub(prev).saturating_sub(1) / scale as u64;
+                cells.extend((0..gap.min(MAX_GAP_CELLS)).map(|_| None));
+            }
+            cells.push(Some(Self::cell_for(path)));
+            previous = Some(chunk[chunk.len() - 1].0);
+        }
+
+        cells
+            .chunks(width)
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        Some((c, color)) => c.to_string().color(*color).to_string(),
+                        None => " ".to_string(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Renders the legend mapping each character to the extensions it stands for.
+    pub fn legend(entries: &[(u64, PathBuf)]) -> Vec<String> {
+        let mut extensions: BTreeMap<String, (char, Color)> = BTreeMap::new();
+        for (_, path) in entries {
+            let label = extension_of(path).map_or("(none)".to_string(), |ext| format!(".{ext}"));
+            extensions.entry(label).or_insert_with(|| Self::cell_for(path));
+        }
+        extensions
+            .into_iter()
+            .map(|(label, (c, color))| format!("  {}  {label}", c.to_string().color(color)))
+            .collect()
+    }
+
+    /// Returns the character and color representing a file.
+    fn cell_for(path: &Path) -> (char, Color) {
+        match extension_of(path) {
+            Some(ext) => {
+                let initial = ext.chars().next().unwrap_or(UNKNOWN_CELL);
+                let hash = ext.bytes().fold(0usize, |acc, b| acc.wrapping_mul(31) + b as usize);
+                (initial, PALETTE[hash % PALETTE.len()])
+            }
+            None => (UNKNOWN_CELL, Color::BrightBlack),
+        }
+    }
+}
+
+/// Returns the lowercase extension of a path, if any.
+fn extension_of(path: &Path) -> Option<String> {
+    path.extension().map(|ext| ext.to_string_lossy().to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_bar() {
+        assert_eq!(render_bar(0, 10, 4), "░░░░");
+        assert_eq!(render_bar(10, 10, 4), "████");
+        assert_eq!(render_bar(5, 10, 4), "██░░");
+        assert_eq!(render_bar(3, 10, 4), "█░░░");
+        assert_eq!(render_bar(4, 10, 4), "█▓░░");
+        assert_eq!(render_bar(0, 0, 3), "░░░");
+        assert_eq!(render_bar(20, 10, 2), "██");
+    }
+
+    #[test]
+    fn test_extension_chart() {
+        let files = [
+            (Path::new("a.rs"), 100),
+            (Path::new("b.RS"), 100),
+            (Path::new("c.toml"), 1024),
+            (Path::new("Makefile"), 10),
+        ];
+        let stats = compute_extension_stats(files);
+        assert_eq!(stats[".rs"], (2, 200));
+        assert_eq!(stats["(none)"], (1, 10));
+
+        let by_count = render_extension_chart(&stats, false, 0);
+        assert_eq!(by_count[0], "     .rs │██████████│ 2 files (200 B)");
+        assert_eq!(by_count[1], "  (none) │█████░░░░░│ 1 files (10 B)");
+        assert_eq!(by_count[2], "   .toml │█████░░░░░│ 1 files (1.0 KiB)");
+
+        let by_size = render_extension_chart(&stats, true, 0);
+        assert!(by_size[0].starts_with("   .toml │██████████│"));
+    }
+
+    #[test]
+    fn test_list_extensions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let files = [
+            "a.rs",
+            "b.rs",
+            "src/c.RS",
+            "src/d.rs",
+            "e.md",
+            "f.md",
+            "g.md",
+            "h.toml",
+            "i.toml",
+            "src/j.json",
+            "Makefile",
+            "src/deep/k.json",
+        ];
+        for file in files {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "12345").unwrap();
+        }
+
+        let args = ListExtArgs::default();
+        let extensions = list_extensions(root, &args).unwrap();
+        let counts: Vec<(&str, usize)> =
+            extensions.iter().map(|(label, (count, _))| (label.as_str(), *count)).collect();
+        assert_eq!(counts, [(".rs", 4), (".md", 3), (".json", 2), (".toml", 2)]);
+
+        let args = ListExtArgs {
+            level: Some(1),
+            list_extensions_include_no_ext: true,
+            list_extensions_size: true,
+            ..Default::default()
+        };
+        let extensions = list_extensions(root, &args).unwrap();
+        assert_eq!(
+            extensions,
+            [
+                (".md".to_string(), (3, 15)),
+                (".rs".to_string(), (2, 10)),
+                (".toml".to_string(), (2, 10)),
+                ("[none]".to_string(), (1, 5)),
+            ]
+        );
+        let lines = format_extension_list(&extensions, false);
+        assert_eq!(lines[0], "  .md     (3)");
+        assert_eq!(lines[3], "  [none]  (1)");
+    }
+
+    #[test]
+    fn test_find_largest_dirs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        for (path, size) in
+            [("big/a.bin", 1000), ("big/nested/b.bin", 2000), ("small/c.bin", 100), ("d.bin", 50)]
+        {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, vec![0u8; size]).unwrap();
+        }
+        std::fs::create_dir(root.join("empty")).unwrap();
+
+        let args = LargeDirsArgs { count: 2, ..Default::default() };
+        let (total, largest) = find_largest_dirs(root, &args).unwrap();
+        assert_eq!(total, 3150);
+        assert_eq!(largest, vec![(root.join("big"), 3000), (root.join("big/nested"), 2000)]);
+
+        let args = LargeDirsArgs { count: 10, large_dirs_depth: Some(1), ..Default::default() };
+        let (_, largest) = find_largest_dirs(root, &args).unwrap();
+        assert_eq!(
+            largest,
+            vec![(root.join("big"), 3000), (root.join("small"), 100), (root.join("empty"), 0)]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_inode_map_render() {
+        colored::control::set_override(false);
+        let entries = vec![
+            (12, PathBuf::from("c.rs")),
+            (10, PathBuf::from("a.rs")),
+            (11, PathBuf::from("b.toml")),
+            (15, PathBuf::from("README")),
+            (1000, PathBuf::from("far.md")),
+        ];
+
+        // 10..12 are contiguous, 15 leaves a gap of two, 1000 a capped gap.
+        let map = InodesMap::default();
+        assert_eq!(map.render(&entries, 80), vec!["rtr  ·        m"]);
+        assert_eq!(map.render(&entries, 4), vec!["rtr ", " ·  ", "    ", "  m"]);
+
+        let scaled = InodesMap { files_per_cell: 2 };
+        assert_eq!(scaled.render(&entries, 80), vec!["rr        m"]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_inode_map_legend() {
+        colored::control::set_override(false);
+        let entries = vec![(1, PathBuf::from("a.rs")), (2, PathBuf::from("LICENSE"))];
+        assert_eq!(InodesMap::legend(&entries), vec!["  ·  (none)", "  r  .rs"]);
+    }
+}
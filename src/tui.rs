@@ -3,40 +3,70 @@
 //! This module contains all logic for running `fstree` in an interactive
 //! session, including state management, event handling, and rendering.
 
-use crate::app::InteractiveArgs;
-use crate::git::{self, StatusCache};
+use crate::app::{ColorMode, InteractiveArgs};
+use crate::colortest::{self, Cell, Swatch};
+use crate::config::{self, KeyBinding};
+use crate::git::{self, GitRepoStatus};
 use crate::icons;
 use crate::sort;
 use crate::utils;
+use crate::view;
 use ignore::WalkBuilder;
 use lscolors::{Color as LsColor, LsColors, Style as LsStyle};
+use notify::{RecursiveMode, Watcher};
 use ratatui::crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, MouseButton,
+        MouseEventKind,
     },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::{Backend, CrosstermBackend},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{List, ListItem, ListState},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Row, Table},
     Frame, Terminal,
 };
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
-use std::io::{stderr, stdout, IsTerminal, Write};
-use std::path::{Path, PathBuf};
+use std::io::{stderr, stdout, IsTerminal, Read, Write};
+use std::path::{Component, Path, PathBuf};
 use std::process::Command;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 // Platform-specific import for unix permissions
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 
+/// Reduces an RGB color to the color depth selected by --color-mode.
+fn apply_color_mode(color: Color, mode: ColorMode) -> Color {
+    match (color, mode) {
+        (Color::Rgb(r, g, b), ColorMode::Ansi8) => map_color(utils::nearest_basic_color(r, g, b)),
+        (Color::Rgb(r, g, b), ColorMode::Ansi256) => {
+            Color::Indexed(utils::nearest_ansi256_index(r, g, b))
+        }
+        _ => color,
+    }
+}
+
 /// Converts an lscolors::Style to a ratatui::style::Style
-fn to_ratatui_style(ls_style: LsStyle) -> Style {
+///
+/// A `--highlight-special` permission sets the background color.
+fn to_ratatui_style(
+    ls_style: LsStyle,
+    color_mode: ColorMode,
+    highlight: Option<utils::PermissionHighlight>,
+) -> Style {
     let mut style = Style::default();
+    if let Some(highlight) = highlight {
+        style = style.bg(map_color(highlight.color()));
+    }
 
     if let Some(fg) = ls_style.foreground {
         style = style.fg(match fg {
@@ -57,7 +87,7 @@ fn to_ratatui_style(ls_style: LsStyle) -> Style {
             LsColor::BrightCyan => Color::LightCyan,
             LsColor::BrightWhite => Color::White,
             LsColor::Fixed(n) => Color::Indexed(n),
-            LsColor::RGB(r, g, b) => Color::Rgb(r, g, b),
+            LsColor::RGB(r, g, b) => apply_color_mode(Color::Rgb(r, g, b), color_mode),
         });
     }
 
@@ -74,10 +104,13 @@ fn to_ratatui_style(ls_style: LsStyle) -> Style {
     style
 }
 
+#[derive(Debug, PartialEq)]
 enum PostExitAction {
     None,
     OpenFile(PathBuf),
     PrintPath(PathBuf),
+    /// Prints each marked path, with `Ctrl+s` after selecting entries with `Space`.
+    PrintPaths(Vec<PathBuf>),
 }
 
 #[derive(Debug, Clone)]
@@ -89,20 +122,218 @@ struct FileEntry {
     size: Option<u64>,
     permissions: Option<String>,
     git_status: Option<git::FileStatus>,
+    commit_count: Option<usize>,
+    diff_stat: Option<git::DiffStat>,
+    /// Number of entries listed directly under a directory.
+    child_count: usize,
+    /// The most recent tag reachable from the file's last commit.
+    tag: Option<String>,
+    /// Whether the file still contains Git conflict markers.
+    has_conflict: bool,
+    is_symlink: bool,
+    /// Where the symlink points, with `--dereference`.
+    symlink_target: Option<PathBuf>,
+    /// The `user:group` label, with `--owner`, padded to the widest label.
+    owner: Option<String>,
+    /// The inode number, with `--inode`.
+    inode: Option<u64>,
+    /// The number of hard links, with `--nlinks`.
+    nlinks: Option<u64>,
+    /// The special permission marked with `--highlight-special`.
+    highlight: Option<utils::PermissionHighlight>,
+}
+
+impl FileEntry {
+    /// Creates an entry with none of the optional columns filled in.
+    fn new(path: PathBuf, depth: usize, is_dir: bool) -> Self {
+        Self {
+            path,
+            depth,
+            is_dir,
+            is_expanded: false,
+            size: None,
+            permissions: None,
+            git_status: None,
+            commit_count: None,
+            diff_stat: None,
+            child_count: 0,
+            tag: None,
+            has_conflict: false,
+            is_symlink: false,
+            symlink_target: None,
+            owner: None,
+            inode: None,
+            nlinks: None,
+            highlight: None,
+        }
+    }
+}
+
+impl sort::Sortable for FileEntry {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn file_name(&self) -> &std::ffi::OsStr {
+        self.path.file_name().unwrap_or(self.path.as_os_str())
+    }
+
+    fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    fn metadata(&self) -> Option<fs::Metadata> {
+        fs::symlink_metadata(&self.path).ok()
+    }
+}
+
+/// An interactive-mode command that can be remapped in `[keybindings]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Action {
+    Next,
+    Previous,
+    Toggle,
+    Quit,
+    Search,
+    PrintPath,
+    CycleSort,
+    TogglePreview,
+    Mark,
+    ExpandAll,
+    CollapseAll,
+    GoUp,
+    GoTo,
+    Delete,
+    Rename,
+    New,
+    ScrollLeft,
+    ScrollRight,
+    Help,
+    SetBookmark,
+    JumpToBookmark,
+}
+
+/// Each action with its `[keybindings]` name and default keys.
+const DEFAULT_KEYS: &[(Action, &str, &[&str])] = &[
+    (Action::Next, "next", &["down", "j"]),
+    (Action::Previous, "previous", &["up", "k"]),
+    (Action::Toggle, "toggle", &["enter"]),
+    (Action::Quit, "quit", &["q", "esc"]),
+    (Action::Search, "search", &["/"]),
+    (Action::PrintPath, "print-path", &["ctrl+s"]),
+    (Action::CycleSort, "cycle-sort", &["S"]),
+    (Action::TogglePreview, "toggle-preview", &["tab"]),
+    (Action::Mark, "mark", &["space"]),
+    (Action::ExpandAll, "expand-all", &["E"]),
+    (Action::CollapseAll, "collapse-all", &["C"]),
+    (Action::GoUp, "go-up", &["backspace"]),
+    (Action::GoTo, "goto", &["g"]),
+    (Action::Delete, "delete", &["d"]),
+    (Action::Rename, "rename", &["r"]),
+    (Action::New, "new", &["n"]),
+    (Action::ScrollLeft, "scroll-left", &["shift+left"]),
+    (Action::ScrollRight, "scroll-right", &["shift+right"]),
+    (Action::Help, "help", &["?"]),
+    (Action::SetBookmark, "set-bookmark", &["m"]),
+    (Action::JumpToBookmark, "jump-to-bookmark", &["'"]),
+];
+
+/// Builds the map from keys to actions.
+///
+/// An action named in `bindings` gets that key in place of its defaults, and
+/// takes the key over from any action it is a default of.
+fn keymap(bindings: &HashMap<String, String>) -> anyhow::Result<HashMap<KeyBinding, Action>> {
+    if let Some(name) = bindings.keys().find(|name| !DEFAULT_KEYS.iter().any(|(_, n, _)| n == name))
+    {
+        anyhow::bail!("Unknown action '{name}' in [keybindings]");
+    }
+    let mut keymap = HashMap::new();
+    for &(action, name, keys) in
+        DEFAULT_KEYS.iter().filter(|(_, name, _)| !bindings.contains_key(*name))
+    {
+        for key in keys {
+            let key = config::parse_key(key).map_err(|e| anyhow::anyhow!("{e} for '{name}'"))?;
+            keymap.insert(key, action);
+        }
+    }
+    for &(action, name, _) in DEFAULT_KEYS {
+        if let Some(key) = bindings.get(name) {
+            let key = config::parse_key(key)
+                .map_err(|e| anyhow::anyhow!("{e} for '{name}' in [keybindings]"))?;
+            keymap.insert(key, action);
+        }
+    }
+    Ok(keymap)
+}
+
+/// What the keyboard currently drives, besides the search and go-to bars.
+#[derive(Debug, Clone, PartialEq)]
+enum Mode {
+    Normal,
+    /// Waiting for `y` before deleting the path.
+    ConfirmDelete(PathBuf),
+    /// Editing a new name for `original`.
+    RenameInput {
+        original: PathBuf,
+        buffer: String,
+    },
+    /// Typing the name of a file or directory to create.
+    NewEntryInput(String),
+    /// Showing the list of keybindings.
+    ShowHelp,
+    /// Waiting for the letter to bookmark the selected entry under, after `m`.
+    SetBookmark,
+    /// Waiting for the letter of the bookmark to jump to, after `'`.
+    JumpToBookmark,
+    /// Showing the bookmarks, after `'?`.
+    ShowBookmarks,
 }
 
 struct AppState {
     master_entries: Vec<FileEntry>,
     visible_entries: Vec<FileEntry>,
     list_state: ListState,
+    current_sort: sort::SortOptions,
+    /// The search filter, set once `/` is pressed.
+    search_string: Option<String>,
+    /// Whether keystrokes are being typed into the search filter.
+    is_searching: bool,
+    /// Whether the preview pane is shown.
+    show_preview: bool,
+    /// The preview of the selected file; `None` for directories and unreadable files.
+    preview_content: Option<String>,
+    /// The path and line count the preview was built for.
+    preview_key: Option<(PathBuf, usize)>,
+    /// The entries marked with `Space`.
+    selected_paths: HashSet<PathBuf>,
+    /// The directory containing the selected entry, shown in the header bar.
+    current_dir: PathBuf,
+    /// A message shown in the status bar until the next key press.
+    message: Option<String>,
+    /// The path being typed after `g`.
+    goto_input: Option<String>,
+    /// The directory the tree was scanned from.
+    root_path: PathBuf,
+    /// The `--virtual-root` label shown for the root in the header.
+    display_root: Option<String>,
+    mode: Mode,
+    /// The action of each key in normal mode.
+    keymap: HashMap<KeyBinding, Action>,
+    /// Where the list was last drawn, to map mouse clicks to entries.
+    list_area: Rect,
+    /// The row index and time of the last click, to detect double-clicks.
+    last_click: Option<(usize, Instant)>,
+    /// The number of columns the entries are scrolled to the left by.
+    scroll_x: usize,
+    /// Paths bookmarked with `m` for this session, by letter.
+    bookmarks: HashMap<char, PathBuf>,
 }
 
 impl AppState {
     fn new(args: &InteractiveArgs, root_path: &Path) -> anyhow::Result<Self> {
-        let git_repo_status = if args.git_status { git::load_status(root_path)? } else { None };
+        let mut git_repo_status = if args.git_status { git::load_status(root_path)? } else { None };
 
-        let status_info = git_repo_status.as_ref().map(|s| (&s.cache, &s.root));
-        let mut master_entries = scan_directory(root_path, status_info, args)?;
+        let mut master_entries = scan_directory(root_path, git_repo_status.as_mut(), args)?;
 
         if let Some(expand_level) = args.expand_level {
             for entry in &mut master_entries {
@@ -112,32 +343,445 @@ impl AppState {
             }
         }
 
-        let mut app_state =
-            Self { master_entries, visible_entries: Vec::new(), list_state: ListState::default() };
+        let mut app_state = Self {
+            master_entries,
+            visible_entries: Vec::new(),
+            list_state: ListState::default(),
+            current_sort: args.to_sort_options(),
+            search_string: None,
+            is_searching: false,
+            show_preview: args.preview,
+            preview_content: None,
+            preview_key: None,
+            selected_paths: HashSet::new(),
+            current_dir: root_path.to_path_buf(),
+            message: None,
+            goto_input: None,
+            root_path: root_path.to_path_buf(),
+            display_root: args.display_root.clone(),
+            mode: Mode::Normal,
+            keymap: keymap(&HashMap::new())?,
+            list_area: Rect::default(),
+            last_click: None,
+            scroll_x: 0,
+            bookmarks: HashMap::new(),
+        };
         app_state.regenerate_visible_entries();
         if !app_state.visible_entries.is_empty() {
             app_state.list_state.select(Some(0));
         }
+        app_state.update_current_dir();
         Ok(app_state)
     }
 
     fn regenerate_visible_entries(&mut self) {
+        if self.search_string.as_ref().is_some_and(|search| !search.is_empty()) {
+            self.filter_visible_entries();
+            return;
+        }
         self.visible_entries.clear();
-        let mut parent_expanded_stack: Vec<bool> = Vec::new();
+        // Tracks (depth, is_expanded) of the ancestors of the current entry. Depths
+        // are compared rather than counted since `--min-depth` hides shallow ancestors.
+        let mut parent_expanded_stack: Vec<(usize, bool)> = Vec::new();
         for entry in &self.master_entries {
-            while parent_expanded_stack.len() >= entry.depth {
+            while parent_expanded_stack.last().is_some_and(|&(depth, _)| depth >= entry.depth) {
                 parent_expanded_stack.pop();
             }
-            if parent_expanded_stack.iter().all(|&x| x) {
+            if parent_expanded_stack.iter().all(|&(_, expanded)| expanded) {
                 self.visible_entries.push(entry.clone());
             }
             if entry.is_dir {
-                parent_expanded_stack.push(entry.is_expanded);
+                parent_expanded_stack.push((entry.depth, entry.is_expanded));
+            }
+        }
+    }
+
+    /// Shows every entry, expanded or not, whose name contains the search string.
+    ///
+    /// The match ignores case. The selection moves to the first match.
+    fn filter_visible_entries(&mut self) {
+        let search = self.search_string.as_deref().unwrap_or_default().to_lowercase();
+        self.visible_entries = self
+            .master_entries
+            .iter()
+            .filter(|entry| {
+                entry
+                    .path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().to_lowercase().contains(&search))
+            })
+            .cloned()
+            .collect();
+        self.list_state.select((!self.visible_entries.is_empty()).then_some(0));
+    }
+
+    /// Replaces the search filter and refreshes the list.
+    ///
+    /// Clearing the filter returns to the tree, keeping the cursor on the same path.
+    fn set_search(&mut self, search_string: Option<String>) {
+        let selected_path = self.get_selected_entry().map(|e| e.path.clone());
+        self.search_string = search_string;
+        self.regenerate_visible_entries();
+        if self.search_string.as_ref().is_none_or(|search| search.is_empty()) {
+            let index = selected_path
+                .and_then(|path| self.visible_entries.iter().position(|e| e.path == path));
+            let fallback = (!self.visible_entries.is_empty()).then_some(0);
+            self.list_state.select(index.or(fallback));
+        }
+        self.update_current_dir();
+    }
+
+    /// Handles a key typed while the search bar is active.
+    fn handle_search_key(&mut self, code: KeyCode) {
+        let mut search = self.search_string.clone().unwrap_or_default();
+        match code {
+            KeyCode::Esc => {
+                self.is_searching = false;
+                self.set_search(None);
+                return;
+            }
+            KeyCode::Enter => {
+                self.is_searching = false;
+                return;
+            }
+            KeyCode::Backspace => {
+                search.pop();
+            }
+            KeyCode::Char(c) => search.push(c),
+            _ => return,
+        }
+        self.set_search(Some(search));
+    }
+
+    /// Rescans the tree from the root, as `--watch` does after a filesystem change.
+    ///
+    /// Directories that still exist keep their expansion, and the cursor stays on
+    /// the same path when it is still there.
+    fn refresh(&mut self, args: &InteractiveArgs) -> anyhow::Result<()> {
+        let mut git_repo_status =
+            if args.git_status { git::load_status(&self.root_path)? } else { None };
+        let mut master_entries = scan_directory(&self.root_path, git_repo_status.as_mut(), args)?;
+        let expanded: HashSet<&Path> = self
+            .master_entries
+            .iter()
+            .filter(|e| e.is_expanded)
+            .map(|e| e.path.as_path())
+            .collect();
+        for entry in &mut master_entries {
+            entry.is_expanded = expanded.contains(entry.path.as_path());
+        }
+        self.master_entries = master_entries;
+        let existing: HashSet<&Path> =
+            self.master_entries.iter().map(|e| e.path.as_path()).collect();
+        self.selected_paths.retain(|path| existing.contains(path.as_path()));
+        self.preview_key = None;
+        // Re-sorting with the current order also restores the cursor.
+        self.resort(self.current_sort.clone());
+        Ok(())
+    }
+
+    /// Selects the entry on a clicked terminal row.
+    ///
+    /// A second click on the same entry within `DOUBLE_CLICK_INTERVAL` toggles a
+    /// directory, or returns a file to open.
+    fn click(&mut self, row: u16, now: Instant) -> Option<PathBuf> {
+        let area = self.list_area;
+        if !(area.y..area.y + area.height).contains(&row) {
+            return None;
+        }
+        let index = self.list_state.offset() + usize::from(row - area.y);
+        let entry = self.visible_entries.get(index)?;
+        let (path, is_dir) = (entry.path.clone(), entry.is_dir);
+        let is_double_click = self.last_click.is_some_and(|(last_index, last_time)| {
+            last_index == index && now.duration_since(last_time) <= DOUBLE_CLICK_INTERVAL
+        });
+        self.list_state.select(Some(index));
+        self.update_current_dir();
+        if !is_double_click {
+            self.last_click = Some((index, now));
+            return None;
+        }
+        self.last_click = None;
+        if is_dir {
+            self.toggle_selected_directory();
+            None
+        } else {
+            Some(path)
+        }
+    }
+
+    /// Handles a key typed while the go-to bar is active.
+    fn handle_goto_key(&mut self, code: KeyCode) {
+        let Some(input) = self.goto_input.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Esc => self.goto_input = None,
+            KeyCode::Enter => {
+                let input = self.goto_input.take().unwrap_or_default();
+                if !self.goto(Path::new(&input)) {
+                    self.message = Some(format!("Not in the tree: {input}"));
+                }
+            }
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+    }
+
+    /// Moves the cursor to `path`, expanding its ancestors and clearing any search.
+    ///
+    /// A relative path is resolved against the root. Returns `false` if the
+    /// path is not in the tree.
+    fn goto(&mut self, path: &Path) -> bool {
+        // `.` and `..` are resolved lexically, since the tree's paths keep their symlinks.
+        let mut target = PathBuf::new();
+        for component in self.root_path.join(path).components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    target.pop();
+                }
+                _ => target.push(component),
             }
         }
+        if !self.master_entries.iter().any(|e| e.path == target) {
+            return false;
+        }
+        for entry in &mut self.master_entries {
+            if entry.is_dir && target.starts_with(&entry.path) && entry.path != target {
+                entry.is_expanded = true;
+            }
+        }
+        self.search_string = None;
+        self.regenerate_visible_entries();
+        let index = self.visible_entries.iter().position(|e| e.path == target);
+        self.list_state.select(index);
+        self.update_current_dir();
+        true
+    }
+
+    /// Passes a key to the handler of the current mode.
+    ///
+    /// With `refresh_git_status`, the Git statuses are reloaded after a rename.
+    fn handle_mode_key(&mut self, key: KeyBinding, refresh_git_status: bool) {
+        let code = key.0;
+        match self.mode {
+            Mode::Normal => {}
+            // The help key or Esc closes the help; other keys are ignored.
+            Mode::ShowHelp => {
+                if code == KeyCode::Esc || self.keymap.get(&key) == Some(&Action::Help) {
+                    self.mode = Mode::Normal;
+                }
+            }
+            Mode::SetBookmark | Mode::JumpToBookmark => self.handle_bookmark_key(code),
+            Mode::ShowBookmarks => self.mode = Mode::Normal,
+            Mode::ConfirmDelete(_) => self.handle_confirm_delete_key(code),
+            Mode::RenameInput { .. } => self.handle_rename_key(code, refresh_git_status),
+            Mode::NewEntryInput(_) => self.handle_new_entry_key(code),
+        }
+    }
+
+    /// Handles the letter typed after `m` or `'`. After `'`, `?` lists the bookmarks.
+    fn handle_bookmark_key(&mut self, code: KeyCode) {
+        let mode = std::mem::replace(&mut self.mode, Mode::Normal);
+        let letter = match code {
+            KeyCode::Char('?') if mode == Mode::JumpToBookmark => {
+                self.mode = Mode::ShowBookmarks;
+                return;
+            }
+            KeyCode::Char(c) if c.is_ascii_lowercase() => c,
+            _ => return,
+        };
+        if mode == Mode::SetBookmark {
+            if let Some(entry) = self.get_selected_entry() {
+                self.bookmarks.insert(letter, entry.path.clone());
+                self.message = Some(format!("Bookmark '{letter}' set"));
+            }
+            return;
+        }
+        match self.bookmarks.get(&letter).cloned() {
+            Some(path) if self.goto(&path) => {}
+            Some(_) => self.message = Some(format!("Bookmark '{letter}' is no longer in the tree")),
+            None => self.message = Some(format!("No bookmark '{letter}'")),
+        }
+    }
+
+    /// Handles a key pressed while the delete confirmation is shown: `y` deletes,
+    /// anything else cancels.
+    fn handle_confirm_delete_key(&mut self, code: KeyCode) {
+        let Mode::ConfirmDelete(path) = std::mem::replace(&mut self.mode, Mode::Normal) else {
+            return;
+        };
+        if code == KeyCode::Char('y') {
+            if let Err(e) = self.delete(&path) {
+                self.message = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Handles a key typed while renaming. `Enter` renames, `Esc` cancels.
+    ///
+    /// With `refresh_git_status`, the Git statuses are reloaded after a rename.
+    fn handle_rename_key(&mut self, code: KeyCode, refresh_git_status: bool) {
+        let Mode::RenameInput { original, buffer } = &mut self.mode else {
+            return;
+        };
+        match code {
+            KeyCode::Esc => self.mode = Mode::Normal,
+            KeyCode::Enter => {
+                let (original, new_name) = (original.clone(), buffer.clone());
+                self.mode = Mode::Normal;
+                let result = self.rename(&original, &new_name).and_then(|()| {
+                    if refresh_git_status {
+                        self.refresh_git_status()?;
+                    }
+                    Ok(())
+                });
+                if let Err(e) = result {
+                    self.message = Some(e.to_string());
+                }
+            }
+            KeyCode::Backspace => {
+                buffer.pop();
+            }
+            KeyCode::Char(c) => buffer.push(c),
+            _ => {}
+        }
+    }
+
+    /// Renames an entry within its directory and updates its path, and those of
+    /// any entries under it, in the tree.
+    fn rename(&mut self, original: &Path, new_name: &str) -> anyhow::Result<()> {
+        if new_name.is_empty() || new_name == "." || new_name == ".." {
+            anyhow::bail!("Invalid name '{new_name}'");
+        }
+        if new_name.contains(std::path::is_separator) {
+            anyhow::bail!("Invalid name '{new_name}': it must not contain path separators");
+        }
+        let renamed = original.with_file_name(new_name);
+        fs::rename(original, &renamed)
+            .map_err(|e| anyhow::anyhow!("Failed to rename '{}': {e}", original.display()))?;
+
+        let moved = |path: &Path| Some(renamed.join(path.strip_prefix(original).ok()?));
+        for entry in &mut self.master_entries {
+            if let Some(path) = moved(&entry.path) {
+                entry.path = path;
+            }
+        }
+        self.selected_paths =
+            self.selected_paths.drain().map(|path| moved(&path).unwrap_or(path)).collect();
+        let selected = self.list_state.selected();
+        self.regenerate_visible_entries();
+        self.list_state.select(selected);
+        self.preview_key = None;
+        self.update_current_dir();
+        Ok(())
+    }
+
+    /// Handles a key typed while naming a new entry. `Enter` creates it, `Esc` cancels.
+    fn handle_new_entry_key(&mut self, code: KeyCode) {
+        let Mode::NewEntryInput(buffer) = &mut self.mode else {
+            return;
+        };
+        match code {
+            KeyCode::Esc => self.mode = Mode::Normal,
+            KeyCode::Enter => {
+                let input = buffer.clone();
+                self.mode = Mode::Normal;
+                if let Err(e) = self.create_entry(&input) {
+                    self.message = Some(e.to_string());
+                }
+            }
+            KeyCode::Backspace => {
+                buffer.pop();
+            }
+            KeyCode::Char(c) => buffer.push(c),
+            _ => {}
+        }
+    }
+
+    /// Creates a file, or a directory if `input` ends with `/`, inside the selected
+    /// directory or else the root, and moves the cursor onto it.
+    fn create_entry(&mut self, input: &str) -> anyhow::Result<()> {
+        let (name, is_dir) = match input.strip_suffix('/') {
+            Some(name) => (name, true),
+            None => (input, false),
+        };
+        if name.is_empty() || name == "." || name == ".." {
+            anyhow::bail!("Invalid name '{input}'");
+        }
+        if name.contains(std::path::is_separator) {
+            anyhow::bail!("Invalid name '{input}': it must not contain path separators");
+        }
+        let (parent, depth) = match self.get_selected_entry().filter(|e| e.is_dir) {
+            Some(entry) => (entry.path.clone(), entry.depth + 1),
+            None => (self.root_path.clone(), 1),
+        };
+        let path = parent.join(name);
+        if fs::symlink_metadata(&path).is_ok() {
+            anyhow::bail!("'{}' already exists", path.display());
+        }
+        let result = if is_dir {
+            fs::create_dir_all(&path)
+        } else {
+            fs::OpenOptions::new().write(true).create_new(true).open(&path).map(|_| ())
+        };
+        result.map_err(|e| anyhow::anyhow!("Failed to create '{}': {e}", path.display()))?;
+
+        if let Some(parent) = self.master_entries.iter_mut().find(|e| e.path == parent) {
+            parent.is_expanded = true;
+            parent.child_count += 1;
+        }
+        // The entry goes right after its parent; re-sorting moves it into place.
+        let index = self.master_entries.iter().position(|e| e.path == parent).map_or(0, |i| i + 1);
+        self.master_entries.insert(index, FileEntry::new(path.clone(), depth, is_dir));
+        self.resort(self.current_sort.clone());
+        let index = self.visible_entries.iter().position(|e| e.path == path);
+        self.list_state.select(index);
+        self.update_current_dir();
+        Ok(())
+    }
+
+    /// Reloads the Git status of every entry.
+    fn refresh_git_status(&mut self) -> anyhow::Result<()> {
+        let Some(status) = git::load_status(&self.root_path)? else {
+            return Ok(());
+        };
+        for entry in &mut self.master_entries {
+            entry.git_status = entry
+                .path
+                .strip_prefix(&status.root)
+                .ok()
+                .and_then(|rel_path| status.cache.get(rel_path))
+                .copied();
+        }
+        self.regenerate_visible_entries();
+        Ok(())
+    }
+
+    /// Deletes a file, or a directory with everything in it, and drops it from the tree.
+    fn delete(&mut self, path: &Path) -> anyhow::Result<()> {
+        let is_dir = fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir());
+        let result = if is_dir { fs::remove_dir_all(path) } else { fs::remove_file(path) };
+        result.map_err(|e| anyhow::anyhow!("Failed to delete '{}': {e}", path.display()))?;
+
+        let selected = self.list_state.selected().unwrap_or(0);
+        self.master_entries.retain(|e| !e.path.starts_with(path));
+        self.selected_paths.retain(|p| !p.starts_with(path));
+        self.regenerate_visible_entries();
+        let last = self.visible_entries.len().checked_sub(1);
+        self.list_state.select(last.map(|last| selected.min(last)));
+        self.update_current_dir();
+        Ok(())
     }
 
     fn next(&mut self) {
+        if self.visible_entries.is_empty() {
+            return;
+        }
         let i = match self.list_state.selected() {
             Some(i) => {
                 if i >= self.visible_entries.len() - 1 {
@@ -149,9 +793,13 @@ impl AppState {
             None => 0,
         };
         self.list_state.select(Some(i));
+        self.update_current_dir();
     }
 
     fn previous(&mut self) {
+        if self.visible_entries.is_empty() {
+            return;
+        }
         let i = match self.list_state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -163,12 +811,160 @@ impl AppState {
             None => 0,
         };
         self.list_state.select(Some(i));
+        self.update_current_dir();
+    }
+
+    /// Re-sorts the entries in place with new options, keeping the cursor on the same path.
+    fn resort(&mut self, sort_options: sort::SortOptions) {
+        let selected_path = self.get_selected_entry().map(|e| e.path.clone());
+
+        let mut sort_options = sort_options;
+        sort_options.commit_counts = self
+            .master_entries
+            .iter()
+            .filter_map(|e| Some((e.path.clone(), e.commit_count?)))
+            .collect();
+        sort::sort_entries(&mut self.master_entries, &sort_options);
+        self.current_sort = sort_options;
+        self.regenerate_visible_entries();
+
+        if let Some(path) = selected_path {
+            let index = self.visible_entries.iter().position(|e| e.path == path);
+            self.list_state.select(index.or(Some(0)));
+        }
+        self.update_current_dir();
+    }
+
+    /// Switches to the next sort type in the cycle.
+    fn cycle_sort(&mut self) {
+        let mut sort_options = self.current_sort.clone();
+        sort_options.sort_type = sort_options.sort_type.cycle();
+        self.resort(sort_options);
+    }
+
+    /// Rebuilds the preview when the selection or the pane height has changed.
+    fn update_preview(&mut self, max_lines: usize) {
+        let selected = self.get_selected_entry().map(|e| (e.path.clone(), e.is_dir));
+        let key = selected.as_ref().map(|(path, _)| (path.clone(), max_lines));
+        if key == self.preview_key {
+            return;
+        }
+        self.preview_key = key;
+        self.preview_content = match selected {
+            Some((path, false)) => file_preview(&path, max_lines),
+            _ => None,
+        };
+    }
+
+    /// Marks the entry under the cursor, or unmarks it if it was already marked.
+    fn toggle_selection(&mut self) {
+        let Some(path) = self.get_selected_entry().map(|e| e.path.clone()) else {
+            return;
+        };
+        if !self.selected_paths.remove(&path) {
+            self.selected_paths.insert(path);
+        }
+    }
+
+    /// Returns what `Ctrl+s` prints: the marked paths in order, or else the entry under the cursor.
+    fn print_action(&self) -> Option<PostExitAction> {
+        if self.selected_paths.is_empty() {
+            return self.get_selected_entry().map(|e| PostExitAction::PrintPath(e.path.clone()));
+        }
+        let mut paths: Vec<PathBuf> = self.selected_paths.iter().cloned().collect();
+        paths.sort();
+        Some(PostExitAction::PrintPaths(paths))
     }
 
     fn get_selected_entry(&self) -> Option<&FileEntry> {
         self.list_state.selected().and_then(|i| self.visible_entries.get(i))
     }
 
+    /// Points `current_dir` at the parent of the selected entry.
+    ///
+    /// It is left alone when nothing is selected.
+    fn update_current_dir(&mut self) {
+        if let Some(parent) = self.get_selected_entry().and_then(|e| e.path.parent()) {
+            self.current_dir = parent.to_path_buf();
+        }
+    }
+
+    /// Returns the header bar's segments: the root's name (or its `--virtual-root`
+    /// label), then each directory down to `current_dir`. Empty while the selection
+    /// is a direct child of the root.
+    fn breadcrumb_segments(&self) -> Vec<String> {
+        // The root is the ancestor `depth` levels up, since depth 1 is a direct child of it.
+        let Some(root) = self
+            .get_selected_entry()
+            .filter(|entry| entry.depth > 1)
+            .and_then(|entry| entry.path.ancestors().nth(entry.depth))
+        else {
+            return Vec::new();
+        };
+        let relative = self.current_dir.strip_prefix(root).unwrap_or(&self.current_dir);
+        let root_name = match &self.display_root {
+            Some(label) => Some(label.clone()),
+            None => root.file_name().map(|name| name.to_string_lossy().into_owned()),
+        };
+        root_name
+            .into_iter()
+            .chain(relative.components().map(|c| c.as_os_str().to_string_lossy().into_owned()))
+            .collect()
+    }
+
+    /// Collapses the deepest expanded ancestor of the selected entry and moves the
+    /// cursor onto it, for `Backspace`.
+    fn collapse_to_parent(&mut self) {
+        let Some(selected) = self.get_selected_entry().map(|e| e.path.clone()) else {
+            return;
+        };
+        let Some(index) = selected.ancestors().skip(1).find_map(|ancestor| {
+            self.master_entries.iter().position(|e| e.path == ancestor && e.is_dir && e.is_expanded)
+        }) else {
+            return;
+        };
+        self.master_entries[index].is_expanded = false;
+        let ancestor = self.master_entries[index].path.clone();
+        self.regenerate_visible_entries();
+        if let Some(index) = self.visible_entries.iter().position(|e| e.path == ancestor) {
+            self.list_state.select(Some(index));
+        }
+        self.update_current_dir();
+    }
+
+    /// Expands every directory, keeping the cursor on the same path.
+    ///
+    /// This can be slow on a huge tree, so a warning is shown past
+    /// `EXPAND_ALL_WARN_ENTRIES` entries.
+    fn expand_all(&mut self) {
+        self.set_all_expanded(true);
+        if self.master_entries.len() > EXPAND_ALL_WARN_ENTRIES {
+            self.message = Some(format!(
+                "Expanded {} entries; the tree may be slow to navigate",
+                self.master_entries.len()
+            ));
+        }
+    }
+
+    /// Collapses every directory, keeping the cursor on the same path if it is still shown.
+    fn collapse_all(&mut self) {
+        self.set_all_expanded(false);
+        self.scroll_x = 0;
+    }
+
+    fn set_all_expanded(&mut self, expanded: bool) {
+        let selected_path = self.get_selected_entry().map(|e| e.path.clone());
+        for entry in self.master_entries.iter_mut().filter(|e| e.is_dir) {
+            entry.is_expanded = expanded;
+        }
+        self.regenerate_visible_entries();
+        let index =
+            selected_path.and_then(|path| self.visible_entries.iter().position(|e| e.path == path));
+        let fallback = (!self.visible_entries.is_empty()).then_some(0);
+        self.list_state.select(index.or(fallback));
+        self.update_current_dir();
+    }
+
     fn toggle_selected_directory(&mut self) {
         if let Some(selected_index) = self.list_state.selected() {
             let selected_path = self.visible_entries[selected_index].path.clone();
@@ -192,15 +988,48 @@ impl AppState {
     }
 }
 
-pub fn run(args: &InteractiveArgs, ls_colors: &LsColors) -> anyhow::Result<()> {
+/// Runs the interactive mode, with the keys remapped in the config file's `[keybindings]`.
+pub fn run(
+    args: &InteractiveArgs,
+    ls_colors: &LsColors,
+    keybindings: &HashMap<String, String>,
+) -> anyhow::Result<()> {
+    if args.color_test {
+        return run_color_test(args);
+    }
     if !args.path.is_dir() {
         anyhow::bail!("'{}' is not a directory.", args.path.display());
     }
+    crate::app::check_commit_sort([args.sort, args.sort2], args.count_commits)?;
     let root_path = fs::canonicalize(&args.path)?;
 
+    let keymap = keymap(keybindings)?;
     let mut app_state = AppState::new(args, &root_path)?;
+    app_state.keymap = keymap;
+
+    if args.headless {
+        let width = utils::terminal_width().min(u16::MAX as usize) as u16;
+        for line in render_tree_to_string(&app_state, args, width) {
+            println!("{line}");
+        }
+        return Ok(());
+    }
+
+    // The watcher stops when dropped, so it lives until the TUI exits.
+    let (tx, rx) = mpsc::channel();
+    let _watcher = if args.watch {
+        let mut watcher = notify::recommended_watcher(tx)
+            .map_err(|e| anyhow::anyhow!("Failed to start watching: {e}"))?;
+        watcher
+            .watch(&root_path, RecursiveMode::Recursive)
+            .map_err(|e| anyhow::anyhow!("Failed to watch '{}': {e}", root_path.display()))?;
+        Some(watcher)
+    } else {
+        None
+    };
+
     let mut terminal = setup_terminal()?;
-    let post_exit_action = run_app(&mut terminal, &mut app_state, args, ls_colors)?;
+    let post_exit_action = run_app(&mut terminal, &mut app_state, args, ls_colors, &rx)?;
     restore_terminal(&mut terminal)?;
 
     match post_exit_action {
@@ -217,6 +1046,11 @@ pub fn run(args: &InteractiveArgs, ls_colors: &LsColors) -> anyhow::Result<()> {
         PostExitAction::PrintPath(path) => {
             println!("{}", path.display());
         }
+        PostExitAction::PrintPaths(paths) => {
+            for path in paths {
+                println!("{}", path.display());
+            }
+        }
         PostExitAction::None => {}
     }
 
@@ -228,24 +1062,96 @@ fn run_app<B: Backend + Write>(
     app_state: &mut AppState,
     args: &InteractiveArgs,
     ls_colors: &LsColors,
+    fs_events: &mpsc::Receiver<notify::Result<notify::Event>>,
 ) -> anyhow::Result<PostExitAction> {
     loop {
         terminal.draw(|f| ui(f, app_state, args, ls_colors))?;
 
-        if let Event::Key(key) = event::read()? {
+        // With --watch, wake up regularly to pick up filesystem changes.
+        if args.watch && !event::poll(WATCH_POLL_INTERVAL)? {
+            let changed = fs_events
+                .try_iter()
+                .any(|event| event.is_ok_and(|event| view::is_watched_event(&event.kind)));
+            if changed {
+                if let Err(e) = app_state.refresh(args) {
+                    app_state.message = Some(e.to_string());
+                }
+            }
+            continue;
+        }
+        let event = event::read()?;
+        if let Event::Mouse(mouse) = event {
+            let is_idle = app_state.mode == Mode::Normal
+                && app_state.goto_input.is_none()
+                && !app_state.is_searching;
+            if is_idle && mouse.kind == MouseEventKind::Down(MouseButton::Left) {
+                if let Some(path) = app_state.click(mouse.row, Instant::now()) {
+                    break Ok(PostExitAction::OpenFile(path));
+                }
+            }
+        } else if let Event::Key(key) = event {
             if key.kind == KeyEventKind::Press {
-                match key.code {
-                    KeyCode::Char('s') if key.modifiers == KeyModifiers::CONTROL => {
-                        if let Some(entry) = app_state.get_selected_entry() {
-                            break Ok(PostExitAction::PrintPath(entry.path.clone()));
+                app_state.message = None;
+            }
+            if key.kind == KeyEventKind::Press && app_state.mode != Mode::Normal {
+                let key = config::normalize_key(key.code, key.modifiers);
+                app_state.handle_mode_key(key, args.git_status);
+            } else if key.kind == KeyEventKind::Press && app_state.goto_input.is_some() {
+                app_state.handle_goto_key(key.code);
+            } else if key.kind == KeyEventKind::Press && app_state.is_searching {
+                app_state.handle_search_key(key.code);
+            } else if key.kind == KeyEventKind::Press {
+                let action = app_state.keymap.get(&config::normalize_key(key.code, key.modifiers));
+                match action.copied() {
+                    // The first Esc only clears a confirmed search.
+                    _ if key.code == KeyCode::Esc && app_state.search_string.is_some() => {
+                        app_state.set_search(None);
+                    }
+                    Some(Action::PrintPath) => {
+                        if let Some(action) = app_state.print_action() {
+                            break Ok(action);
                         }
                     }
-                    KeyCode::Char('q') | KeyCode::Esc => {
+                    Some(Action::Quit) => {
                         break Ok(PostExitAction::None);
                     }
-                    KeyCode::Down | KeyCode::Char('j') => app_state.next(),
-                    KeyCode::Up | KeyCode::Char('k') => app_state.previous(),
-                    KeyCode::Enter => {
+                    Some(Action::Search) => {
+                        app_state.is_searching = true;
+                        let search = app_state.search_string.take().unwrap_or_default();
+                        app_state.set_search(Some(search));
+                    }
+                    Some(Action::Delete) => {
+                        if let Some(entry) = app_state.get_selected_entry() {
+                            app_state.mode = Mode::ConfirmDelete(entry.path.clone());
+                        }
+                    }
+                    Some(Action::Rename) => {
+                        if let Some(entry) = app_state.get_selected_entry() {
+                            let name = entry.path.file_name().unwrap_or_default();
+                            app_state.mode = Mode::RenameInput {
+                                original: entry.path.clone(),
+                                buffer: name.to_string_lossy().into_owned(),
+                            };
+                        }
+                    }
+                    Some(Action::New) => app_state.mode = Mode::NewEntryInput(String::new()),
+                    Some(Action::GoTo) => app_state.goto_input = Some(String::new()),
+                    Some(Action::CycleSort) => app_state.cycle_sort(),
+                    Some(Action::ExpandAll) => app_state.expand_all(),
+                    Some(Action::CollapseAll) => app_state.collapse_all(),
+                    Some(Action::Mark) => app_state.toggle_selection(),
+                    Some(Action::TogglePreview) => app_state.show_preview = !app_state.show_preview,
+                    Some(Action::ScrollLeft) => {
+                        app_state.scroll_x = app_state.scroll_x.saturating_sub(1);
+                    }
+                    Some(Action::ScrollRight) => app_state.scroll_x += 1,
+                    Some(Action::Help) => app_state.mode = Mode::ShowHelp,
+                    Some(Action::SetBookmark) => app_state.mode = Mode::SetBookmark,
+                    Some(Action::JumpToBookmark) => app_state.mode = Mode::JumpToBookmark,
+                    Some(Action::Next) => app_state.next(),
+                    Some(Action::Previous) => app_state.previous(),
+                    Some(Action::GoUp) => app_state.collapse_to_parent(),
+                    Some(Action::Toggle) => {
                         if let Some(entry) = app_state.get_selected_entry() {
                             if entry.is_dir {
                                 app_state.toggle_selected_directory();
@@ -254,114 +1160,645 @@ fn run_app<B: Backend + Write>(
                             }
                         }
                     }
-                    _ => {}
+                    None => {}
                 }
             }
         }
     }
 }
 
+/// The symbol drawn in front of the selected entry. Its width is reserved on every row.
+const HIGHLIGHT_SYMBOL: &str = "> ";
+
+/// The longest gap between two clicks on an entry that still counts as a double-click.
+///
+/// Terminals only report single presses, so double-clicks are detected from their
+/// arrival times, which includes any delay in the terminal and event queue.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(300);
+
+/// How often the TUI checks for filesystem changes with `--watch`.
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Entry count past which expanding the whole tree shows a warning.
+const EXPAND_ALL_WARN_ENTRIES: usize = 5000;
+
+/// The most bytes read from a file to build its preview.
+const PREVIEW_MAX_BYTES: u64 = 64 * 1024;
+
+/// The number of bytes of a binary file shown in its hex dump.
+const HEX_DUMP_BYTES: usize = 256;
+
+/// The role of a piece of text within a rendered entry line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SegmentKind {
+    Inode,
+    GitStatus,
+    CommitCount,
+    Insertions,
+    Deletions,
+    Permissions,
+    Links { shared: bool },
+    Owner,
+    Tree,
+    Icon(colored::Color),
+    Name,
+    Tag,
+    Conflict,
+    SymlinkTarget,
+    BrokenSymlinkTarget,
+    Padding,
+    Size,
+}
+
+/// A piece of text in an entry line, tagged with its role so callers can style it.
+#[derive(Debug, Clone)]
+struct Segment {
+    text: String,
+    kind: SegmentKind,
+}
+
+impl Segment {
+    fn new(text: impl Into<String>, kind: SegmentKind) -> Self {
+        Self { text: text.into(), kind }
+    }
+}
+
+/// Drops the first `columns` display columns of a line, for horizontal scrolling.
+///
+/// A wide character cut in half is replaced by a space.
+fn scroll_segments(segments: Vec<Segment>, columns: usize) -> Vec<Segment> {
+    let mut skip = columns;
+    segments
+        .into_iter()
+        .filter_map(|mut segment| {
+            if skip == 0 {
+                return Some(segment);
+            }
+            let mut text = String::new();
+            for c in segment.text.chars() {
+                let width = c.width().unwrap_or(0);
+                if skip == 0 {
+                    text.push(c);
+                } else if width > skip {
+                    text.push_str(&" ".repeat(width - skip));
+                    skip = 0;
+                } else {
+                    skip -= width;
+                }
+            }
+            segment.text = text;
+            (!segment.text.is_empty()).then_some(segment)
+        })
+        .collect()
+}
+
+/// Builds the unstyled segments that make up the line for one entry.
+///
+/// `frame_width` is the width available to the line and is used to right-align
+/// the size column; `widths` aligns the numeric columns across entries.
+fn entry_segments(
+    entry: &FileEntry,
+    args: &InteractiveArgs,
+    frame_width: usize,
+    widths: ColumnWidths,
+) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    if args.inode {
+        let inode_str = entry.inode.map(|inode| inode.to_string()).unwrap_or_default();
+        let width = widths.inode;
+        segments.push(Segment::new(format!("{inode_str:>width$} "), SegmentKind::Inode));
+    }
+    if args.git_status {
+        let status_char = entry.git_status.map_or(' ', |status| status.get_char());
+        segments.push(Segment::new(format!("{status_char} "), SegmentKind::GitStatus));
+    }
+    if args.count_commits && args.git_status {
+        let count_str = match entry.commit_count {
+            Some(count) => format!("{:>5} ", git::format_commit_count(count)),
+            None => " ".repeat(6),
+        };
+        segments.push(Segment::new(count_str, SegmentKind::CommitCount));
+    }
+    if args.git_diff_stat && args.git_status {
+        let (insertions, deletions) =
+            entry.diff_stat.map(|stat| stat.parts(None)).unwrap_or_default();
+        let width = insertions.len() + deletions.len();
+        let padding = git::DIFF_STAT_WIDTH.saturating_sub(width);
+        segments.push(Segment::new(" ".repeat(padding), SegmentKind::Padding));
+        segments.push(Segment::new(insertions, SegmentKind::Insertions));
+        segments.push(Segment::new(format!("{deletions} "), SegmentKind::Deletions));
+    }
+    if args.permissions {
+        let perms_str = entry.permissions.as_deref().unwrap_or("----------");
+        segments.push(Segment::new(format!("{perms_str} "), SegmentKind::Permissions));
+    }
+    if args.nlinks {
+        let width = widths.nlinks;
+        let count = entry.nlinks.map(|n| n.to_string()).unwrap_or_default();
+        let shared = entry.nlinks.is_some_and(|n| n > 1) && !entry.is_dir;
+        segments.push(Segment::new(format!("{count:>width$} "), SegmentKind::Links { shared }));
+    }
+    if let Some(owner) = &entry.owner {
+        segments.push(Segment::new(format!("{owner} "), SegmentKind::Owner));
+    }
+    let indent_str = " ".repeat(args.indent * entry.depth.saturating_sub(1));
+    let branch_str = if entry.is_dir {
+        if args.no_branch_for_single_child && entry.child_count == 1 {
+            "─ "
+        } else if entry.is_expanded {
+            "▼ "
+        } else {
+            "▶ "
+        }
+    } else {
+        "  "
+    };
+    segments.push(Segment::new(indent_str + branch_str, SegmentKind::Tree));
+    if args.shows_icon(&entry.path, entry.is_dir) {
+        let (icon, color) = icons::get_icon_for_path(&entry.path, entry.is_dir);
+        segments.push(Segment::new(format!("{icon} "), SegmentKind::Icon(color)));
+    }
+
+    let name = entry.path.file_name().unwrap_or(entry.path.as_os_str()).to_string_lossy();
+    if entry.has_conflict {
+        segments.push(Segment::new("⚡ ", SegmentKind::Conflict));
+    }
+    segments.push(Segment::new(name, SegmentKind::Name));
+    if let Some(target) = entry.symlink_target.as_ref().filter(|_| entry.is_symlink) {
+        let kind = if entry.path.exists() {
+            SegmentKind::SymlinkTarget
+        } else {
+            SegmentKind::BrokenSymlinkTarget
+        };
+        segments.push(Segment::new(format!(" -> {}", target.display()), kind));
+    }
+    if let Some(tag) = &entry.tag {
+        segments.push(Segment::new(format!(" {tag}"), SegmentKind::Tag));
+    }
+    if entry.has_conflict {
+        segments.push(Segment::new(" [CONFLICT]", SegmentKind::Conflict));
+    }
+
+    if args.size && !entry.is_dir {
+        if let Some(size) = entry.size {
+            let size_str = utils::format_size(size);
+            let left_len: usize = segments.iter().map(|s| s.text.width()).sum();
+            let padding = frame_width.saturating_sub(left_len).saturating_sub(size_str.len());
+            segments.push(Segment::new(" ".repeat(padding), SegmentKind::Padding));
+            segments.push(Segment::new(size_str, SegmentKind::Size));
+        }
+    }
+    segments
+}
+
+/// Builds the plain-text (non-ANSI) line for one entry, as `ui` would lay it out.
+fn build_entry_line(
+    entry: &FileEntry,
+    args: &InteractiveArgs,
+    frame_width: usize,
+    widths: ColumnWidths,
+) -> String {
+    entry_segments(entry, args, frame_width, widths).into_iter().map(|s| s.text).collect()
+}
+
+/// The widths of the right-aligned numeric columns, wide enough for every entry.
+#[derive(Debug, Clone, Copy, Default)]
+struct ColumnWidths {
+    inode: usize,
+    nlinks: usize,
+}
+
+impl ColumnWidths {
+    fn of(entries: &[FileEntry]) -> Self {
+        let digits = |values: &mut dyn Iterator<Item = u64>| {
+            values.max().map_or(0, |max| max.to_string().len())
+        };
+        Self {
+            inode: digits(&mut entries.iter().filter_map(|e| e.inode)),
+            nlinks: digits(&mut entries.iter().filter_map(|e| e.nlinks)),
+        }
+    }
+}
+
+/// Renders the visible entries as plain-text lines of at most `width` columns.
+///
+/// This mirrors `ui` without needing a live terminal, which makes it usable for
+/// tests and the `--headless` mode.
+fn render_tree_to_string(app_state: &AppState, args: &InteractiveArgs, width: u16) -> Vec<String> {
+    let width = width as usize;
+    let content_width = width.saturating_sub(HIGHLIGHT_SYMBOL.width());
+    let selected = app_state.list_state.selected();
+    let widths = ColumnWidths::of(&app_state.master_entries);
+    app_state
+        .visible_entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let symbol = if selected == Some(i) { HIGHLIGHT_SYMBOL } else { "  " };
+            let line = format!("{symbol}{}", build_entry_line(entry, args, content_width, widths));
+            truncate_to_width(&line, width).trim_end().to_string()
+        })
+        .collect()
+}
+
+/// Truncates a string to at most `width` display columns.
+fn truncate_to_width(s: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, c) in s.char_indices() {
+        used += c.width().unwrap_or(0);
+        if used > width {
+            return &s[..i];
+        }
+    }
+    s
+}
+
+/// Returns the display color for a git status.
+fn git_status_color(status: Option<git::FileStatus>) -> Color {
+    match status {
+        Some(git::FileStatus::New | git::FileStatus::Renamed) => Color::Green,
+        Some(git::FileStatus::Modified | git::FileStatus::Typechange) => Color::Yellow,
+        Some(git::FileStatus::Deleted) => Color::Red,
+        Some(git::FileStatus::Conflicted) => Color::LightRed,
+        Some(git::FileStatus::Untracked) => Color::Magenta,
+        Some(git::FileStatus::Submodule) => Color::Cyan,
+        None => Color::Reset,
+    }
+}
+
 fn ui(f: &mut Frame, app_state: &mut AppState, args: &InteractiveArgs, ls_colors: &LsColors) {
-    let frame_width = f.size().width as usize;
+    let breadcrumb = app_state.breadcrumb_segments();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(u16::from(!breadcrumb.is_empty())),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .split(f.area());
+    let (list_area, preview_area) = if app_state.show_preview {
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(chunks[1]);
+        (panes[0], Some(panes[1]))
+    } else {
+        (chunks[1], None)
+    };
+
+    let frame_width = (list_area.width as usize).saturating_sub(HIGHLIGHT_SYMBOL.width());
+    let color_mode = args.color_mode.resolve();
+    let widths = ColumnWidths::of(&app_state.master_entries);
     let items: Vec<ListItem> = app_state
         .visible_entries
         .iter()
         .map(|entry| {
-            let mut spans = Vec::new();
-            if args.git_status {
-                let (status_char, status_color) = if let Some(status) = entry.git_status {
-                    let color = match status {
-                        git::FileStatus::New | git::FileStatus::Renamed => Color::Green,
-                        git::FileStatus::Modified | git::FileStatus::Typechange => Color::Yellow,
-                        git::FileStatus::Deleted => Color::Red,
-                        git::FileStatus::Conflicted => Color::LightRed,
-                        git::FileStatus::Untracked => Color::Magenta,
+            let segments = entry_segments(entry, args, frame_width, widths);
+            let spans: Vec<Span> = scroll_segments(segments, app_state.scroll_x)
+                .into_iter()
+                .map(|segment| {
+                    let style = match segment.kind {
+                        SegmentKind::GitStatus => {
+                            Style::default().fg(git_status_color(entry.git_status))
+                        }
+                        SegmentKind::CommitCount => Style::default().fg(Color::Cyan),
+                        SegmentKind::Insertions => Style::default().fg(Color::Green),
+                        SegmentKind::Deletions => Style::default().fg(Color::Red),
+                        SegmentKind::Conflict => Style::default().fg(Color::LightRed),
+                        SegmentKind::BrokenSymlinkTarget => Style::default().fg(Color::Red),
+                        SegmentKind::SymlinkTarget => Style::default(),
+                        SegmentKind::Links { shared: true } => Style::default().fg(Color::Yellow),
+                        SegmentKind::Inode
+                        | SegmentKind::Links { shared: false }
+                        | SegmentKind::Permissions
+                        | SegmentKind::Owner
+                        | SegmentKind::Size
+                        | SegmentKind::Tag => Style::default().fg(Color::DarkGray),
+                        SegmentKind::Icon(color) => Style::default().fg(map_color(color)),
+                        SegmentKind::Name => {
+                            let lscolors_style =
+                                ls_colors.style_for_path(&entry.path).cloned().unwrap_or_default();
+                            to_ratatui_style(lscolors_style, color_mode, entry.highlight)
+                        }
+                        SegmentKind::Tree | SegmentKind::Padding => Style::default(),
                     };
-                    (status.get_char().to_string(), color)
-                } else {
-                    (" ".to_string(), Color::Reset)
-                };
-                spans.push(Span::styled(
-                    format!("{status_char} "),
-                    Style::default().fg(status_color),
-                ));
-            }
-            if args.permissions {
-                let perms_str = entry.permissions.as_deref().unwrap_or("----------");
-                spans.push(Span::styled(
-                    format!("{perms_str} "),
-                    Style::default().fg(Color::DarkGray),
-                ));
-            }
-            let indent_str = "    ".repeat(entry.depth.saturating_sub(1));
-            spans.push(Span::raw(indent_str));
-            let branch_str = if entry.is_dir {
-                if entry.is_expanded {
-                    "▼ "
-                } else {
-                    "▶ "
-                }
+                    Span::styled(segment.text, style)
+                })
+                .collect();
+            let item = ListItem::new(Line::from(spans));
+            if app_state.selected_paths.contains(&entry.path) {
+                item.style(Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED))
             } else {
-                "  "
-            };
-            spans.push(Span::raw(branch_str));
-            if args.icons {
-                let (icon, color) = icons::get_icon_for_path(&entry.path, entry.is_dir);
-                spans.push(Span::styled(format!("{icon} "), Style::default().fg(map_color(color))));
-            }
-
-            let name = entry.path.file_name().unwrap().to_string_lossy();
-            let lscolors_style = ls_colors.style_for_path(&entry.path).cloned().unwrap_or_default();
-            let ratatui_style = to_ratatui_style(lscolors_style);
-            let name_span = Span::styled(name.to_string(), ratatui_style);
-            spans.push(name_span);
-
-            if args.size && !entry.is_dir {
-                if let Some(size) = entry.size {
-                    let size_str = utils::format_size(size);
-                    let left_len: usize = spans.iter().map(|s| s.width()).sum();
-                    let padding =
-                        frame_width.saturating_sub(left_len).saturating_sub(size_str.len());
-                    spans.push(Span::raw(" ".repeat(padding)));
-                    spans.push(Span::styled(size_str, Style::default().fg(Color::DarkGray)));
-                }
+                item
             }
-            ListItem::new(Line::from(spans))
         })
         .collect();
+
+    if let Some((deepest, parents)) = breadcrumb.split_last() {
+        let mut spans: Vec<Span> = parents
+            .iter()
+            .flat_map(|segment| [Span::raw(segment.as_str()), Span::raw(" / ")])
+            .collect();
+        spans.push(Span::styled(deepest.as_str(), Style::default().add_modifier(Modifier::BOLD)));
+        f.render_widget(Paragraph::new(Line::from(spans)), chunks[0]);
+    }
+
     let list = List::new(items)
         .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
-        .highlight_symbol("> ");
-    f.render_stateful_widget(list, f.size(), &mut app_state.list_state);
+        .highlight_symbol(HIGHLIGHT_SYMBOL);
+    f.render_stateful_widget(list, list_area, &mut app_state.list_state);
+    app_state.list_area = list_area;
+
+    if let Some(area) = preview_area {
+        // Two rows go to the block's borders.
+        app_state.update_preview(area.height.saturating_sub(2) as usize);
+        let title = app_state
+            .get_selected_entry()
+            .and_then(|entry| entry.path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let preview = Paragraph::new(app_state.preview_content.clone().unwrap_or_default())
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(preview, area);
+    }
+
+    let status = status_line(app_state);
+    f.render_widget(
+        Paragraph::new(status).style(Style::default().add_modifier(Modifier::DIM)),
+        chunks[2],
+    );
+
+    if app_state.mode == Mode::ShowHelp {
+        let frame = f.area();
+        let area = centered_rect(frame.width * 3 / 5, frame.height * 3 / 5, frame);
+        let rows = help_rows(&app_state.keymap);
+        let name_width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        let table = Table::new(
+            rows.into_iter().map(|(name, keys)| Row::new([name.to_string(), keys])),
+            [Constraint::Length(name_width as u16), Constraint::Min(1)],
+        )
+        .header(Row::new(["Action", "Key"]).style(Style::default().add_modifier(Modifier::BOLD)))
+        .column_spacing(2)
+        .block(Block::default().borders(Borders::ALL).title(" Keys (? or Esc to close) "));
+        f.render_widget(Clear, area);
+        f.render_widget(table, area);
+    }
+
+    if app_state.mode == Mode::ShowBookmarks {
+        let mut bookmarks: Vec<_> = app_state.bookmarks.iter().collect();
+        bookmarks.sort();
+        let lines: Vec<String> = if bookmarks.is_empty() {
+            vec!["No bookmarks".to_string()]
+        } else {
+            bookmarks
+                .into_iter()
+                .map(|(letter, path)| format!("{letter}  {}", path.display()))
+                .collect()
+        };
+        let width = lines.iter().map(|line| line.width()).max().unwrap_or(0);
+        // Two columns and rows go to the border, two more columns to padding.
+        let area = centered_rect(width as u16 + 4, lines.len() as u16 + 2, f.area());
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(lines.join("\n"))
+                .block(Block::default().borders(Borders::ALL).title(" Bookmarks ")),
+            area,
+        );
+    }
+
+    if let Mode::ConfirmDelete(path) = &app_state.mode {
+        let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+        let prompt = format!("Delete {name}? (y/n)");
+        // Two columns and rows go to the border, two more columns to padding.
+        let area = centered_rect(prompt.width() as u16 + 4, 3, f.area());
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(prompt).alignment(Alignment::Center).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Red)),
+            ),
+            area,
+        );
+    }
+}
+
+/// Lists each action with its keys, in the order of `DEFAULT_KEYS`, for the help overlay.
+fn help_rows(keymap: &HashMap<KeyBinding, Action>) -> Vec<(&'static str, String)> {
+    DEFAULT_KEYS
+        .iter()
+        .map(|&(action, name, _)| {
+            let mut keys: Vec<String> = keymap
+                .iter()
+                .filter(|&(_, bound)| *bound == action)
+                .map(|(&key, _)| config::describe_key(key))
+                .collect();
+            keys.sort();
+            (name, keys.join(", "))
+        })
+        .collect()
+}
+
+/// Returns a rectangle of the given size centered in `area`, shrunk to fit it.
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Returns the first `max_lines` lines of a file, or a hex dump if it is binary.
+///
+/// A file counts as binary if it contains a NUL byte or is not valid UTF-8.
+fn file_preview(path: &Path, max_lines: usize) -> Option<String> {
+    let mut bytes = Vec::new();
+    fs::File::open(path).ok()?.take(PREVIEW_MAX_BYTES).read_to_end(&mut bytes).ok()?;
+    let text = match std::str::from_utf8(&bytes) {
+        Ok(text) => text,
+        // The read may have cut a multi-byte character in half.
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&bytes[..e.valid_up_to()]).ok()?,
+        Err(_) => return Some(hex_dump(&bytes[..bytes.len().min(HEX_DUMP_BYTES)])),
+    };
+    if text.contains('\0') {
+        return Some(hex_dump(&bytes[..bytes.len().min(HEX_DUMP_BYTES)]));
+    }
+    Some(text.lines().take(max_lines).collect::<Vec<_>>().join("\n"))
+}
+
+/// Formats bytes as rows of an offset, 16 hex bytes and their printable ASCII.
+fn hex_dump(bytes: &[u8]) -> String {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(i, row)| {
+            let hex: Vec<String> = row.iter().map(|b| format!("{b:02x}")).collect();
+            let ascii: String = row
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            format!("{:08x}  {:<47}  {ascii}", i * 16, hex.join(" "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Builds the bottom status bar: the selected entry's path, size and permissions
+/// followed by the sort order, or the text being typed, or a message.
+fn status_line(app_state: &AppState) -> String {
+    if let Some(input) = &app_state.goto_input {
+        return format!(" go to: {input}");
+    }
+    if let Mode::RenameInput { buffer, .. } = &app_state.mode {
+        return format!(" rename: {buffer}");
+    }
+    if let Mode::NewEntryInput(buffer) = &app_state.mode {
+        return format!(" New name (end with / for directory): {buffer}");
+    }
+    match app_state.mode {
+        Mode::SetBookmark => return " Bookmark the selection as (a-z):".to_string(),
+        Mode::JumpToBookmark => return " Jump to bookmark (a-z, ? to list):".to_string(),
+        _ => {}
+    }
+    if let Some(message) = &app_state.message {
+        return format!(" {message}");
+    }
+    let selection = match app_state.get_selected_entry() {
+        Some(entry) => {
+            let mut fields = vec![entry.path.display().to_string()];
+            fields.extend(entry.size.map(utils::format_size));
+            fields.extend(entry.permissions.clone());
+            fields.join("  ")
+        }
+        None => "No selection".to_string(),
+    };
+    let mut status = format!(" {selection}  sort: {}", app_state.current_sort.sort_type.name());
+    if app_state.scroll_x > 0 {
+        status.push_str(&format!("  col {}", app_state.scroll_x));
+    }
+    match &app_state.search_string {
+        Some(search) if app_state.is_searching => format!(" /{search}"),
+        Some(search) => format!("{status}  search: {search}"),
+        None => status,
+    }
 }
 
 fn scan_directory(
     path: &Path,
-    status_info: Option<(&StatusCache, &PathBuf)>,
+    mut git_repo_status: Option<&mut GitRepoStatus>,
     args: &InteractiveArgs,
 ) -> anyhow::Result<Vec<FileEntry>> {
     let mut builder = WalkBuilder::new(path);
-    builder.hidden(!args.all).git_ignore(args.gitignore);
+    builder.hidden(!args.all).git_ignore(args.gitignore).follow_links(args.follow_links);
+    let excludes = utils::build_exclude_matcher(path, &args.exclude_from)?;
+    let glob_filter = utils::GlobFilter::new(path, &args.include, &args.exclude)?;
+    if excludes.is_some() || glob_filter.is_active() || args.follow_links {
+        let glob_filter = glob_filter.clone();
+        let follow_links = args.follow_links;
+        builder.filter_entry(move |entry| {
+            !excludes.as_ref().is_some_and(|matcher| utils::is_excluded(matcher, entry))
+                && !glob_filter.prunes(entry)
+                && (!follow_links || !view::skips_symlink_cycle(entry))
+        });
+    }
+
+    let extension_filter = args.extension_filter();
+    let name_regex = utils::compile_filter_regex(args.filter_regex.as_deref())?;
 
     // Collect all DirEntry objects first, filtering out the root path
-    let mut dir_entries: Vec<_> =
-        builder.build().flatten().filter(|result| result.path() != path).collect();
+    let mut dir_entries: Vec<_> = builder
+        .build()
+        .flatten()
+        .filter(|result| result.path() != path)
+        .filter(|result| args.min_depth.is_none_or(|min| result.depth() >= min))
+        .filter(|result| args.file_type.is_none_or(|filter| filter.keeps(result.file_type())))
+        .filter(|result| {
+            let is_dir = result.file_type().is_some_and(|ft| ft.is_dir());
+            !(args.files_only && is_dir)
+                && extension_filter.keeps(result.path(), is_dir)
+                && glob_filter.keeps(result)
+                && name_regex.as_ref().is_none_or(|regex| utils::matches_name_regex(regex, result))
+                && utils::modified_in_range(result, args.newer_than, args.older_than)
+        })
+        .collect();
+
+    // Count commits before sorting, since `--sort commits` depends on them
+    let mut sort_options = args.to_sort_options();
+    if let Some(status) = git_repo_status.as_deref_mut().filter(|_| args.count_commits) {
+        let relative_paths: Vec<PathBuf> = dir_entries
+            .iter()
+            .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()))
+            .filter_map(|e| e.path().strip_prefix(&status.root).ok().map(Path::to_path_buf))
+            .collect();
+        status.load_commit_counts(&relative_paths);
+        sort_options.commit_counts = status
+            .commit_counts
+            .iter()
+            .map(|(rel, &count)| (status.root.join(rel), count))
+            .collect();
+    }
+    if let Some(status) = git_repo_status.as_deref_mut().filter(|_| args.git_diff_stat) {
+        status.load_diff_stats()?;
+    }
+    if let Some(status) = git_repo_status.as_deref_mut().filter(|_| args.git_tag) {
+        let relative_paths: Vec<PathBuf> = dir_entries
+            .iter()
+            .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()))
+            .filter_map(|e| e.path().strip_prefix(&status.root).ok().map(Path::to_path_buf))
+            .filter(|rel_path| args.git_tag_all || status.cache.contains_key(rel_path))
+            .collect();
+        status.load_file_tags(&relative_paths, args.git_tag_format);
+    }
+    let status_info = git_repo_status.as_deref();
+
+    if sort_options.uses(sort::SortType::Children) {
+        sort_options.child_counts = utils::read_child_counts(
+            dir_entries
+                .iter()
+                .filter(|e| e.file_type().is_some_and(|ft| ft.is_dir()))
+                .map(|e| e.path()),
+        );
+    }
 
     // Apply sorting to the DirEntry objects
-    let sort_options = args.to_sort_options();
     sort::sort_entries(&mut dir_entries, &sort_options);
 
+    let child_counts = view::count_children(dir_entries.iter().map(|e| e.path()));
+    let owners = if args.owner { view::owner_labels(&dir_entries) } else { HashMap::new() };
+
     // Convert DirEntry objects to FileEntry objects
     let mut entries = Vec::new();
     for result in dir_entries {
         let metadata = if args.size || args.permissions { result.metadata().ok() } else { None };
         let is_dir = result.file_type().is_some_and(|ft| ft.is_dir());
-        let git_status = if let Some((cache, root)) = status_info {
-            result.path().strip_prefix(root).ok().and_then(|rel_path| cache.get(rel_path)).copied()
+        let git_status = if let Some(status) = status_info {
+            result
+                .path()
+                .strip_prefix(&status.root)
+                .ok()
+                .and_then(|rel_path| status.cache.get(rel_path))
+                .copied()
         } else {
             None
         };
+        if args.only_git_changed && !is_dir && git_status.is_none() {
+            continue;
+        }
+        let commit_count = sort_options.commit_counts.get(result.path()).copied();
+        let diff_stat = status_info.and_then(|status| {
+            let rel_path = result.path().strip_prefix(&status.root).ok()?;
+            status.diff_stats.get(rel_path).copied()
+        });
+        let tag = status_info.and_then(|status| {
+            let rel_path = result.path().strip_prefix(&status.root).ok()?;
+            status.file_tags.get(rel_path).cloned()
+        });
         let size = if args.size && !is_dir { metadata.as_ref().map(|m| m.len()) } else { None };
+        let has_conflict =
+            args.git_conflict_markers && !is_dir && utils::has_conflict_markers(result.path());
         let permissions = if args.permissions {
             metadata.map(|_md| {
                 #[cfg(unix)]
@@ -378,6 +1815,23 @@ fn scan_directory(
         } else {
             None
         };
+        #[cfg(unix)]
+        let highlight = if args.highlight_special && !is_dir {
+            result
+                .metadata()
+                .ok()
+                .and_then(|md| utils::classify_special_permissions(md.permissions().mode()))
+        } else {
+            None
+        };
+        #[cfg(not(unix))]
+        let highlight = None;
+        let is_symlink = result.path_is_symlink();
+        let symlink_target = if is_symlink && (args.dereference || (args.follow_links && is_dir)) {
+            fs::read_link(result.path()).ok()
+        } else {
+            None
+        };
         entries.push(FileEntry {
             path: result.path().to_path_buf(),
             depth: result.depth(),
@@ -386,8 +1840,27 @@ fn scan_directory(
             size,
             permissions,
             git_status,
+            commit_count,
+            diff_stat,
+            child_count: child_counts.get(result.path()).copied().unwrap_or_default(),
+            tag,
+            has_conflict,
+            is_symlink,
+            symlink_target,
+            owner: owners.get(result.path()).cloned(),
+            inode: args.inode.then(|| result.metadata().ok().map(|m| utils::inode(&m))).flatten(),
+            nlinks: args
+                .nlinks
+                .then(|| result.metadata().ok().map(|m| utils::link_count(&m)))
+                .flatten(),
+            highlight,
         });
     }
+    let owner_width =
+        entries.iter().filter_map(|e| e.owner.as_ref()).map(|o| o.width()).max().unwrap_or(0);
+    for owner in entries.iter_mut().filter_map(|e| e.owner.as_mut()) {
+        *owner = format!("{owner:<owner_width$}");
+    }
     Ok(entries)
 }
 
@@ -408,11 +1881,61 @@ fn map_color(c: colored::Color) -> Color {
         colored::Color::BrightBlue => Color::LightBlue,
         colored::Color::BrightMagenta => Color::LightMagenta,
         colored::Color::BrightCyan => Color::LightCyan,
+        colored::Color::BrightWhite => Color::White,
         colored::Color::TrueColor { r, g, b } => Color::Rgb(r, g, b),
-        _ => Color::Reset,
     }
 }
 
+/// Converts the palette into styled lines for the TUI.
+fn color_test_lines(ascii: bool) -> Vec<Line<'static>> {
+    colortest::palette_lines(ascii)
+        .into_iter()
+        .map(|cells| {
+            Line::from(
+                cells
+                    .into_iter()
+                    .map(|cell| match cell {
+                        Cell::Text(text) => Span::raw(text),
+                        Cell::Swatch(swatch, text) => {
+                            let color = match swatch {
+                                Swatch::Named(color) => map_color(color),
+                                Swatch::Indexed(index) => Color::Indexed(index),
+                                Swatch::Rgb(r, g, b) => Color::Rgb(r, g, b),
+                            };
+                            Span::styled(text, Style::default().fg(color))
+                        }
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+/// Shows the color palette as a static screen until a key is pressed.
+fn run_color_test(args: &InteractiveArgs) -> anyhow::Result<()> {
+    if args.headless {
+        for cells in colortest::palette_lines(args.color_test_ascii) {
+            println!("{}", colortest::render_line(&cells));
+        }
+        return Ok(());
+    }
+
+    let mut lines = color_test_lines(args.color_test_ascii);
+    lines.push(Line::default());
+    lines.push(Line::styled("Press any key to exit", Style::default().fg(Color::DarkGray)));
+
+    let mut terminal = setup_terminal()?;
+    loop {
+        terminal.draw(|f| f.render_widget(Paragraph::new(lines.clone()), f.area()))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                break;
+            }
+        }
+    }
+    restore_terminal(&mut terminal)
+}
+
 type TerminalWriter = CrosstermBackend<Box<dyn Write + Send>>;
 
 fn setup_terminal() -> anyhow::Result<Terminal<TerminalWriter>> {
@@ -435,6 +1958,14 @@ fn restore_terminal<B: Backend + Write>(terminal: &mut Terminal<B>) -> anyhow::R
 #[cfg(test)]
 mod tests {
     use super::*;
+    use clap::Parser;
+    use ratatui::crossterm::event::KeyModifiers;
+
+    /// The arguments as parsed from a bare command line, with the CLI defaults.
+    fn default_args() -> InteractiveArgs {
+        InteractiveArgs::parse_from(["fstree"])
+    }
+
     fn setup_test_app_state() -> AppState {
         let master_entries = vec![
             FileEntry {
@@ -445,6 +1976,17 @@ mod tests {
                 size: None,
                 permissions: Some("drwxr-xr-x".to_string()),
                 git_status: None,
+                commit_count: None,
+                diff_stat: None,
+                child_count: 1,
+                tag: None,
+                has_conflict: false,
+                is_symlink: false,
+                symlink_target: None,
+                owner: None,
+                inode: None,
+                nlinks: None,
+                highlight: None,
             },
             FileEntry {
                 path: PathBuf::from("src/main.rs"),
@@ -454,6 +1996,17 @@ mod tests {
                 size: Some(1024),
                 permissions: Some("-rw-r--r--".to_string()),
                 git_status: Some(git::FileStatus::Modified),
+                commit_count: Some(3),
+                diff_stat: None,
+                child_count: 0,
+                tag: None,
+                has_conflict: false,
+                is_symlink: false,
+                symlink_target: None,
+                owner: None,
+                inode: None,
+                nlinks: None,
+                highlight: None,
             },
             FileEntry {
                 path: PathBuf::from("README.md"),
@@ -463,12 +2016,41 @@ mod tests {
                 size: Some(512),
                 permissions: Some("-rw-r--r--".to_string()),
                 git_status: None,
+                commit_count: None,
+                diff_stat: None,
+                child_count: 0,
+                tag: None,
+                has_conflict: false,
+                is_symlink: false,
+                symlink_target: None,
+                owner: None,
+                inode: None,
+                nlinks: None,
+                highlight: None,
             },
         ];
         let mut app_state = AppState {
             master_entries,
             visible_entries: Vec::new(),
             list_state: ListState::default(),
+            current_sort: sort::SortOptions::default(),
+            search_string: None,
+            is_searching: false,
+            show_preview: false,
+            preview_content: None,
+            preview_key: None,
+            selected_paths: HashSet::new(),
+            current_dir: PathBuf::new(),
+            message: None,
+            goto_input: None,
+            root_path: PathBuf::new(),
+            display_root: None,
+            mode: Mode::Normal,
+            keymap: keymap(&HashMap::new()).unwrap(),
+            list_area: Rect::default(),
+            last_click: None,
+            scroll_x: 0,
+            bookmarks: HashMap::new(),
         };
         app_state.regenerate_visible_entries();
         app_state.list_state.select(Some(0));
@@ -506,4 +2088,529 @@ mod tests {
         assert!(selected.is_some());
         assert_eq!(selected.unwrap().path, PathBuf::from("README.md"));
     }
+    #[test]
+    fn test_render_tree_to_string() {
+        let app_state = setup_test_app_state();
+        let args = default_args();
+        let lines = render_tree_to_string(&app_state, &args, 40);
+        assert_eq!(lines, vec!["> ▶ src", "    README.md"]);
+    }
+    #[test]
+    fn test_render_tree_single_child_indicator() {
+        let app_state = setup_test_app_state();
+        let args = InteractiveArgs { no_branch_for_single_child: true, ..default_args() };
+        let lines = render_tree_to_string(&app_state, &args, 40);
+        assert_eq!(lines, vec!["> ─ src", "    README.md"]);
+    }
+    #[test]
+    fn test_render_tree_to_string_with_columns() {
+        let mut app_state = setup_test_app_state();
+        app_state.toggle_selected_directory();
+        let args = InteractiveArgs { size: true, permissions: true, ..default_args() };
+        let lines = render_tree_to_string(&app_state, &args, 40);
+        assert_eq!(lines[0], "> drwxr-xr-x ▼ src");
+        assert_eq!(lines[1], "  -rw-r--r--       main.rs       1.0 KiB");
+        assert_eq!(lines[2], "  -rw-r--r--   README.md           512 B");
+        assert!(lines[1..].iter().all(|line| line.width() == 40));
+    }
+    #[test]
+    fn test_build_entry_line() {
+        let app_state = setup_test_app_state();
+        let args = InteractiveArgs { git_status: true, ..default_args() };
+        let line =
+            build_entry_line(&app_state.master_entries[1], &args, 80, ColumnWidths::default());
+        assert_eq!(line, "M       main.rs");
+
+        let mut entry = app_state.master_entries[1].clone();
+        entry.inode = Some(42);
+        entry.nlinks = Some(3);
+        let args = InteractiveArgs { inode: true, nlinks: true, ..default_args() };
+        let widths = ColumnWidths { inode: 4, nlinks: 2 };
+        assert_eq!(build_entry_line(&entry, &args, 80, widths), "  42  3       main.rs");
+    }
+    #[test]
+    fn test_resort() {
+        let mut app_state = setup_test_app_state();
+        let paths = |state: &AppState| -> Vec<PathBuf> {
+            state.master_entries.iter().map(|e| e.path.clone()).collect()
+        };
+
+        app_state.resort(sort::SortOptions::default());
+        assert_eq!(
+            paths(&app_state),
+            vec![PathBuf::from("README.md"), PathBuf::from("src"), PathBuf::from("src/main.rs")]
+        );
+        assert_eq!(app_state.get_selected_entry().unwrap().path, PathBuf::from("src"));
+
+        let dirs_first = sort::SortOptions { directories_first: true, ..Default::default() };
+        app_state.resort(dirs_first);
+        assert_eq!(
+            paths(&app_state),
+            vec![PathBuf::from("src"), PathBuf::from("src/main.rs"), PathBuf::from("README.md")]
+        );
+        assert!(app_state.current_sort.directories_first);
+    }
+    #[test]
+    fn test_search_filter() {
+        let mut app_state = setup_test_app_state();
+        app_state.is_searching = true;
+        for c in "MAIN".chars() {
+            app_state.handle_search_key(KeyCode::Char(c));
+        }
+        // Matches inside collapsed directories are shown too, ignoring case.
+        let paths: Vec<&Path> =
+            app_state.visible_entries.iter().map(|e| e.path.as_path()).collect();
+        assert_eq!(paths, [Path::new("src/main.rs")]);
+        assert_eq!(app_state.get_selected_entry().unwrap().path, PathBuf::from("src/main.rs"));
+        assert_eq!(status_line(&app_state), " /MAIN");
+
+        app_state.handle_search_key(KeyCode::Char('x'));
+        assert!(app_state.visible_entries.is_empty());
+        assert_eq!(app_state.list_state.selected(), None);
+        app_state.next();
+        app_state.handle_search_key(KeyCode::Backspace);
+        assert_eq!(app_state.visible_entries.len(), 1);
+        app_state.handle_search_key(KeyCode::Char('x'));
+        app_state.handle_search_key(KeyCode::Enter);
+        assert_eq!(status_line(&app_state), " No selection  sort: name  search: MAINx");
+        app_state.is_searching = true;
+        app_state.handle_search_key(KeyCode::Backspace);
+
+        app_state.handle_search_key(KeyCode::Enter);
+        assert!(!app_state.is_searching);
+        assert_eq!(app_state.search_string.as_deref(), Some("MAIN"));
+        assert_eq!(
+            status_line(&app_state),
+            " src/main.rs  1.0 KiB  -rw-r--r--  sort: name  search: MAIN"
+        );
+    }
+    #[test]
+    fn test_search_clear() {
+        let mut app_state = setup_test_app_state();
+        app_state.is_searching = true;
+        app_state.handle_search_key(KeyCode::Char('m'));
+        assert_eq!(app_state.visible_entries.len(), 2);
+        app_state.list_state.select(Some(1));
+
+        app_state.handle_search_key(KeyCode::Esc);
+        assert!(!app_state.is_searching);
+        assert_eq!(app_state.search_string, None);
+        // The collapsed tree is back, with the cursor kept on README.md.
+        assert_eq!(app_state.visible_entries.len(), 2);
+        assert_eq!(app_state.get_selected_entry().unwrap().path, PathBuf::from("README.md"));
+        assert_eq!(status_line(&app_state), " README.md  512 B  -rw-r--r--  sort: name");
+    }
+    #[test]
+    fn test_cycle_sort() {
+        let mut app_state = setup_test_app_state();
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            app_state.cycle_sort();
+            seen.push(app_state.current_sort.sort_type);
+        }
+        assert_eq!(
+            seen,
+            vec![
+                sort::SortType::Size,
+                sort::SortType::Modified,
+                sort::SortType::Extension,
+                sort::SortType::Name
+            ]
+        );
+    }
+    #[test]
+    fn test_file_preview() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let text = temp_dir.path().join("notes.txt");
+        fs::write(&text, "one\ntwo\nthree\n").unwrap();
+        assert_eq!(file_preview(&text, 2).as_deref(), Some("one\ntwo"));
+
+        let binary = temp_dir.path().join("data.bin");
+        let bytes: Vec<u8> = (0..=255).chain(0..=255).collect();
+        fs::write(&binary, bytes).unwrap();
+        let preview = file_preview(&binary, 100).unwrap();
+        // Only the first 256 bytes are dumped, 16 to a row.
+        assert_eq!(preview.lines().count(), 16);
+        assert!(preview.starts_with(
+            "00000000  00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f  ................"
+        ));
+        assert!(preview.lines().nth(4).unwrap().ends_with("  @ABCDEFGHIJKLMNO"));
+
+        assert_eq!(file_preview(&temp_dir.path().join("missing"), 10), None);
+        assert_eq!(hex_dump(b"hi"), format!("00000000  68 69{}  hi", " ".repeat(42)));
+    }
+    #[test]
+    fn test_toggle_selection() {
+        let mut app_state = setup_test_app_state();
+        app_state.toggle_selection();
+        assert!(app_state.selected_paths.contains(Path::new("src")));
+        app_state.toggle_selection();
+        assert!(app_state.selected_paths.is_empty());
+
+        // Without marks, the cursor path is printed.
+        assert_eq!(app_state.print_action(), Some(PostExitAction::PrintPath(PathBuf::from("src"))));
+
+        app_state.next();
+        app_state.toggle_selection();
+        app_state.previous();
+        app_state.toggle_selection();
+        assert_eq!(
+            app_state.print_action(),
+            Some(PostExitAction::PrintPaths(vec![
+                PathBuf::from("README.md"),
+                PathBuf::from("src")
+            ]))
+        );
+    }
+    #[test]
+    fn test_collapse_to_parent() {
+        let mut app_state = setup_test_app_state();
+        assert!(app_state.breadcrumb_segments().is_empty());
+        app_state.toggle_selected_directory();
+        app_state.next();
+        assert_eq!(app_state.get_selected_entry().unwrap().path, PathBuf::from("src/main.rs"));
+        assert_eq!(app_state.current_dir, PathBuf::from("src"));
+        assert_eq!(app_state.breadcrumb_segments(), ["src"]);
+        app_state.display_root = Some("project/".to_string());
+        assert_eq!(app_state.breadcrumb_segments(), ["project/", "src"]);
+        app_state.display_root = None;
+
+        app_state.collapse_to_parent();
+        assert_eq!(app_state.get_selected_entry().unwrap().path, PathBuf::from("src"));
+        assert!(!app_state.master_entries[0].is_expanded);
+        assert_eq!(app_state.visible_entries.len(), 2);
+
+        // Nothing above a top-level entry is expanded.
+        app_state.collapse_to_parent();
+        assert_eq!(app_state.get_selected_entry().unwrap().path, PathBuf::from("src"));
+    }
+    #[test]
+    fn test_expand_and_collapse_all() {
+        let mut app_state = setup_test_app_state();
+        app_state.next();
+        app_state.expand_all();
+        assert_eq!(app_state.visible_entries.len(), 3);
+        assert_eq!(app_state.get_selected_entry().unwrap().path, PathBuf::from("README.md"));
+        assert_eq!(app_state.message, None);
+
+        app_state.previous();
+        app_state.collapse_all();
+        // src/main.rs is hidden, so the cursor falls back to the first entry.
+        assert_eq!(app_state.visible_entries.len(), 2);
+        assert_eq!(app_state.list_state.selected(), Some(0));
+    }
+    #[test]
+    fn test_goto() {
+        let mut app_state = setup_test_app_state();
+        app_state.goto_input = Some(String::new());
+        for c in "./src/../src/main.rs".chars() {
+            app_state.handle_goto_key(KeyCode::Char(c));
+        }
+        assert_eq!(status_line(&app_state), " go to: ./src/../src/main.rs");
+        app_state.handle_goto_key(KeyCode::Enter);
+        assert_eq!(app_state.goto_input, None);
+        // The collapsed parent is expanded to reveal the entry.
+        assert!(app_state.master_entries[0].is_expanded);
+        assert_eq!(app_state.get_selected_entry().unwrap().path, PathBuf::from("src/main.rs"));
+
+        app_state.goto_input = Some("nope".to_string());
+        app_state.handle_goto_key(KeyCode::Enter);
+        assert_eq!(status_line(&app_state), " Not in the tree: nope");
+        assert_eq!(app_state.get_selected_entry().unwrap().path, PathBuf::from("src/main.rs"));
+    }
+    #[test]
+    fn test_confirm_delete_popup() {
+        let mut app_state = setup_test_app_state();
+        app_state.mode = Mode::ConfirmDelete(PathBuf::from("src"));
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(40, 8)).unwrap();
+        terminal.draw(|f| ui(f, &mut app_state, &default_args(), &LsColors::empty())).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let rows: Vec<String> = (0..buffer.area.height)
+            .map(|y| (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect();
+        // The popup is centered: 21 columns wide, on rows 2 to 4.
+        assert_eq!(rows[2], format!("{0}┌{1}┐{0} ", " ".repeat(9), "─".repeat(19)));
+        assert_eq!(rows[3], format!("{0}│ Delete src? (y/n) │{0} ", " ".repeat(9)));
+        assert_eq!(rows[4], format!("{0}└{1}┘{0} ", " ".repeat(9), "─".repeat(19)));
+    }
+    #[test]
+    fn test_delete() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join("docs")).unwrap();
+        fs::write(temp_dir.path().join("docs/guide.md"), "").unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "").unwrap();
+        let args = InteractiveArgs::parse_from(["fstree".as_ref(), temp_dir.path().as_os_str()]);
+        let root = fs::canonicalize(temp_dir.path()).unwrap();
+        let mut app_state = AppState::new(&args, &root).unwrap();
+        app_state.toggle_selected_directory();
+        assert_eq!(app_state.visible_entries.len(), 3);
+
+        // Anything but `y` cancels.
+        app_state.mode = Mode::ConfirmDelete(root.join("docs"));
+        app_state.handle_confirm_delete_key(KeyCode::Char('n'));
+        assert_eq!(app_state.mode, Mode::Normal);
+        assert!(root.join("docs").exists());
+
+        app_state.mode = Mode::ConfirmDelete(root.join("docs"));
+        app_state.handle_confirm_delete_key(KeyCode::Char('y'));
+        assert!(!root.join("docs").exists());
+        let paths: Vec<&Path> =
+            app_state.visible_entries.iter().map(|e| e.path.as_path()).collect();
+        assert_eq!(paths, [root.join("notes.txt")]);
+
+        app_state.mode = Mode::ConfirmDelete(root.join("docs"));
+        app_state.handle_confirm_delete_key(KeyCode::Char('y'));
+        assert!(app_state.message.unwrap().starts_with("Failed to delete"));
+    }
+    #[test]
+    fn test_rename() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join("docs")).unwrap();
+        fs::write(temp_dir.path().join("docs/guide.md"), "").unwrap();
+        let args = InteractiveArgs::parse_from(["fstree".as_ref(), temp_dir.path().as_os_str()]);
+        let root = fs::canonicalize(temp_dir.path()).unwrap();
+        let mut app_state = AppState::new(&args, &root).unwrap();
+        app_state.toggle_selected_directory();
+
+        let original = root.join("docs");
+        app_state.mode = Mode::RenameInput { original, buffer: "docs".to_string() };
+        for code in [KeyCode::Backspace, KeyCode::Char('/'), KeyCode::Char('x'), KeyCode::Enter] {
+            app_state.handle_rename_key(code, false);
+        }
+        assert_eq!(
+            app_state.message.take().unwrap(),
+            "Invalid name 'doc/x': it must not contain path separators"
+        );
+        assert!(root.join("docs").exists());
+
+        app_state.mode =
+            Mode::RenameInput { original: root.join("docs"), buffer: "manual".to_string() };
+        assert_eq!(status_line(&app_state), " rename: manual");
+        app_state.handle_rename_key(KeyCode::Enter, false);
+        assert_eq!(app_state.mode, Mode::Normal);
+        assert!(root.join("manual/guide.md").exists());
+        let paths: Vec<&Path> =
+            app_state.visible_entries.iter().map(|e| e.path.as_path()).collect();
+        assert_eq!(paths, [root.join("manual"), root.join("manual/guide.md")]);
+
+        app_state.mode = Mode::RenameInput { original: root.join("manual"), buffer: String::new() };
+        app_state.handle_rename_key(KeyCode::Esc, false);
+        assert_eq!(app_state.mode, Mode::Normal);
+    }
+    #[test]
+    fn test_create_entry() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join("docs")).unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "").unwrap();
+        let args = InteractiveArgs::parse_from(["fstree".as_ref(), temp_dir.path().as_os_str()]);
+        let root = fs::canonicalize(temp_dir.path()).unwrap();
+        let mut app_state = AppState::new(&args, &root).unwrap();
+
+        // With the cursor on `docs`, the entry is created inside it.
+        app_state.mode = Mode::NewEntryInput(String::new());
+        for c in "guide.md".chars() {
+            app_state.handle_new_entry_key(KeyCode::Char(c));
+        }
+        assert_eq!(status_line(&app_state), " New name (end with / for directory): guide.md");
+        app_state.handle_new_entry_key(KeyCode::Enter);
+        assert!(root.join("docs/guide.md").is_file());
+        let selected = app_state.get_selected_entry().unwrap();
+        assert_eq!((selected.path.clone(), selected.depth), (root.join("docs/guide.md"), 2));
+
+        // With the cursor on a file, it is created in the root.
+        app_state.create_entry("assets/").unwrap();
+        assert!(root.join("assets").is_dir());
+        let paths: Vec<&Path> =
+            app_state.visible_entries.iter().map(|e| e.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            [
+                root.join("assets"),
+                root.join("docs"),
+                root.join("docs/guide.md"),
+                root.join("notes.txt")
+            ]
+        );
+        assert_eq!(app_state.get_selected_entry().unwrap().path, root.join("assets"));
+
+        app_state.previous();
+        let error = app_state.create_entry("notes.txt").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("'{}' already exists", root.join("notes.txt").display())
+        );
+    }
+    #[test]
+    fn test_refresh() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join("docs")).unwrap();
+        fs::write(temp_dir.path().join("docs/guide.md"), "").unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "").unwrap();
+        let args = InteractiveArgs::parse_from(["fstree".as_ref(), temp_dir.path().as_os_str()]);
+        let root = fs::canonicalize(temp_dir.path()).unwrap();
+        let mut app_state = AppState::new(&args, &root).unwrap();
+        app_state.toggle_selected_directory();
+        app_state.previous();
+
+        fs::write(root.join("docs/api.md"), "").unwrap();
+        fs::remove_file(root.join("docs/guide.md")).unwrap();
+        app_state.refresh(&args).unwrap();
+        let paths: Vec<&Path> =
+            app_state.visible_entries.iter().map(|e| e.path.as_path()).collect();
+        // `docs` is still expanded and the cursor is still on notes.txt.
+        assert_eq!(paths, [root.join("docs"), root.join("docs/api.md"), root.join("notes.txt")]);
+        assert_eq!(app_state.get_selected_entry().unwrap().path, root.join("notes.txt"));
+    }
+    #[test]
+    fn test_keymap() {
+        let defaults = keymap(&HashMap::new()).unwrap();
+        assert_eq!(defaults[&(KeyCode::Char('j'), KeyModifiers::NONE)], Action::Next);
+        assert_eq!(defaults[&(KeyCode::Char('s'), KeyModifiers::CONTROL)], Action::PrintPath);
+        assert_eq!(
+            defaults[&config::normalize_key(KeyCode::Char('S'), KeyModifiers::SHIFT)],
+            Action::CycleSort
+        );
+
+        // `k` moves from `previous` to `next`, which loses its default keys.
+        let bindings = HashMap::from([("next".to_string(), "k".to_string())]);
+        let remapped = keymap(&bindings).unwrap();
+        assert_eq!(remapped[&(KeyCode::Char('k'), KeyModifiers::NONE)], Action::Next);
+        assert_eq!(remapped[&(KeyCode::Up, KeyModifiers::NONE)], Action::Previous);
+        assert!(!remapped.contains_key(&(KeyCode::Char('j'), KeyModifiers::NONE)));
+
+        let bindings = HashMap::from([("jump".to_string(), "j".to_string())]);
+        assert_eq!(
+            keymap(&bindings).unwrap_err().to_string(),
+            "Unknown action 'jump' in [keybindings]"
+        );
+        let bindings = HashMap::from([("quit".to_string(), "ctrl+".to_string())]);
+        assert_eq!(
+            keymap(&bindings).unwrap_err().to_string(),
+            "Invalid key 'ctrl+' for 'quit' in [keybindings]"
+        );
+    }
+    #[test]
+    fn test_click() {
+        let mut app_state = setup_test_app_state();
+        app_state.list_area = Rect::new(0, 1, 40, 10);
+        let start = Instant::now();
+
+        // Row 2 is the second entry, since the list starts on row 1.
+        assert_eq!(app_state.click(2, start), None);
+        assert_eq!(app_state.get_selected_entry().unwrap().path, PathBuf::from("README.md"));
+        assert_eq!(
+            app_state.click(2, start + Duration::from_millis(100)),
+            Some("README.md".into())
+        );
+
+        // Clicks too far apart are two single clicks.
+        app_state.click(1, start);
+        assert_eq!(app_state.click(1, start + Duration::from_millis(400)), None);
+        assert_eq!(app_state.visible_entries.len(), 2);
+        app_state.click(1, start + Duration::from_millis(500));
+        assert_eq!(app_state.visible_entries.len(), 3);
+
+        // Rows outside the list, or below its last entry, are ignored.
+        assert_eq!(app_state.click(0, start), None);
+        app_state.click(9, start);
+        assert_eq!(app_state.get_selected_entry().unwrap().path, PathBuf::from("src"));
+    }
+    #[test]
+    fn test_horizontal_scroll() {
+        let mut app_state = setup_test_app_state();
+        app_state.master_entries[2].path = PathBuf::from("a-very-long-file-name-12345.txt");
+        app_state.regenerate_visible_entries();
+        app_state.scroll_x = 10;
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(20, 4)).unwrap();
+        terminal.draw(|f| ui(f, &mut app_state, &default_args(), &LsColors::empty())).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let row: String = (0..20).map(|x| buffer[(x, 1)].symbol()).collect();
+        // The first 10 columns, the tree prefix among them, are scrolled off, but
+        // the highlight column stays.
+        assert_eq!(row, "  ong-file-name-1234");
+        assert!(status_line(&app_state).ends_with("  col 10"));
+
+        app_state.collapse_all();
+        assert_eq!(app_state.scroll_x, 0);
+
+        let segments =
+            vec![Segment::new("ab", SegmentKind::Tree), Segment::new("界c", SegmentKind::Name)];
+        let texts = |segments: Vec<Segment>| -> Vec<String> {
+            segments.into_iter().map(|segment| segment.text).collect()
+        };
+        assert_eq!(texts(scroll_segments(segments.clone(), 3)), [" c"]);
+        assert_eq!(texts(scroll_segments(segments, 1)), ["b", "界c"]);
+    }
+    #[test]
+    fn test_help_overlay() {
+        let mut app_state = setup_test_app_state();
+        let bindings = HashMap::from([("cycle-sort".to_string(), "ctrl+o".to_string())]);
+        app_state.keymap = keymap(&bindings).unwrap();
+        let rows = help_rows(&app_state.keymap);
+        assert_eq!(rows[0], ("next", "down, j".to_string()));
+        assert!(rows.contains(&("cycle-sort", "ctrl+o".to_string())));
+
+        let help = config::parse_key("?").unwrap();
+        app_state.mode = Mode::ShowHelp;
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(50, 30)).unwrap();
+        terminal.draw(|f| ui(f, &mut app_state, &default_args(), &LsColors::empty())).unwrap();
+        let buffer = terminal.backend().buffer();
+        let rows: Vec<String> = (0..buffer.area.height)
+            .map(|y| (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect();
+        // The overlay covers 30 of 50 columns and 18 of 30 rows, centered.
+        assert!(rows[6].starts_with("          ┌ Keys (? or Esc to close) ─"), "{}", rows[6]);
+        assert_eq!(rows[7].trim(), "│Action            Key       │");
+        assert_eq!(rows[8].trim(), "│next              down, j   │");
+        assert!(rows.iter().any(|row| row.trim() == "│cycle-sort        ctrl+o    │"));
+        assert!(rows[23].starts_with("          └"));
+
+        app_state.handle_mode_key((KeyCode::Char('j'), KeyModifiers::NONE), false);
+        assert_eq!(app_state.mode, Mode::ShowHelp);
+        app_state.handle_mode_key(help, false);
+        assert_eq!(app_state.mode, Mode::Normal);
+    }
+    #[test]
+    fn test_bookmarks() {
+        let mut app_state = setup_test_app_state();
+        app_state.toggle_selected_directory();
+        app_state.next();
+        app_state.mode = Mode::SetBookmark;
+        app_state.handle_mode_key((KeyCode::Char('a'), KeyModifiers::NONE), false);
+        assert_eq!(app_state.bookmarks[&'a'], PathBuf::from("src/main.rs"));
+        assert_eq!(app_state.mode, Mode::Normal);
+
+        // Jumping expands the collapsed parent again.
+        app_state.collapse_all();
+        app_state.mode = Mode::JumpToBookmark;
+        assert_eq!(status_line(&app_state), " Jump to bookmark (a-z, ? to list):");
+        app_state.handle_mode_key((KeyCode::Char('a'), KeyModifiers::NONE), false);
+        assert_eq!(app_state.get_selected_entry().unwrap().path, PathBuf::from("src/main.rs"));
+
+        app_state.mode = Mode::JumpToBookmark;
+        app_state.handle_mode_key((KeyCode::Char('b'), KeyModifiers::NONE), false);
+        assert_eq!(app_state.message.as_deref(), Some("No bookmark 'b'"));
+
+        app_state.mode = Mode::JumpToBookmark;
+        app_state.handle_mode_key((KeyCode::Char('?'), KeyModifiers::NONE), false);
+        assert_eq!(app_state.mode, Mode::ShowBookmarks);
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(30, 9)).unwrap();
+        terminal.draw(|f| ui(f, &mut app_state, &default_args(), &LsColors::empty())).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row: String = (0..30).map(|x| buffer[(x, 4)].symbol()).collect();
+        assert_eq!(row.trim(), "│a  src/main.rs  │");
+        app_state.handle_mode_key((KeyCode::Esc, KeyModifiers::NONE), false);
+        assert_eq!(app_state.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_to_ratatui_style_highlight() {
+        let style = to_ratatui_style(LsStyle::default(), ColorMode::TrueColor, None);
+        assert_eq!(style.bg, None);
+        let highlight = Some(utils::PermissionHighlight::Suid);
+        let style = to_ratatui_style(LsStyle::default(), ColorMode::TrueColor, highlight);
+        assert_eq!(style.bg, Some(Color::Red));
+    }
 }
@@ -1,6 +1,960 @@
 //! Shared utility functions for the fstree application.
 
-// This entire module will only be compiled on Unix-like systems.
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{DirEntry, WalkBuilder, WalkState};
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use regex::Regex;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs::{File, FileType, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, SystemTime};
+use unicode_normalization::UnicodeNormalization;
+
+/// Decides which errors encountered during a directory walk are reported.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ErrorPolicy {
+    /// Silently skip entries that cannot be read due to missing permissions.
+    pub ignore_permission_denied: bool,
+}
+
+impl ErrorPolicy {
+    /// Returns `true` if the error should be hidden from the user.
+    pub fn is_suppressed(&self, err: &ignore::Error) -> bool {
+        self.ignore_permission_denied && is_permission_denied(err)
+    }
+}
+
+/// Checks whether a walk error was caused by missing permissions.
+pub fn is_permission_denied(err: &ignore::Error) -> bool {
+    err.io_error().is_some_and(|e| e.kind() == io::ErrorKind::PermissionDenied)
+}
+
+/// Identifies the filesystem holding `path`, without following a final symlink.
+///
+/// This is the device number on Unix, like `find -xdev` and `du -x` use, and
+/// the drive or share prefix on Windows. Other platforms have no identifier.
+pub fn filesystem_id(path: &Path) -> Option<String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        std::fs::symlink_metadata(path).ok().map(|metadata| metadata.dev().to_string())
+    }
+    #[cfg(windows)]
+    {
+        match std::fs::canonicalize(path).ok()?.components().next()? {
+            std::path::Component::Prefix(prefix) => {
+                Some(prefix.as_os_str().to_string_lossy().to_uppercase())
+            }
+            _ => None,
+        }
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// Returns the symlink that the walker refused to follow, if `err` reports a loop.
+pub fn symlink_loop_path(err: &ignore::Error) -> Option<&Path> {
+    match err {
+        ignore::Error::Loop { child, .. } => Some(child),
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => symlink_loop_path(err),
+        _ => None,
+    }
+}
+
+/// Returns true if the symlink at `path` points to a directory containing it,
+/// so following it would walk the same directories forever.
+///
+/// Directories are compared by device and inode on Unix, and by canonical path
+/// elsewhere.
+pub fn is_symlink_cycle(path: &Path) -> bool {
+    let Ok(target) = std::fs::metadata(path) else {
+        return false;
+    };
+    let Some(parent) = path.parent().and_then(|parent| std::fs::canonicalize(parent).ok()) else {
+        return false;
+    };
+    if !target.is_dir() {
+        return false;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let target_id = (target.dev(), target.ino());
+        parent
+            .ancestors()
+            .filter_map(|ancestor| std::fs::metadata(ancestor).ok())
+            .any(|ancestor| (ancestor.dev(), ancestor.ino()) == target_id)
+    }
+    #[cfg(not(unix))]
+    {
+        std::fs::canonicalize(path).is_ok_and(|target| parent.starts_with(target))
+    }
+}
+
+/// The kinds of entries accepted by `--type`, mirroring `find -type`.
+#[derive(clap::ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FileTypeFilter {
+    /// Regular files.
+    #[value(name = "f")]
+    File,
+    /// Directories.
+    #[value(name = "d")]
+    Directory,
+    /// Symbolic links.
+    #[value(name = "l")]
+    Symlink,
+    /// Named pipes (FIFOs).
+    #[value(name = "p")]
+    Pipe,
+    /// Unix domain sockets.
+    #[value(name = "s")]
+    Socket,
+    /// Block devices.
+    #[value(name = "b")]
+    BlockDevice,
+    /// Character devices.
+    #[value(name = "c")]
+    CharDevice,
+}
+
+impl FileTypeFilter {
+    /// Returns `true` if the file type is of the requested kind.
+    ///
+    /// Pipes, sockets and devices never match on non-Unix platforms.
+    pub fn matches(self, file_type: FileType) -> bool {
+        #[cfg(unix)]
+        use std::os::unix::fs::FileTypeExt;
+
+        match self {
+            Self::File => file_type.is_file(),
+            Self::Directory => file_type.is_dir(),
+            Self::Symlink => file_type.is_symlink(),
+            #[cfg(unix)]
+            Self::Pipe => file_type.is_fifo(),
+            #[cfg(unix)]
+            Self::Socket => file_type.is_socket(),
+            #[cfg(unix)]
+            Self::BlockDevice => file_type.is_block_device(),
+            #[cfg(unix)]
+            Self::CharDevice => file_type.is_char_device(),
+            #[cfg(not(unix))]
+            Self::Pipe | Self::Socket | Self::BlockDevice | Self::CharDevice => false,
+        }
+    }
+
+    /// Returns `true` if an entry should be displayed.
+    ///
+    /// Directories are always kept to preserve the tree structure, so filtering
+    /// by `d` leaves only directories, like `--dirs-only`.
+    pub fn keeps(self, file_type: Option<FileType>) -> bool {
+        file_type.is_some_and(|ft| ft.is_dir() || self.matches(ft))
+    }
+}
+
+/// The Unicode normalization forms accepted by `--unicode-normalize`.
+#[derive(clap::ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NormalizationForm {
+    /// Canonical composition, as typically stored on Linux.
+    Nfc,
+    /// Canonical decomposition, as stored by macOS HFS+.
+    Nfd,
+    /// Compatibility composition.
+    Nfkc,
+    /// Compatibility decomposition.
+    Nfkd,
+    /// The preferred form of the current platform (NFD on macOS, NFC elsewhere).
+    Auto,
+}
+
+impl NormalizationForm {
+    /// Resolves `Auto` to the current platform's preferred form.
+    pub fn resolve(self) -> Self {
+        match self {
+            Self::Auto if cfg!(target_os = "macos") => Self::Nfd,
+            Self::Auto => Self::Nfc,
+            form => form,
+        }
+    }
+}
+
+/// Normalizes a file name so that equivalent spellings compare equal when sorting.
+pub fn normalize_for_sort(s: &str, form: NormalizationForm) -> String {
+    match form.resolve() {
+        NormalizationForm::Nfd => s.nfd().collect(),
+        NormalizationForm::Nfkc => s.nfkc().collect(),
+        NormalizationForm::Nfkd => s.nfkd().collect(),
+        NormalizationForm::Nfc | NormalizationForm::Auto => s.nfc().collect(),
+    }
+}
+
+/// Returns the inode number of a file, or 0 where inodes are not available.
+pub fn inode(metadata: &std::fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        metadata.ino()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        0
+    }
+}
+
+/// Returns the number of hard links to a file, or 1 where link counts are not available.
+pub fn link_count(metadata: &std::fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        metadata.nlink()
+    }
+    #[cfg(not(unix))]
+    {
+        // `number_of_links` on Windows is still unstable.
+        let _ = metadata;
+        1
+    }
+}
+
+/// Resolves user and group ids to names, caching each lookup.
+#[cfg(unix)]
+#[derive(Debug, Default)]
+pub struct OwnerNames {
+    users: HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+}
+
+#[cfg(unix)]
+impl OwnerNames {
+    /// Returns the name of a user, or the numeric id if it has no name.
+    pub fn user_name(&mut self, uid: u32) -> &str {
+        self.users.entry(uid).or_insert_with(|| {
+            uzers::get_user_by_uid(uid)
+                .map_or_else(|| uid.to_string(), |user| user.name().to_string_lossy().into_owned())
+        })
+    }
+
+    /// Returns the name of a group, or the numeric id if it has no name.
+    pub fn group_name(&mut self, gid: u32) -> &str {
+        self.groups.entry(gid).or_insert_with(|| {
+            uzers::get_group_by_gid(gid).map_or_else(
+                || gid.to_string(),
+                |group| group.name().to_string_lossy().into_owned(),
+            )
+        })
+    }
+}
+
+/// The targets a symlink resolves through, hop by hop.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymlinkChain {
+    /// Each resolved target in order; the last one is the final target.
+    pub hops: Vec<PathBuf>,
+    /// Whether the chain ends at a path that does not exist.
+    pub is_broken: bool,
+    /// Whether the chain leads back to a link it already visited.
+    pub is_loop: bool,
+}
+
+/// Follows a symlink one hop at a time until reaching a non-symlink.
+///
+/// Relative targets are resolved against the directory containing the link,
+/// like the kernel does. A chain that revisits a link is reported as a loop.
+pub fn resolve_symlink_chain(path: &Path) -> SymlinkChain {
+    let mut chain = SymlinkChain::default();
+    let mut seen: HashSet<PathBuf> = HashSet::from([path.to_path_buf()]);
+    let mut current = path.to_path_buf();
+    while let Ok(target) = std::fs::read_link(&current) {
+        let next = match current.parent() {
+            Some(parent) if target.is_relative() => parent.join(&target),
+            _ => target,
+        };
+        chain.hops.push(next.clone());
+        if !seen.insert(next.clone()) {
+            chain.is_loop = true;
+            break;
+        }
+        match std::fs::symlink_metadata(&next) {
+            Ok(metadata) if metadata.file_type().is_symlink() => current = next,
+            Ok(_) => break,
+            Err(_) => {
+                chain.is_broken = true;
+                break;
+            }
+        }
+    }
+    chain
+}
+
+/// Tries to open every path for reading, returning the ones that failed.
+pub fn check_readability(paths: &[PathBuf]) -> Vec<(PathBuf, io::Error)> {
+    check_access(paths, |path| File::open(path))
+}
+
+/// Tries to open every path for writing, without truncating, returning the ones that failed.
+pub fn check_writability(paths: &[PathBuf]) -> Vec<(PathBuf, io::Error)> {
+    check_access(paths, |path| OpenOptions::new().write(true).open(path))
+}
+
+/// Opens each path in parallel with `open`, keeping the input order of failures.
+fn check_access(
+    paths: &[PathBuf],
+    open: impl Fn(&Path) -> io::Result<File> + Sync,
+) -> Vec<(PathBuf, io::Error)> {
+    paths.par_iter().filter_map(|path| open(path).err().map(|err| (path.clone(), err))).collect()
+}
+
+/// Returns the width of the terminal in columns, falling back to 80.
+pub fn terminal_width() -> usize {
+    ratatui::crossterm::terminal::size().map(|(cols, _)| cols as usize).unwrap_or(80)
+}
+
+/// Returns the height of the terminal in rows, falling back to 24.
+pub fn terminal_height() -> usize {
+    ratatui::crossterm::terminal::size().map(|(_, rows)| rows as usize).unwrap_or(24)
+}
+
+/// The 8 basic ANSI colors with their xterm default RGB values.
+const BASIC_COLORS: [(colored::Color, (u8, u8, u8)); 8] = [
+    (colored::Color::Black, (0, 0, 0)),
+    (colored::Color::Red, (205, 0, 0)),
+    (colored::Color::Green, (0, 205, 0)),
+    (colored::Color::Yellow, (205, 205, 0)),
+    (colored::Color::Blue, (0, 0, 238)),
+    (colored::Color::Magenta, (205, 0, 205)),
+    (colored::Color::Cyan, (0, 205, 205)),
+    (colored::Color::White, (229, 229, 229)),
+];
+
+/// Intensity levels of each channel in the xterm 6x6x6 color cube.
+pub const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Returns the basic ANSI color closest to an RGB color by Euclidean distance.
+pub fn nearest_basic_color(r: u8, g: u8, b: u8) -> colored::Color {
+    let distance = |(cr, cg, cb): (u8, u8, u8)| {
+        let dr = i32::from(r) - i32::from(cr);
+        let dg = i32::from(g) - i32::from(cg);
+        let db = i32::from(b) - i32::from(cb);
+        dr * dr + dg * dg + db * db
+    };
+    BASIC_COLORS.iter().min_by_key(|(_, rgb)| distance(*rgb)).map(|(color, _)| *color).unwrap()
+}
+
+/// Returns the index of the xterm-256 color cube entry closest to an RGB color.
+pub fn nearest_ansi256_index(r: u8, g: u8, b: u8) -> u8 {
+    let level =
+        |c: u8| (0..CUBE_LEVELS.len()).min_by_key(|&i| CUBE_LEVELS[i].abs_diff(c)).unwrap() as u8;
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+/// Removes ANSI escape sequences from text that may arrive in pieces.
+///
+/// Sequences split across calls to `strip` are still removed, since the
+/// state of an unfinished sequence carries over to the next call.
+#[derive(Debug, Default)]
+pub struct AnsiStripper {
+    state: EscapeState,
+}
+
+/// Where an `AnsiStripper` is within an escape sequence.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum EscapeState {
+    #[default]
+    Text,
+    /// After `ESC`, waiting for the sequence type.
+    Escape,
+    /// Inside a control sequence such as `ESC [ 31 m`, up to its final byte.
+    Csi,
+    /// Inside an operating system command such as a hyperlink, up to `BEL` or `ESC \`.
+    Osc,
+    /// After `ESC` inside an operating system command.
+    OscEscape,
+}
+
+impl AnsiStripper {
+    /// Returns `bytes` without the escape sequences they contain.
+    pub fn strip(&mut self, bytes: &[u8]) -> Vec<u8> {
+        let mut plain = Vec::with_capacity(bytes.len());
+        for &byte in bytes {
+            self.state = match (self.state, byte) {
+                (EscapeState::Text, 0x1B) => EscapeState::Escape,
+                (EscapeState::Text, _) => {
+                    plain.push(byte);
+                    EscapeState::Text
+                }
+                (EscapeState::Escape, b'[') => EscapeState::Csi,
+                (EscapeState::Escape, b']') => EscapeState::Osc,
+                (EscapeState::Escape, _) => EscapeState::Text,
+                (EscapeState::Csi, 0x40..=0x7E) => EscapeState::Text,
+                (EscapeState::Csi, _) => EscapeState::Csi,
+                (EscapeState::Osc, 0x07) => EscapeState::Text,
+                (EscapeState::Osc, 0x1B) => EscapeState::OscEscape,
+                (EscapeState::Osc, _) => EscapeState::Osc,
+                (EscapeState::OscEscape, b'\\') => EscapeState::Text,
+                (EscapeState::OscEscape, _) => EscapeState::Osc,
+            };
+        }
+        plain
+    }
+}
+
+/// Returns the number of bytes actually allocated on disk for a file.
+#[cfg(unix)]
+pub fn allocated_bytes(metadata: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    // `st_blocks` is always counted in 512-byte units, whatever the block size.
+    metadata.blocks() * 512
+}
+
+/// Checks whether less than 90% of a file's apparent size is allocated on disk.
+#[cfg(unix)]
+pub fn is_sparse_file(metadata: &std::fs::Metadata) -> bool {
+    metadata.is_file() && (allocated_bytes(metadata) as u128) * 10 < (metadata.len() as u128) * 9
+}
+
+/// The number of bytes searched for conflict markers at the start of a file.
+const CONFLICT_SCAN_BYTES: u64 = 8 * 1024;
+
+/// Checks whether the first 8 KB of a file contain a Git conflict marker.
+///
+/// A marker is a line starting with `<<<<<<< `. Binary files, detected by a
+/// NUL byte, and unreadable files never match.
+pub fn has_conflict_markers(path: &Path) -> bool {
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    let mut buffer = Vec::new();
+    if BufReader::new(file).take(CONFLICT_SCAN_BYTES).read_to_end(&mut buffer).is_err()
+        || buffer.contains(&0)
+    {
+        return false;
+    }
+    buffer.split(|&byte| byte == b'\n').any(|line| line.starts_with(b"<<<<<<< "))
+}
+
+/// The number of bytes checked for a NUL byte before counting lines.
+const BINARY_SCAN_BYTES: usize = 8192;
+
+/// Counts the lines of a text file, or returns `None` for a binary file.
+///
+/// A file is binary if its first 8 KB contain a NUL byte. A last line without
+/// a trailing newline still counts.
+pub fn count_lines(path: &Path) -> anyhow::Result<Option<usize>> {
+    let file = File::open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read '{}': {e}", path.display()))?;
+    let mut reader = BufReader::with_capacity(BINARY_SCAN_BYTES, file);
+    let mut lines = 0;
+    let mut last_byte = None;
+    let mut first_chunk = true;
+    loop {
+        let chunk = reader.fill_buf()?;
+        if chunk.is_empty() {
+            break;
+        }
+        if first_chunk && chunk.contains(&0) {
+            return Ok(None);
+        }
+        first_chunk = false;
+        lines += chunk.iter().filter(|&&byte| byte == b'\n').count();
+        last_byte = chunk.last().copied();
+        let len = chunk.len();
+        reader.consume(len);
+    }
+    Ok(Some(lines + usize::from(last_byte.is_some_and(|byte| byte != b'\n'))))
+}
+
+/// The number of bytes read to detect a MIME type from a file's contents.
+const MIME_SNIFF_BYTES: u64 = 512;
+
+/// Detects the MIME type of a file, e.g. `text/x-rust` or `image/png`.
+///
+/// A known extension is trusted without opening the file. Otherwise the first
+/// 512 bytes are matched against known signatures, falling back to `text/plain`
+/// for UTF-8 text and `application/octet-stream` for anything else. Returns
+/// `None` if the file cannot be read.
+pub fn detect_mime(path: &Path) -> Option<String> {
+    if let Some(mime) = mime_guess::from_path(path).first_raw() {
+        return Some(mime.to_string());
+    }
+    let file = File::open(path).ok()?;
+    let mut buffer = Vec::new();
+    BufReader::new(file).take(MIME_SNIFF_BYTES).read_to_end(&mut buffer).ok()?;
+    if let Some(kind) = infer::get(&buffer) {
+        return Some(kind.mime_type().to_string());
+    }
+    // The sample may end in the middle of a multi-byte character.
+    let is_text = !buffer.contains(&0)
+        && std::str::from_utf8(&buffer).map_or_else(|e| e.error_len().is_none(), |_| true);
+    let mime = if is_text { "text/plain" } else { "application/octet-stream" };
+    Some(mime.to_string())
+}
+
+/// Reads exclusion patterns from a file, or from stdin when `path` is `-`.
+///
+/// The file uses the `.gitignore` format: one pattern per line, with blank
+/// lines and `#` comments skipped.
+pub fn load_patterns_from_file(path: &Path) -> anyhow::Result<Vec<String>> {
+    let mut text = String::new();
+    if path == Path::new("-") {
+        io::stdin().read_to_string(&mut text)?;
+    } else {
+        text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read '{}': {e}", path.display()))?;
+    }
+    Ok(text
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Builds one matcher from every `--exclude-from` file, rooted at `root`.
+///
+/// Returns `None` when no files were given, so callers can skip matching.
+pub fn build_exclude_matcher(root: &Path, files: &[PathBuf]) -> anyhow::Result<Option<Gitignore>> {
+    if files.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GitignoreBuilder::new(root);
+    for file in files {
+        for pattern in load_patterns_from_file(file)? {
+            builder.add_line(Some(file.clone()), &pattern)?;
+        }
+    }
+    Ok(Some(builder.build()?))
+}
+
+/// Checks whether an entry below the root matches the exclusion patterns.
+pub fn is_excluded(matcher: &Gitignore, entry: &DirEntry) -> bool {
+    let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+    entry.depth() > 0 && matcher.matched(entry.path(), is_dir).is_ignore()
+}
+
+/// Filters entries with the `--include` and `--exclude` glob patterns, matched
+/// against each entry's path relative to the scanned root.
+///
+/// Files are kept if they match any `--include` pattern; directories are always
+/// kept so the matches stay reachable. Entries matching any `--exclude`
+/// pattern are pruned, and a pattern such as `target/**` also prunes `target`.
+#[derive(Debug, Clone)]
+pub struct GlobFilter {
+    root: PathBuf,
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl GlobFilter {
+    pub fn new(root: &Path, include: &[String], exclude: &[String]) -> anyhow::Result<Self> {
+        let build = |flag: &str, patterns: &[String]| -> anyhow::Result<GlobSet> {
+            let mut builder = GlobSetBuilder::new();
+            for pattern in patterns {
+                let glob = |pattern: &str| {
+                    Glob::new(pattern)
+                        .map_err(|e| anyhow::anyhow!("Invalid {flag} glob '{pattern}': {e}"))
+                };
+                builder.add(glob(pattern)?);
+                if let Some(dir) = pattern.strip_suffix("/**").filter(|_| flag == "--exclude") {
+                    builder.add(glob(dir)?);
+                }
+            }
+            Ok(builder.build()?)
+        };
+        Ok(Self {
+            root: root.to_path_buf(),
+            include: build("--include", include)?,
+            exclude: build("--exclude", exclude)?,
+        })
+    }
+
+    /// Returns true if either flag was given.
+    pub fn is_active(&self) -> bool {
+        !self.include.is_empty() || !self.exclude.is_empty()
+    }
+
+    /// Returns true if the entry and everything below it should not be walked.
+    pub fn prunes(&self, entry: &DirEntry) -> bool {
+        entry.depth() > 0 && self.exclude.is_match(self.relative(entry.path()))
+    }
+
+    /// Returns true if the entry matches `--include`, or is a directory.
+    pub fn keeps(&self, entry: &DirEntry) -> bool {
+        self.include.is_empty()
+            || entry.file_type().is_some_and(|ft| ft.is_dir())
+            || self.include.is_match(self.relative(entry.path()))
+    }
+
+    fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+}
+
+/// Stops a walk once `--max-files` or `--max-dirs` is exceeded.
+///
+/// Meant for `WalkBuilder::filter_entry`: after the limit is hit, every further
+/// entry is rejected, so no more directories are read. The counters are shared
+/// between clones, which keeps the limit exact on the parallel walker.
+#[derive(Debug, Clone, Default)]
+pub struct WalkLimit {
+    max_files: Option<usize>,
+    max_dirs: Option<usize>,
+    files: Arc<AtomicUsize>,
+    dirs: Arc<AtomicUsize>,
+    truncated: Arc<AtomicBool>,
+}
+
+impl WalkLimit {
+    pub fn new(max_files: Option<usize>, max_dirs: Option<usize>) -> Self {
+        Self { max_files, max_dirs, ..Default::default() }
+    }
+
+    /// Returns true if either limit was given.
+    pub fn is_active(&self) -> bool {
+        self.max_files.is_some() || self.max_dirs.is_some()
+    }
+
+    /// Counts the entry, returning false once the walk is over a limit.
+    pub fn admits(&self, entry: &DirEntry) -> bool {
+        if entry.depth() == 0 {
+            return true;
+        }
+        if self.truncated.load(Ordering::Relaxed) {
+            return false;
+        }
+        let (count, max) = if entry.file_type().is_some_and(|ft| ft.is_dir()) {
+            (&self.dirs, self.max_dirs)
+        } else {
+            (&self.files, self.max_files)
+        };
+        let admitted = max.is_none_or(|max| count.fetch_add(1, Ordering::Relaxed) < max);
+        if !admitted {
+            self.truncated.store(true, Ordering::Relaxed);
+        }
+        admitted
+    }
+
+    /// Returns true if the walk stopped early.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated.load(Ordering::Relaxed)
+    }
+}
+
+/// Parses a size such as `512`, `4K`, `1.5M` or `2GiB` into bytes.
+///
+/// Suffixes are binary and case-insensitive: `K`/`KiB`, `M`/`MiB`, `G`/`GiB`
+/// and `T`/`TiB`, with an optional trailing `B`.
+pub fn parse_size_string(size: &str) -> Result<u64, String> {
+    let trimmed = size.trim();
+    let split = trimmed.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(trimmed.len());
+    let (number, suffix) = trimmed.split_at(split);
+    let multiplier: u64 = match suffix.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => return Err(format!("invalid size suffix in '{size}' (expected K, M, G or T)")),
+    };
+    let number: f64 =
+        number.parse().map_err(|_| format!("invalid size '{size}' (expected e.g. 512K or 1M)"))?;
+    Ok((number * multiplier as f64).round() as u64)
+}
+
+/// Returns true if the entry is a directory or a file within `--min-size` and `--max-size`.
+pub fn size_in_range(entry: &DirEntry, min: Option<u64>, max: Option<u64>) -> bool {
+    if entry.file_type().is_some_and(|ft| ft.is_dir()) {
+        return true;
+    }
+    let Ok(len) = entry.metadata().map(|m| m.len()) else {
+        return false;
+    };
+    min.is_none_or(|min| len >= min) && max.is_none_or(|max| len <= max)
+}
+
+/// Parses a `YYYY-MM-DD` date, for `--newer-than` and `--older-than`, as
+/// midnight UTC at the start of that day.
+pub fn parse_date(date: &str) -> Result<SystemTime, String> {
+    let day = chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|e| format!("invalid date '{date}' (expected YYYY-MM-DD): {e}"))?;
+    let seconds = day.and_hms_opt(0, 0, 0).expect("midnight is a valid time").and_utc().timestamp();
+    let offset = Duration::from_secs(seconds.unsigned_abs());
+    Ok(if seconds >= 0 { SystemTime::UNIX_EPOCH + offset } else { SystemTime::UNIX_EPOCH - offset })
+}
+
+/// Returns true if the entry is a directory or a file modified after `newer`
+/// and before `older`.
+pub fn modified_in_range(
+    entry: &DirEntry,
+    newer: Option<SystemTime>,
+    older: Option<SystemTime>,
+) -> bool {
+    if entry.file_type().is_some_and(|ft| ft.is_dir()) || (newer.is_none() && older.is_none()) {
+        return true;
+    }
+    let Ok(modified) = entry.metadata().and_then(|m| m.modified().map_err(Into::into)) else {
+        return false;
+    };
+    newer.is_none_or(|newer| modified > newer) && older.is_none_or(|older| modified < older)
+}
+
+/// Compiles the `--filter-regex` pattern, which is matched against file names.
+pub fn compile_filter_regex(pattern: Option<&str>) -> anyhow::Result<Option<Regex>> {
+    pattern
+        .map(|pattern| {
+            Regex::new(pattern)
+                .map_err(|e| anyhow::anyhow!("Invalid --filter-regex pattern '{pattern}': {e}"))
+        })
+        .transpose()
+}
+
+/// Returns true if the entry is a directory or its file name matches `regex`.
+pub fn matches_name_regex(regex: &Regex, entry: &DirEntry) -> bool {
+    entry.file_type().is_some_and(|ft| ft.is_dir())
+        || regex.is_match(&entry.file_name().to_string_lossy())
+}
+
+/// Keeps or hides files by extension, for `--filter-ext` and `--exclude-ext`.
+///
+/// Extensions are compared case-insensitively, with or without a leading dot.
+/// Directories are always kept, except with `--dirs-only`, where the filter
+/// applies to their own names.
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    dirs_only: bool,
+}
+
+impl ExtensionFilter {
+    pub fn new(include: &[String], exclude: &[String], dirs_only: bool) -> Self {
+        let normalize = |exts: &[String]| {
+            exts.iter().map(|ext| ext.trim_start_matches('.').to_lowercase()).collect()
+        };
+        Self { include: normalize(include), exclude: normalize(exclude), dirs_only }
+    }
+
+    /// Returns true if an extension was given to either flag.
+    pub fn is_active(&self) -> bool {
+        !self.include.is_empty() || !self.exclude.is_empty()
+    }
+
+    /// Returns true if the entry stays in the listing.
+    pub fn keeps(&self, path: &Path, is_dir: bool) -> bool {
+        if is_dir && !self.dirs_only {
+            return true;
+        }
+        let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase());
+        let matches = |exts: &[String]| extension.as_ref().is_some_and(|ext| exts.contains(ext));
+        (self.include.is_empty() || matches(&self.include)) && !matches(&self.exclude)
+    }
+}
+
+/// The order in which entries are visited by post-processing passes.
+#[derive(clap::ValueEnum, Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum WalkStrategy {
+    /// Parents before their children, as the walker yields them.
+    #[default]
+    Topdown,
+    /// Children before their parents, so values can be aggregated upwards.
+    Bottomup,
+}
+
+/// Calls `f` on every entry, deepest entries first.
+///
+/// Entries at the same depth are visited in path order, so every directory is
+/// visited after all of its descendants. The slice itself keeps its order.
+pub fn process_bottom_up<F: FnMut(&DirEntry)>(entries: &[DirEntry], mut f: F) {
+    let mut order: Vec<&DirEntry> = entries.iter().collect();
+    order.sort_by(|a, b| b.depth().cmp(&a.depth()).then_with(|| a.path().cmp(b.path())));
+    for entry in order {
+        f(entry);
+    }
+}
+
+/// Returns a spinner on stderr that counts the scanned entries.
+pub fn scan_progress() -> ProgressBar {
+    let bar = ProgressBar::new_spinner();
+    bar.set_style(
+        ProgressStyle::with_template("{spinner} Scanning... {pos} entries")
+            .expect("the progress template is valid"),
+    );
+    bar.enable_steady_tick(Duration::from_millis(100));
+    bar
+}
+
+/// Runs the walk, on several threads when `parallel` is set.
+///
+/// Parallel results arrive in any order, so they are sorted by path. That keeps
+/// each directory before its contents and makes the output deterministic.
+/// Each result advances `progress`, if given.
+pub fn walk(
+    builder: &WalkBuilder,
+    parallel: bool,
+    progress: Option<&ProgressBar>,
+) -> Vec<Result<DirEntry, ignore::Error>> {
+    if !parallel {
+        return builder
+            .build()
+            .inspect(|_| {
+                if let Some(bar) = progress {
+                    bar.inc(1);
+                }
+            })
+            .collect();
+    }
+    let (tx, rx) = mpsc::channel();
+    builder.build_parallel().run(|| {
+        let tx = tx.clone();
+        let progress = progress.cloned();
+        Box::new(move |result| {
+            if let Some(bar) = &progress {
+                bar.inc(1);
+            }
+            _ = tx.send(result);
+            WalkState::Continue
+        })
+    });
+    drop(tx);
+    let mut results: Vec<_> = rx.into_iter().collect();
+    results
+        .sort_by(|a, b| a.as_ref().ok().map(|e| e.path()).cmp(&b.as_ref().ok().map(|e| e.path())));
+    results
+}
+
+/// Computes the total size of each directory from the files below it.
+///
+/// Subdirectory totals are complete before they are added to their parent
+/// because the entries are processed bottom-up.
+pub fn accumulate_dir_sizes(entries: &[DirEntry]) -> HashMap<PathBuf, u64> {
+    let mut sizes: HashMap<PathBuf, u64> = HashMap::new();
+    process_bottom_up(entries, |entry| {
+        let total = if entry.file_type().is_some_and(|ft| ft.is_dir()) {
+            sizes.get(entry.path()).copied().unwrap_or(0)
+        } else {
+            entry.metadata().map_or(0, |m| m.len())
+        };
+        if let Some(parent) = entry.path().parent() {
+            *sizes.entry(parent.to_path_buf()).or_default() += total;
+        }
+    });
+    sizes
+}
+
+/// Counts the immediate children of each directory on disk, reading each one once.
+///
+/// Unlike the walk, this ignores `--level` and the filters, so it reflects how
+/// crowded a directory really is. Unreadable directories are left out.
+pub fn read_child_counts<'a>(dirs: impl IntoIterator<Item = &'a Path>) -> HashMap<PathBuf, usize> {
+    dirs.into_iter()
+        .filter_map(|dir| Some((dir.to_path_buf(), std::fs::read_dir(dir).ok()?.count())))
+        .collect()
+}
+
+/// Returns the `n` most recently modified files, newest first.
+///
+/// Keeps a min-heap of at most `n` candidates, so the oldest candidate is
+/// evicted whenever a newer file is found.
+pub fn find_most_recent(entries: &[DirEntry], n: usize) -> Vec<(DirEntry, SystemTime)> {
+    let mut heap: BinaryHeap<Reverse<(SystemTime, usize)>> = BinaryHeap::with_capacity(n + 1);
+    for (index, entry) in entries.iter().enumerate() {
+        if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+            continue;
+        }
+        let Some(modified) = entry.metadata().ok().and_then(|m| m.modified().ok()) else {
+            continue;
+        };
+        heap.push(Reverse((modified, index)));
+        if heap.len() > n {
+            heap.pop();
+        }
+    }
+
+    // Ascending order of `Reverse` is descending order of the timestamps.
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((modified, index))| (entries[index].clone(), modified))
+        .collect()
+}
+
+/// Groups files by (directory, stem) and keeps the stems present with several extensions.
+///
+/// For example, `config.json` and `config.yaml` in the same directory produce the
+/// entry `(dir, "config") => ["json", "yaml"]`.
+pub fn find_duplicate_extensions(entries: &[DirEntry]) -> HashMap<(PathBuf, String), Vec<String>> {
+    group_extensions_by_stem(entries, true)
+}
+
+/// Like `find_duplicate_extensions`, but groups stems across the whole tree.
+///
+/// The directory in each key is left empty.
+pub fn find_stem_conflicts(entries: &[DirEntry]) -> HashMap<(PathBuf, String), Vec<String>> {
+    group_extensions_by_stem(entries, false)
+}
+
+/// Returns the key a file is grouped under by the duplicate-extension reports.
+///
+/// Files without an extension are never part of a conflict.
+pub fn stem_key(path: &Path, per_directory: bool) -> Option<(PathBuf, String)> {
+    path.extension()?;
+    let stem = path.file_stem()?.to_string_lossy().into_owned();
+    let dir = if per_directory { path.parent()?.to_path_buf() } else { PathBuf::new() };
+    Some((dir, stem))
+}
+
+fn group_extensions_by_stem(
+    entries: &[DirEntry],
+    per_directory: bool,
+) -> HashMap<(PathBuf, String), Vec<String>> {
+    let mut groups: HashMap<(PathBuf, String), Vec<String>> = HashMap::new();
+    for entry in entries.iter().filter(|e| e.file_type().is_some_and(|ft| ft.is_file())) {
+        let path = entry.path();
+        let (Some(key), Some(ext)) = (stem_key(path, per_directory), path.extension()) else {
+            continue;
+        };
+        let extensions = groups.entry(key).or_default();
+        let ext = ext.to_string_lossy().into_owned();
+        if !extensions.contains(&ext) {
+            extensions.push(ext);
+        }
+    }
+    groups.retain(|_, extensions| extensions.len() > 1);
+    groups.values_mut().for_each(|extensions| extensions.sort());
+    groups
+}
+
+/// Formats how long ago `time` was relative to `now` (e.g. "5 minutes ago").
+pub fn format_relative_time(time: SystemTime, now: SystemTime) -> String {
+    const UNITS: [(&str, u64); 7] = [
+        ("year", 365 * 24 * 3600),
+        ("month", 30 * 24 * 3600),
+        ("week", 7 * 24 * 3600),
+        ("day", 24 * 3600),
+        ("hour", 3600),
+        ("minute", 60),
+        ("second", 1),
+    ];
+
+    let elapsed = now.duration_since(time).unwrap_or(Duration::ZERO).as_secs();
+    for (unit, seconds) in UNITS {
+        let count = elapsed / seconds;
+        if count > 0 {
+            let plural = if count == 1 { "" } else { "s" };
+            return format!("{count} {unit}{plural} ago");
+        }
+    }
+    "just now".to_string()
+}
 
 /// Formats a size in bytes into a human-readable string using binary prefixes (KiB, MiB).
 pub fn format_size(bytes: u64) -> String {
@@ -24,6 +978,57 @@ pub fn format_size(bytes: u64) -> String {
     }
 }
 
+/// The permission problems `--permission-fix` can suggest fixes for.
+#[derive(clap::ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PermissionIssue {
+    /// Writable by everyone (directories with the sticky bit, like `/tmp`, are fine).
+    WorldWritable,
+    /// Runs with the privileges of its owner.
+    Setuid,
+    /// Runs with the privileges of its group (ignored on directories, where it is common).
+    Setgid,
+    /// Every issue above.
+    All,
+}
+
+impl PermissionIssue {
+    /// Returns the `chmod` expression fixing this issue, if `mode` has it.
+    pub fn fix_for(self, mode: u32, is_dir: bool) -> Option<&'static str> {
+        match self {
+            Self::WorldWritable if mode & 0o002 != 0 && !(is_dir && mode & 0o1000 != 0) => {
+                Some("o-w")
+            }
+            Self::Setuid if mode & 0o4000 != 0 => Some("u-s"),
+            Self::Setgid if mode & 0o2000 != 0 && !is_dir => Some("g-s"),
+            _ => None,
+        }
+    }
+
+    /// Returns the `chmod` expressions fixing every requested issue that `mode` has.
+    pub fn fixes_for(issues: &[Self], mode: u32, is_dir: bool) -> Vec<&'static str> {
+        let expand = |issue: Self| match issue {
+            Self::All => vec![Self::WorldWritable, Self::Setuid, Self::Setgid],
+            issue => vec![issue],
+        };
+        let mut fixes: Vec<&'static str> = issues
+            .iter()
+            .flat_map(|&issue| expand(issue))
+            .filter_map(|issue| issue.fix_for(mode, is_dir))
+            .collect();
+        fixes.dedup();
+        fixes
+    }
+}
+
+/// Builds a `chmod` command applying `target_expr` to `path`.
+///
+/// The path is single-quoted for the shell and the current mode is kept as a
+/// trailing comment, e.g. `chmod o-w '/srv/app.log' # was 0666`.
+pub fn suggest_chmod(path: &Path, mode: u32, target_expr: &str) -> String {
+    let quoted = path.display().to_string().replace('\'', r"'\''");
+    format!("chmod {target_expr} '{quoted}' # was {:04o}", mode & 0o7777)
+}
+
 /// Formats a Unix file mode into a human-readable string (e.g., "rwxr-xr-x").
 #[cfg(unix)]
 pub fn format_permissions(mode: u32) -> String {
@@ -39,6 +1044,49 @@ pub fn format_permissions(mode: u32) -> String {
     format!("{user_r}{user_w}{user_x}{group_r}{group_w}{group_x}{other_r}{other_w}{other_x}")
 }
 
+/// Formats the permission bits of a Unix file mode as four octal digits,
+/// including the SUID, SGID and sticky bits (e.g., "0755" or "4755").
+pub fn format_octal_permissions(mode: u32) -> String {
+    format!("{:04o}", mode & 0o7777)
+}
+
+/// A permission that `--highlight-special` marks with a background color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionHighlight {
+    /// Anyone may modify the file (`o+w`).
+    WorldWritable,
+    /// The file runs as its owner (`u+s`).
+    Suid,
+    /// The file runs with its group (`g+s`).
+    Sgid,
+}
+
+impl PermissionHighlight {
+    /// The background color of the highlighted name.
+    pub fn color(self) -> colored::Color {
+        match self {
+            PermissionHighlight::WorldWritable => colored::Color::Yellow,
+            PermissionHighlight::Suid => colored::Color::Red,
+            PermissionHighlight::Sgid => colored::Color::Magenta,
+        }
+    }
+}
+
+/// Classifies the special permission bits of a Unix file mode.
+///
+/// When several apply, SUID wins over SGID, which wins over world-writable.
+pub fn classify_special_permissions(mode: u32) -> Option<PermissionHighlight> {
+    if mode & 0o4000 != 0 {
+        Some(PermissionHighlight::Suid)
+    } else if mode & 0o2000 != 0 {
+        Some(PermissionHighlight::Sgid)
+    } else if mode & 0o002 != 0 {
+        Some(PermissionHighlight::WorldWritable)
+    } else {
+        None
+    }
+}
+
 // Unit tests for utility functions
 #[cfg(test)]
 mod tests {
@@ -56,6 +1104,475 @@ mod tests {
         assert_eq!(format_size(gib), "1.0 GiB");
     }
 
+    #[test]
+    fn test_check_readability() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let present = temp_dir.path().join("present.txt");
+        std::fs::write(&present, "data").unwrap();
+        let missing = temp_dir.path().join("missing.txt");
+
+        let failures = check_readability(&[present.clone(), missing.clone()]);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, missing);
+        assert_eq!(failures[0].1.kind(), io::ErrorKind::NotFound);
+
+        // Opening for writing must not truncate the file.
+        assert!(check_writability(std::slice::from_ref(&present)).is_empty());
+        assert_eq!(std::fs::read_to_string(&present).unwrap(), "data");
+    }
+
+    #[test]
+    fn test_nearest_basic_color() {
+        assert_eq!(nearest_basic_color(0, 0, 0), colored::Color::Black);
+        assert_eq!(nearest_basic_color(255, 255, 255), colored::Color::White);
+        assert_eq!(nearest_basic_color(250, 20, 30), colored::Color::Red);
+        assert_eq!(nearest_basic_color(30, 200, 60), colored::Color::Green);
+        assert_eq!(nearest_basic_color(10, 10, 200), colored::Color::Blue);
+        assert_eq!(nearest_basic_color(240, 230, 10), colored::Color::Yellow);
+        assert_eq!(nearest_basic_color(0, 190, 220), colored::Color::Cyan);
+        assert_eq!(nearest_basic_color(200, 0, 180), colored::Color::Magenta);
+    }
+
+    #[test]
+    fn test_nearest_ansi256_index() {
+        assert_eq!(nearest_ansi256_index(0, 0, 0), 16);
+        assert_eq!(nearest_ansi256_index(255, 255, 255), 231);
+        assert_eq!(nearest_ansi256_index(255, 0, 0), 196);
+        // 100 is closest to the 95 level, 200 to the 215 level.
+        assert_eq!(nearest_ansi256_index(100, 200, 0), 16 + 36 + 6 * 4);
+    }
+
+    #[test]
+    fn test_format_relative_time() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        assert_eq!(format_relative_time(now, now), "just now");
+        assert_eq!(format_relative_time(now + Duration::from_secs(5), now), "just now");
+        assert_eq!(format_relative_time(now - Duration::from_secs(1), now), "1 second ago");
+        assert_eq!(format_relative_time(now - Duration::from_secs(150), now), "2 minutes ago");
+        assert_eq!(format_relative_time(now - Duration::from_secs(3 * 86400), now), "3 days ago");
+        assert_eq!(format_relative_time(now - Duration::from_secs(59), now), "59 seconds ago");
+        assert_eq!(format_relative_time(now - Duration::from_secs(3599), now), "59 minutes ago");
+        assert_eq!(format_relative_time(now - Duration::from_secs(3 * 3600), now), "3 hours ago");
+        assert_eq!(format_relative_time(now - Duration::from_secs(6 * 86400), now), "6 days ago");
+        assert_eq!(format_relative_time(now - Duration::from_secs(15 * 86400), now), "2 weeks ago");
+        assert_eq!(
+            format_relative_time(now - Duration::from_secs(3 * 365 * 86400), now),
+            "3 years ago"
+        );
+    }
+
+    #[test]
+    fn test_find_duplicate_extensions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let nested = temp_dir.path().join("nested");
+        std::fs::create_dir(&nested).unwrap();
+        for path in ["config.json", "config.yaml", "config", "main.rs", "nested/main.c"] {
+            std::fs::File::create(temp_dir.path().join(path)).unwrap();
+        }
+        let entries: Vec<DirEntry> =
+            ignore::WalkBuilder::new(temp_dir.path()).build().filter_map(Result::ok).collect();
+
+        let duplicates = find_duplicate_extensions(&entries);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(
+            duplicates[&(temp_dir.path().to_path_buf(), "config".to_string())],
+            vec!["json", "yaml"]
+        );
+
+        let stems = find_stem_conflicts(&entries);
+        assert_eq!(stems.len(), 2);
+        assert_eq!(stems[&(PathBuf::new(), "main".to_string())], vec!["c", "rs"]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_file_type_filter() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file = temp_dir.path().join("file.txt");
+        let link = temp_dir.path().join("link");
+        let socket = temp_dir.path().join("socket");
+        let pipe = temp_dir.path().join("pipe");
+        std::fs::File::create(&file).unwrap();
+        assert!(std::process::Command::new("mkfifo").arg(&pipe).status().unwrap().success());
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+        let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        let file_type = |path: &Path| std::fs::symlink_metadata(path).unwrap().file_type();
+
+        let cases = [
+            (FileTypeFilter::File, file_type(&file)),
+            (FileTypeFilter::Directory, file_type(temp_dir.path())),
+            (FileTypeFilter::Symlink, file_type(&link)),
+            (FileTypeFilter::Pipe, file_type(&pipe)),
+            (FileTypeFilter::Socket, file_type(&socket)),
+            (FileTypeFilter::CharDevice, file_type(Path::new("/dev/null"))),
+        ];
+        for (filter, matching) in cases {
+            for (other, file_type) in cases {
+                assert_eq!(filter.matches(file_type), filter == other, "{filter:?} vs {other:?}");
+            }
+            assert!(filter.keeps(Some(file_type(temp_dir.path()))));
+            assert!(filter.keeps(Some(matching)));
+        }
+        assert!(!FileTypeFilter::BlockDevice.matches(file_type(&file)));
+        assert!(!FileTypeFilter::File.keeps(None));
+    }
+
+    #[test]
+    fn test_ansi_stripper() {
+        let mut stripper = AnsiStripper::default();
+        assert_eq!(stripper.strip(b"\x1B[1;31mred\x1B[0m plain"), b"red plain");
+        let link = b"\x1B]8;;file:///a\x07a.rs\x1B]8;;\x07 \x1B]8;;x\x1B\\b\x1B]8;;\x1B\\";
+        assert_eq!(stripper.strip(link), b"a.rs b");
+
+        // A sequence split across writes is still removed.
+        assert_eq!(stripper.strip(b"one \x1B["), b"one ");
+        assert_eq!(stripper.strip(b"38;5;20"), b"");
+        assert_eq!(stripper.strip(b"mtwo\n"), b"two\n");
+        assert_eq!(stripper.strip("└── ü".as_bytes()), "└── ü".as_bytes());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_is_sparse_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let empty = temp_dir.path().join("empty");
+        std::fs::write(&empty, "").unwrap();
+        assert!(!is_sparse_file(&std::fs::metadata(&empty).unwrap()));
+
+        let dense = temp_dir.path().join("dense");
+        std::fs::write(&dense, vec![1u8; 64 * 1024]).unwrap();
+        assert!(!is_sparse_file(&std::fs::metadata(&dense).unwrap()));
+
+        // Extending a file without writing leaves a hole on file systems that support them.
+        let sparse = temp_dir.path().join("sparse");
+        File::create(&sparse).unwrap().set_len(16 * 1024 * 1024).unwrap();
+        let metadata = std::fs::metadata(&sparse).unwrap();
+        if allocated_bytes(&metadata) < metadata.len() {
+            assert!(is_sparse_file(&metadata));
+        }
+        assert!(!is_sparse_file(&std::fs::metadata(temp_dir.path()).unwrap()));
+    }
+
+    #[test]
+    fn test_count_lines() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, contents: &[u8]| {
+            let path = temp_dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        };
+
+        assert_eq!(count_lines(&write("five.txt", b"1\n2\n3\n4\n5\n")).unwrap(), Some(5));
+        assert_eq!(count_lines(&write("no_newline.txt", b"1\n2")).unwrap(), Some(2));
+        assert_eq!(count_lines(&write("empty.txt", b"")).unwrap(), Some(0));
+        assert_eq!(count_lines(&write("data.bin", b"\x7fELF\0\n")).unwrap(), None);
+        // Only the start of a file is checked for NUL bytes.
+        let mut late_nul = vec![b'\n'; BINARY_SCAN_BYTES];
+        late_nul.push(0);
+        assert_eq!(
+            count_lines(&write("late.txt", &late_nul)).unwrap(),
+            Some(BINARY_SCAN_BYTES + 1)
+        );
+        assert!(count_lines(&temp_dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_detect_mime() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, contents: &[u8]| {
+            let path = temp_dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        };
+
+        // The extension wins, even over the contents.
+        assert_eq!(detect_mime(&write("main.rs", b"\x89PNG\r\n\x1a\n")).unwrap(), "text/x-rust");
+        assert_eq!(
+            detect_mime(&write("logo", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR")).unwrap(),
+            "image/png"
+        );
+        assert_eq!(detect_mime(&write("notes", "caf\u{e9}".as_bytes())).unwrap(), "text/plain");
+        // A sample cut in the middle of a character is still text.
+        assert_eq!(detect_mime(&write("cut", &"\u{e9}".as_bytes()[..1])).unwrap(), "text/plain");
+        assert_eq!(detect_mime(&write("blob", b"\0\x01\x02")).unwrap(), "application/octet-stream");
+        assert_eq!(detect_mime(&temp_dir.path().join("missing")), None);
+    }
+
+    #[test]
+    fn test_has_conflict_markers() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, contents: &[u8]| {
+            let path = temp_dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        };
+
+        let conflict = write("merge.rs", b"fn a() {}\n<<<<<<< HEAD\nx\n=======\ny\n>>>>>>> main\n");
+        assert!(has_conflict_markers(&conflict));
+        assert!(!has_conflict_markers(&write("clean.rs", b"fn a() {}\n")));
+        // The marker must start a line and be followed by a space.
+        assert!(!has_conflict_markers(&write("inline.md", b"see <<<<<<< HEAD\n")));
+        assert!(!has_conflict_markers(&write("banner.txt", b"<<<<<<<<<<\n")));
+        assert!(!has_conflict_markers(&write("blob.bin", b"\0<<<<<<< HEAD\n")));
+        // Markers past the first 8 KB are not searched.
+        let mut late = vec![b'a'; 9000];
+        late.extend_from_slice(b"\n<<<<<<< HEAD\n");
+        assert!(!has_conflict_markers(&write("late.txt", &late)));
+        assert!(!has_conflict_markers(&temp_dir.path().join("missing")));
+    }
+
+    #[test]
+    fn test_exclude_matcher() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("build/out")).unwrap();
+        std::fs::write(root.join("build/out/app"), "").unwrap();
+        std::fs::write(root.join("main.rs"), "").unwrap();
+        std::fs::write(root.join("debug.log"), "").unwrap();
+        std::fs::write(root.join("keep.log"), "").unwrap();
+        std::fs::write(root.join("first.txt"), "# generated\n\nbuild/\n").unwrap();
+        std::fs::write(root.join("second.txt"), "*.log\r\n!keep.log\n").unwrap();
+
+        let first = root.join("first.txt");
+        assert_eq!(load_patterns_from_file(&first).unwrap(), ["build/"]);
+        assert!(load_patterns_from_file(&root.join("missing.txt")).is_err());
+
+        let matcher =
+            build_exclude_matcher(root, &[first, root.join("second.txt")]).unwrap().unwrap();
+        let mut kept: Vec<String> = ignore::WalkBuilder::new(root)
+            .filter_entry(move |entry| !is_excluded(&matcher, entry))
+            .build()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.depth() > 0)
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        kept.sort();
+        assert_eq!(kept, ["first.txt", "keep.log", "main.rs", "second.txt"]);
+        assert!(build_exclude_matcher(root, &[]).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_glob_filter() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("target/debug")).unwrap();
+        std::fs::create_dir(root.join("src")).unwrap();
+        for file in ["src/main.rs", "Cargo.toml", "README.md", "target/debug/app.rs"] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+        let filter = GlobFilter::new(
+            root,
+            &["*.{rs,toml}".to_string()],
+            &["target/**".to_string(), "*.md".to_string()],
+        )
+        .unwrap();
+
+        let mut walker = ignore::WalkBuilder::new(root);
+        let pruning = filter.clone();
+        walker.filter_entry(move |entry| !pruning.prunes(entry));
+        let mut listed: Vec<String> = walker
+            .build()
+            .flatten()
+            .filter(|entry| entry.depth() > 0 && filter.keeps(entry))
+            .map(|entry| entry.path().strip_prefix(root).unwrap().display().to_string())
+            .collect();
+        listed.sort();
+        assert_eq!(listed, ["Cargo.toml", "src", "src/main.rs"]);
+
+        let err = GlobFilter::new(root, &["a[".to_string()], &[]).unwrap_err();
+        assert!(err.to_string().contains("--include"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_size_string() {
+        assert_eq!(parse_size_string("512"), Ok(512));
+        assert_eq!(parse_size_string("1M"), Ok(1_048_576));
+        assert_eq!(parse_size_string("1MiB"), Ok(1_048_576));
+        assert_eq!(parse_size_string("4k"), Ok(4096));
+        assert_eq!(parse_size_string("1.5K"), Ok(1536));
+        assert_eq!(parse_size_string("2GiB"), Ok(2 << 30));
+        assert!(parse_size_string("10X").is_err());
+        assert!(parse_size_string("M").is_err());
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(
+            parse_date("2024-01-01"),
+            Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(1_704_067_200))
+        );
+        assert_eq!(parse_date("1970-01-01"), Ok(SystemTime::UNIX_EPOCH));
+        assert!(parse_date("2024-13-01").is_err());
+        assert!(parse_date("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_extension_filter() {
+        let include = ExtensionFilter::new(&["rs".to_string(), ".TOML".to_string()], &[], false);
+        assert!(include.keeps(Path::new("src/main.RS"), false));
+        assert!(include.keeps(Path::new("Cargo.toml"), false));
+        assert!(!include.keeps(Path::new("README.md"), false));
+        assert!(!include.keeps(Path::new("Makefile"), false));
+        assert!(include.keeps(Path::new("docs"), true));
+
+        let exclude = ExtensionFilter::new(&[], &["lock".to_string()], true);
+        assert!(!exclude.keeps(Path::new("Cargo.lock"), false));
+        assert!(exclude.keeps(Path::new("Makefile"), false));
+        assert!(!exclude.keeps(Path::new("build.lock"), true));
+        assert!(!ExtensionFilter::default().is_active());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_is_symlink_cycle() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        std::fs::create_dir_all(dir.join("a/b")).unwrap();
+        std::fs::create_dir(dir.join("other")).unwrap();
+        symlink("..", dir.join("a/b/up")).unwrap();
+        symlink(dir.join("a"), dir.join("a/b/abs")).unwrap();
+        symlink("../../other", dir.join("a/b/sideways")).unwrap();
+        std::fs::write(dir.join("file.txt"), "").unwrap();
+        symlink("../../file.txt", dir.join("a/b/file")).unwrap();
+
+        assert!(is_symlink_cycle(&dir.join("a/b/up")));
+        assert!(is_symlink_cycle(&dir.join("a/b/abs")));
+        assert!(!is_symlink_cycle(&dir.join("a/b/sideways")));
+        assert!(!is_symlink_cycle(&dir.join("a/b/file")));
+        assert!(!is_symlink_cycle(&dir.join("a/b/missing")));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_filesystem_id() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        let root = filesystem_id(temp_dir.path()).unwrap();
+        assert_eq!(filesystem_id(&temp_dir.path().join("sub")), Some(root));
+        assert_eq!(filesystem_id(&temp_dir.path().join("missing")), None);
+    }
+
+    #[test]
+    fn test_process_bottom_up() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("a/b")).unwrap();
+        std::fs::write(temp_dir.path().join("a/b/deep.txt"), vec![0u8; 100]).unwrap();
+        std::fs::write(temp_dir.path().join("a/mid.txt"), vec![0u8; 50]).unwrap();
+        std::fs::write(temp_dir.path().join("top.txt"), vec![0u8; 7]).unwrap();
+        let entries: Vec<DirEntry> =
+            ignore::WalkBuilder::new(temp_dir.path()).build().filter_map(Result::ok).collect();
+
+        let mut visited = Vec::new();
+        process_bottom_up(&entries, |entry| {
+            let relative = entry.path().strip_prefix(temp_dir.path()).unwrap();
+            visited.push(relative.to_string_lossy().to_string());
+        });
+        assert_eq!(visited, ["a/b/deep.txt", "a/b", "a/mid.txt", "a", "top.txt", ""]);
+
+        let sizes = accumulate_dir_sizes(&entries);
+        assert_eq!(sizes[&temp_dir.path().join("a/b")], 100);
+        assert_eq!(sizes[&temp_dir.path().join("a")], 150);
+        assert_eq!(sizes[temp_dir.path()], 157);
+    }
+
+    #[test]
+    fn test_read_child_counts() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("crowded/nested")).unwrap();
+        for name in ["a", "b", "c"] {
+            std::fs::write(temp_dir.path().join("crowded").join(name), "").unwrap();
+        }
+        std::fs::create_dir(temp_dir.path().join("empty")).unwrap();
+
+        let dirs =
+            ["crowded", "crowded/nested", "empty", "missing"].map(|d| temp_dir.path().join(d));
+        let counts = read_child_counts(dirs.iter().map(PathBuf::as_path));
+        assert_eq!(counts[&dirs[0]], 4);
+        assert_eq!(counts[&dirs[1]], 0);
+        assert_eq!(counts[&dirs[2]], 0);
+        assert!(!counts.contains_key(&dirs[3]));
+    }
+
+    #[test]
+    fn test_normalize_for_sort() {
+        let nfc = "caf\u{e9}";
+        let nfd = "cafe\u{301}";
+        assert_ne!(nfc, nfd);
+        assert_eq!(normalize_for_sort(nfd, NormalizationForm::Nfc), nfc);
+        assert_eq!(normalize_for_sort(nfc, NormalizationForm::Nfd), nfd);
+        assert_eq!(
+            normalize_for_sort(nfc, NormalizationForm::Auto),
+            normalize_for_sort(nfd, NormalizationForm::Auto)
+        );
+        // Compatibility forms also fold ligatures such as `ﬁ`.
+        assert_eq!(normalize_for_sort("\u{fb01}le", NormalizationForm::Nfkc), "file");
+        assert_eq!(normalize_for_sort("\u{fb01}le", NormalizationForm::Nfc), "\u{fb01}le");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_resolve_symlink_chain() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        std::fs::File::create(dir.join("target.txt")).unwrap();
+        symlink("target.txt", dir.join("hop2")).unwrap();
+        symlink("hop2", dir.join("hop1")).unwrap();
+        symlink("missing.txt", dir.join("dangling")).unwrap();
+        symlink("loop_b", dir.join("loop_a")).unwrap();
+        symlink("loop_a", dir.join("loop_b")).unwrap();
+
+        let chain = resolve_symlink_chain(&dir.join("hop1"));
+        assert_eq!(chain.hops, vec![dir.join("hop2"), dir.join("target.txt")]);
+        assert!(!chain.is_broken && !chain.is_loop);
+
+        let broken = resolve_symlink_chain(&dir.join("dangling"));
+        assert_eq!(broken.hops, vec![dir.join("missing.txt")]);
+        assert!(broken.is_broken && !broken.is_loop);
+
+        let looping = resolve_symlink_chain(&dir.join("loop_a"));
+        assert_eq!(looping.hops, vec![dir.join("loop_b"), dir.join("loop_a")]);
+        assert!(looping.is_loop);
+
+        assert_eq!(resolve_symlink_chain(&dir.join("target.txt")), SymlinkChain::default());
+    }
+
+    #[test]
+    fn test_permission_fixes() {
+        assert_eq!(PermissionIssue::fixes_for(&[PermissionIssue::All], 0o666, false), vec!["o-w"]);
+        assert!(PermissionIssue::fixes_for(&[PermissionIssue::All], 0o644, false).is_empty());
+        // Sticky world-writable directories such as /tmp are intended.
+        assert!(PermissionIssue::fixes_for(&[PermissionIssue::All], 0o1777, true).is_empty());
+        assert_eq!(
+            PermissionIssue::fixes_for(&[PermissionIssue::All], 0o6757, false),
+            vec!["o-w", "u-s", "g-s"]
+        );
+        assert_eq!(
+            PermissionIssue::fixes_for(&[PermissionIssue::Setuid], 0o6757, false),
+            vec!["u-s"]
+        );
+        assert_eq!(
+            suggest_chmod(Path::new("/srv/it's.log"), 0o100666, "o-w"),
+            r"chmod o-w '/srv/it'\''s.log' # was 0666"
+        );
+    }
+
+    #[test]
+    fn test_error_policy() {
+        let denied = ignore::Error::Io(io::Error::from(io::ErrorKind::PermissionDenied));
+        let missing = ignore::Error::Io(io::Error::from(io::ErrorKind::NotFound));
+
+        let default_policy = ErrorPolicy::default();
+        assert!(!default_policy.is_suppressed(&denied));
+
+        let policy = ErrorPolicy { ignore_permission_denied: true };
+        assert!(policy.is_suppressed(&denied));
+        assert!(!policy.is_suppressed(&missing));
+    }
+
     #[test]
     #[cfg(unix)]
     fn test_format_permissions() {
@@ -69,4 +1586,62 @@ mod tests {
         let mode_user_only = 0o700;
         assert_eq!(format_permissions(mode_user_only), "rwx------");
     }
+
+    #[test]
+    fn test_format_octal_permissions() {
+        assert_eq!(format_octal_permissions(0o755), "0755");
+        // The file type bits of a regular file are dropped.
+        assert_eq!(format_octal_permissions(0o100644), "0644");
+        // SUID, SGID and sticky bits.
+        assert_eq!(format_octal_permissions(0o4755), "4755");
+        assert_eq!(format_octal_permissions(0o2755), "2755");
+        assert_eq!(format_octal_permissions(0o1755), "1755");
+        assert_eq!(format_octal_permissions(0o40000), "0000");
+    }
+
+    #[test]
+    fn test_classify_special_permissions() {
+        assert_eq!(classify_special_permissions(0o100755), None);
+        assert_eq!(classify_special_permissions(0o666), Some(PermissionHighlight::WorldWritable));
+        assert_eq!(classify_special_permissions(0o4755), Some(PermissionHighlight::Suid));
+        assert_eq!(classify_special_permissions(0o2755), Some(PermissionHighlight::Sgid));
+        // The sticky bit alone is not highlighted.
+        assert_eq!(classify_special_permissions(0o1755), None);
+        // SUID wins over SGID, and both over world-writable.
+        assert_eq!(classify_special_permissions(0o6777), Some(PermissionHighlight::Suid));
+        assert_eq!(classify_special_permissions(0o2777), Some(PermissionHighlight::Sgid));
+    }
+
+    #[test]
+    fn test_walk_limit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for dir in ["a", "b", "c"] {
+            std::fs::create_dir(temp_dir.path().join(dir)).unwrap();
+            for file in ["1.txt", "2.txt"] {
+                File::create(temp_dir.path().join(dir).join(file)).unwrap();
+            }
+        }
+        let count = |limit: &WalkLimit| {
+            let mut builder = WalkBuilder::new(temp_dir.path());
+            let filter = limit.clone();
+            builder.filter_entry(move |entry| filter.admits(entry));
+            let entries: Vec<_> = builder.build().filter_map(Result::ok).collect();
+            let dirs =
+                entries.iter().filter(|e| e.file_type().is_some_and(|ft| ft.is_dir())).count();
+            (dirs - 1, entries.len() - dirs)
+        };
+
+        let limit = WalkLimit::new(Some(3), None);
+        assert_eq!(count(&limit).1, 3);
+        assert!(limit.is_truncated());
+
+        let limit = WalkLimit::new(None, Some(2));
+        assert_eq!(count(&limit).0, 2);
+        assert!(limit.is_truncated());
+
+        // Reaching a limit exactly is not a truncation.
+        let limit = WalkLimit::new(Some(6), Some(3));
+        assert_eq!(count(&limit), (3, 6));
+        assert!(!limit.is_truncated());
+    }
 }
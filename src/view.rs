@@ -1,77 +1,630 @@
 //! Implements the classic, non-interactive directory tree view.
 
-use crate::app::ViewArgs;
+use crate::app::{ColorMode, SortType, ViewArgs};
+use crate::archive;
 use crate::git;
 use crate::icons;
+use crate::output::{self, OutputFormat};
 use crate::sort;
+use crate::stats;
 use crate::utils;
 use colored::{control, Colorize};
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use ignore::{self, WalkBuilder};
 use lscolors::LsColors;
+use notify::event::{EventKind, MetadataKind, ModifyKind};
+use notify::{RecursiveMode, Watcher};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
 use url::Url;
 
 // Platform-specific import for unix permissions
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 
+/// The connector drawn before each entry.
+const CONNECTOR: &str = "└──";
+/// The connector drawn before an only child with `--no-branch-for-single-child`.
+const SINGLE_CHILD_CONNECTOR: &str = "──";
+
+/// Writes the classic view, copying it without colors to the `--output` file.
+///
+/// The copy stops at `finish_tree`, so the summary only goes to the terminal.
+/// With `--format markdown`, the tree is also wrapped in a code fence, unless it
+/// is drawn as a nested list.
+struct TreeWriter<'a> {
+    out: &'a mut dyn Write,
+    copy: Option<(io::BufWriter<fs::File>, utils::AnsiStripper)>,
+    /// Whether a code fence was opened and still has to be closed.
+    fenced: bool,
+}
+
+impl<'a> TreeWriter<'a> {
+    /// Creates the `--output` file, if any, failing if it cannot be opened.
+    fn new(out: &'a mut dyn Write, output: Option<&Path>, fenced: bool) -> anyhow::Result<Self> {
+        let copy = match output {
+            Some(path) => {
+                let file = fs::File::create(path)
+                    .map_err(|e| anyhow::anyhow!("Failed to create '{}': {e}", path.display()))?;
+                Some((io::BufWriter::new(file), utils::AnsiStripper::default()))
+            }
+            None => None,
+        };
+        let mut writer = Self { out, copy, fenced };
+        if fenced {
+            writeln!(writer, "{}", output::markdown::FENCE)?;
+        }
+        Ok(writer)
+    }
+
+    /// Closes the code fence and the copy; later writes only go to `out`.
+    ///
+    /// Calling this again has no effect.
+    fn finish_tree(&mut self) -> anyhow::Result<()> {
+        if std::mem::take(&mut self.fenced) {
+            writeln!(self, "{}", output::markdown::FENCE)?;
+        }
+        if let Some((mut file, _)) = self.copy.take() {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Write for TreeWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some((file, stripper)) = &mut self.copy {
+            file.write_all(&stripper.strip(buf))?;
+        }
+        self.out.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some((file, _)) = &mut self.copy {
+            file.flush()?;
+        }
+        self.out.flush()
+    }
+}
+
 /// Executes the classic directory tree view
 pub fn run(args: &ViewArgs, ls_colors: &LsColors) -> anyhow::Result<()> {
-    if !args.path.is_dir() {
-        anyhow::bail!("'{}' is not a directory.", args.path.display());
+    crate::app::check_commit_sort([args.sort, args.sort2], args.count_commits)?;
+    if args.watch {
+        return watch(args, ls_colors);
     }
+    if args.watch_persistent || args.watch_diff_only {
+        return watch_persistent(args, ls_colors);
+    }
+    render(args, ls_colors, &mut io::stdout())
+}
 
-    let canonical_root = fs::canonicalize(&args.path)?;
-
+/// Scans the tree once and writes the classic view to `out`.
+fn render(args: &ViewArgs, ls_colors: &LsColors, out: &mut dyn Write) -> anyhow::Result<()> {
     match args.color {
         crate::app::ColorChoice::Always => control::set_override(true),
         crate::app::ColorChoice::Never => control::set_override(false),
         crate::app::ColorChoice::Auto => {}
     }
+    let markdown = args.format == OutputFormat::Markdown;
+    if markdown {
+        // Markdown is meant to be pasted, so it never contains escape codes.
+        control::set_override(false);
+    }
+    // An HTML page goes either to the terminal or, with `--output`, only to the file.
+    let sink = &mut io::sink();
+    let out: &mut dyn Write =
+        if args.format == OutputFormat::Html && args.output.is_some() { sink } else { out };
+    let fenced = markdown && !args.prints_markdown_list();
+    let out = &mut TreeWriter::new(out, args.output.as_deref(), fenced)?;
+    let result = render_tree(args, ls_colors, out);
+    out.finish_tree()?;
+    result
+}
+
+/// Walks the tree and writes it to `out`, followed by the summary.
+fn render_tree(args: &ViewArgs, ls_colors: &LsColors, out: &mut TreeWriter) -> anyhow::Result<()> {
+    if let Some(input) = &args.input {
+        let paths = read_path_list(input, args.input_null)?;
+        return print_path_list(out, args, &paths);
+    }
 
-    if writeln!(io::stdout(), "{}", args.path.display().to_string().blue().bold()).is_err() {
+    if !args.path.is_dir() {
+        anyhow::bail!("'{}' is not a directory.", args.path.display());
+    }
+
+    let canonical_root = fs::canonicalize(&args.path)?;
+    // The output file is created before the walk, so it must not list itself.
+    let output_path = args.output.as_ref().and_then(|path| fs::canonicalize(path).ok());
+
+    let color_mode = args.color_mode.resolve();
+
+    let markdown = args.format == OutputFormat::Markdown;
+    let root_label = args.display_root.clone().unwrap_or_else(|| args.path.display().to_string());
+    let root_branch = if args.git_status {
+        git::get_branch_name(&args.path).map(|branch| format!(" ({branch})")).unwrap_or_default()
+    } else {
+        String::new()
+    };
+    let root_ignored = if args.git_show_ignored_count {
+        ignored_annotation(git::count_ignored_children(&args.path, args.all))
+    } else {
+        String::new()
+    };
+    let root_totals = if args.top_level_only || args.top_level_summary_only {
+        let (dirs, files) = scan_full_for_stats(&args.path, args)?;
+        format!(" ({dirs} dirs, {files} files total)")
+    } else {
+        String::new()
+    };
+    // Structured formats print the whole tree at once after the walk.
+    if args.format.is_tree()
+        && !args.prints_flat_paths()
+        && !args.count_only
+        && (writeln!(out, "{}{root_branch}{root_totals}{root_ignored}", root_label.blue().bold())
+            .is_err()
+            || args.top_level_summary_only)
+    {
         return Ok(());
     }
 
-    let git_repo_status = if args.git_status { git::load_status(&canonical_root)? } else { None };
-    let status_cache = git_repo_status.as_ref().map(|s| &s.cache);
-    let repo_root = git_repo_status.as_ref().map(|s| &s.root);
+    let mut git_repo_status =
+        if args.git_status { git::load_status(&canonical_root)? } else { None };
 
     let mut builder = WalkBuilder::new(&args.path);
-    builder.hidden(!args.all).git_ignore(args.gitignore);
-    if let Some(level) = args.level {
+    // Testing an ignore pattern needs the full tree, as if nothing were ignored yet.
+    let dry_run = args.dry_run_gitignore.is_some();
+    builder
+        .hidden(!(args.all || dry_run))
+        .git_ignore(args.gitignore && !dry_run)
+        .follow_links(args.follow_links);
+    if let Some(level) = args.effective_level() {
         builder.max_depth(Some(level));
     }
+    let summarizer = DirSummarizer::new(&args.summarize_dir, args.summarize_depth)?;
+    let excludes = utils::build_exclude_matcher(&args.path, &args.exclude_from)?;
+    let glob_filter = utils::GlobFilter::new(&args.path, &args.include, &args.exclude)?;
+    let name_regex = utils::compile_filter_regex(args.filter_regex.as_deref())?;
+    // Network and bind mounts below the root are skipped with `--one-filesystem`.
+    let root_filesystem = if args.one_filesystem {
+        let id = utils::filesystem_id(&args.path);
+        if id.is_none() {
+            eprintln!("fstree: WARNING: --one-filesystem is not supported on this platform.");
+        }
+        id
+    } else {
+        None
+    };
+    let walk_limit = utils::WalkLimit::new(args.max_files, args.max_dirs);
+    if summarizer.is_active()
+        || excludes.is_some()
+        || glob_filter.is_active()
+        || args.follow_links
+        || root_filesystem.is_some()
+        || walk_limit.is_active()
+    {
+        // Summarized directories are listed, but their contents are not walked.
+        let summarizer = summarizer.clone();
+        let glob_filter = glob_filter.clone();
+        let walk_limit = walk_limit.clone();
+        let follow_links = args.follow_links;
+        builder.filter_entry(move |entry| {
+            !summarizer.summarizes_parent_of(entry)
+                && !excludes.as_ref().is_some_and(|matcher| utils::is_excluded(matcher, entry))
+                && !glob_filter.prunes(entry)
+                && (!follow_links || !skips_symlink_cycle(entry))
+                && root_filesystem.as_ref().is_none_or(|root| {
+                    utils::filesystem_id(entry.path()).is_none_or(|id| id == *root)
+                })
+                // Counted last, so only entries the other filters keep use up the limit.
+                && walk_limit.admits(entry)
+        });
+    }
 
     let mut dir_count = 0;
     let mut file_count = 0;
+    let mut suppressed_permission_errors = 0;
+    let error_policy = args.to_error_policy();
 
     // Collect all entries first, then sort them
-    let mut entries: Vec<_> = builder
-        .build()
+    // The spinner only makes sense next to a tree printed on the terminal.
+    let progress = (args.progress && !args.no_progress && io::stdout().is_terminal())
+        .then(utils::scan_progress);
+    let results = utils::walk(&builder, args.parallel, progress.as_ref());
+    if let Some(bar) = progress {
+        bar.finish_and_clear();
+    }
+    let mut entries: Vec<_> = results
+        .into_iter()
         .filter_map(|result| match result {
             Ok(entry) => {
-                if entry.depth() == 0 {
-                    None // Skip the root directory
+                let is_output = output_path.as_ref().is_some_and(|output| {
+                    entry
+                        .path()
+                        .strip_prefix(&args.path)
+                        .is_ok_and(|relative| canonical_root.join(relative) == *output)
+                });
+                // Skip the root directory and the `--output` file
+                if entry.depth() == 0 || is_output {
+                    None
                 } else {
                     Some(entry)
                 }
             }
             Err(err) => {
-                eprintln!("fstree: ERROR: {err}");
+                if let Some(link) = utils::symlink_loop_path(&err) {
+                    warn_symlink_loop(link);
+                } else if error_policy.is_suppressed(&err) {
+                    suppressed_permission_errors += 1;
+                } else {
+                    eprintln!("fstree: ERROR: {err}");
+                }
                 None
             }
         })
         .collect();
 
+    let extension_filter = args.extension_filter();
+    let metadata_filtered = args.min_size.is_some()
+        || args.max_size.is_some()
+        || args.newer_than.is_some()
+        || args.older_than.is_some();
+    if extension_filter.is_active()
+        || glob_filter.is_active()
+        || name_regex.is_some()
+        || metadata_filtered
+    {
+        entries.retain(|entry| {
+            let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+            extension_filter.keeps(entry.path(), is_dir)
+                && glob_filter.keeps(entry)
+                && name_regex.as_ref().is_none_or(|regex| utils::matches_name_regex(regex, entry))
+                && utils::size_in_range(entry, args.min_size, args.max_size)
+                && utils::modified_in_range(entry, args.newer_than, args.older_than)
+        });
+    }
+
+    if args.only_git_changed || args.only_untracked {
+        entries.retain(|entry| {
+            let status = git_repo_status.as_ref().and_then(|status| status.status_of(entry.path()));
+            entry.file_type().is_some_and(|ft| ft.is_dir())
+                || if args.only_untracked {
+                    status == Some(git::FileStatus::Untracked)
+                } else {
+                    status.is_some()
+                }
+        });
+    }
+
+    // Count commits per file before sorting, since `--sort commits` depends on them
+    let mut sort_options = args.to_sort_options();
+    if args.count_commits {
+        if let Some(status) = git_repo_status.as_mut() {
+            let relative_paths: HashMap<PathBuf, PathBuf> = entries
+                .iter()
+                .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()))
+                .filter_map(|e| {
+                    let relative = git::relative_to_root(e.path(), &status.root)?;
+                    Some((e.path().to_path_buf(), relative))
+                })
+                .collect();
+            status.load_commit_counts(&relative_paths.values().cloned().collect::<Vec<_>>());
+            sort_options.commit_counts = relative_paths
+                .into_iter()
+                .filter_map(|(path, rel)| Some((path, *status.commit_counts.get(&rel)?)))
+                .collect();
+        }
+    }
+
+    let mut file_tags: HashMap<PathBuf, String> = HashMap::new();
+    if args.git_tag {
+        if let Some(status) = git_repo_status.as_mut() {
+            // Describing every file is expensive, so only changed files are tagged by default.
+            let relative_paths: HashMap<PathBuf, PathBuf> = entries
+                .iter()
+                .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()))
+                .filter_map(|e| {
+                    let relative = git::relative_to_root(e.path(), &status.root)?;
+                    Some((e.path().to_path_buf(), relative))
+                })
+                .filter(|(_, relative)| args.git_tag_all || status.cache.contains_key(relative))
+                .collect();
+            status.load_file_tags(
+                &relative_paths.values().cloned().collect::<Vec<_>>(),
+                args.git_tag_format,
+            );
+            file_tags = relative_paths
+                .into_iter()
+                .filter_map(|(path, rel)| Some((path, status.file_tags.get(&rel)?.clone())))
+                .collect();
+        }
+    }
+
+    let mut diff_stats: HashMap<PathBuf, git::DiffStat> = HashMap::new();
+    if args.git_diff_stat {
+        if let Some(status) = git_repo_status.as_mut() {
+            status.load_diff_stats()?;
+            diff_stats = entries
+                .iter()
+                .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()))
+                .filter_map(|e| {
+                    let relative = git::relative_to_root(e.path(), &status.root)?;
+                    Some((e.path().to_path_buf(), *status.diff_stats.get(&relative)?))
+                })
+                .collect();
+        }
+    }
+    // Bars are scaled against the largest change in the tree.
+    let diff_stat_scale = args
+        .git_diff_stat_bar
+        .then(|| diff_stats.values().map(git::DiffStat::total).max().unwrap_or(0));
+
+    // The display stays top-down; the bottom-up pass only aggregates sizes.
+    // Totals cover the entries left after filtering, so they match the tree.
+    let dir_sizes = if args.du || (args.size && args.walk_strategy == utils::WalkStrategy::Bottomup)
+    {
+        utils::accumulate_dir_sizes(&entries)
+    } else {
+        HashMap::new()
+    };
+    if args.sort == SortType::Size {
+        sort_options.dir_sizes = dir_sizes.clone();
+    }
+
+    if sort_options.uses(sort::SortType::Children) {
+        sort_options.child_counts = utils::read_child_counts(
+            entries
+                .iter()
+                .filter(|e| e.file_type().is_some_and(|ft| ft.is_dir()))
+                .map(|e| e.path()),
+        );
+    }
+
     // Apply sorting
-    let sort_options = args.to_sort_options();
     sort::sort_entries(&mut entries, &sort_options);
 
+    if args.prints_flat_paths() {
+        print_flat_paths(out, args, &entries);
+        return Ok(());
+    }
+
+    if !args.format.is_tree() {
+        let tree = collect_tree_entries(args, &entries, git_repo_status.as_ref());
+        return match args.format {
+            OutputFormat::Json => output::json::write(
+                out,
+                &args.path.display().to_string(),
+                args.display_root.as_deref(),
+                &tree,
+            ),
+            OutputFormat::Csv => output::csv::write(out, &tree),
+            OutputFormat::Html => {
+                let html_entries = collect_html_entries(args, ls_colors, &entries);
+                let html = output::html::render(
+                    &root_label,
+                    &html_entries,
+                    output::Summary::of(&tree),
+                    args.indent,
+                );
+                out.write_all(html.as_bytes())?;
+                Ok(())
+            }
+            OutputFormat::Text | OutputFormat::Markdown => unreachable!("rendered below"),
+        };
+    }
+
+    let dir_summaries: HashMap<PathBuf, (usize, u64)> = entries
+        .par_iter()
+        .filter(|entry| summarizer.should_summarize(entry))
+        .map(|entry| (entry.path().to_path_buf(), summarize_directory(args, entry.path())))
+        .collect();
+
+    if args.inode_map {
+        return print_inode_map(out, args, &entries);
+    }
+    if args.by_owner || args.by_owner_gid {
+        return print_owner_groups(out, args, entries);
+    }
+    if args.scan_symlinks_only {
+        print_symlink_chains(out, args, &entries);
+        return Ok(());
+    }
+
+    let extension_stats = (args.extension_stats || args.extension_stats_only).then(|| {
+        stats::compute_extension_stats(
+            entries
+                .iter()
+                .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()))
+                .map(|e| (e.path(), e.metadata().map_or(0, |m| m.len()))),
+        )
+    });
+    if args.extension_stats_only {
+        print_extension_chart(out, args, extension_stats.as_ref());
+        return Ok(());
+    }
+
+    let permission_fixes = permission_fix_commands(args, &entries);
+    if let Some(script) = &args.permission_fix_script {
+        write_permission_fix_script(script, &permission_fixes)?;
+    }
+    if args.permission_fix_only {
+        print_lines(out, &permission_fixes);
+        return Ok(());
+    }
+
+    let last_modified_n = args.last_modified_n.or(args.last_modified.then_some(1));
+    let most_recent = last_modified_n.map(|n| utils::find_most_recent(&entries, n));
+
+    let would_ignore: HashSet<PathBuf> = match &args.dry_run_gitignore {
+        Some(pattern) => git::test_gitignore_pattern(&entries, pattern, &args.path)?
+            .into_iter()
+            .zip(&entries)
+            .filter(|(ignored, _)| *ignored)
+            .map(|(_, entry)| entry.path().to_path_buf())
+            .collect(),
+        None => HashSet::new(),
+    };
+
+    let conflicting_files = find_conflicting_files(args, &entries);
+    // With `--report-duplicate-extensions-only`, directories are kept only to give
+    // the conflicting files some structure.
+    let conflict_dirs: HashSet<PathBuf> = conflicting_files
+        .iter()
+        .flat_map(|path| path.ancestors().skip(1).map(|p| p.to_path_buf()))
+        .collect();
+
+    let sparse_files = find_sparse_files(args, &entries);
+    let sparse_dirs: HashSet<PathBuf> = sparse_files
+        .keys()
+        .flat_map(|path| path.ancestors().skip(1).map(|p| p.to_path_buf()))
+        .collect();
+
+    let files: Vec<PathBuf> = if args.access_check || args.write_check {
+        entries
+            .iter()
+            .filter(|e| !e.file_type().is_some_and(|ft| ft.is_dir()))
+            .map(|e| e.path().to_path_buf())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let (unreadable, unwritable) = check_access(args, &files)?;
+
+    let conflict_marker_files: HashSet<PathBuf> = if args.git_conflict_markers {
+        entries
+            .par_iter()
+            .filter(|e| !e.file_type().is_some_and(|ft| ft.is_dir()))
+            .filter(|e| utils::has_conflict_markers(e.path()))
+            .map(|e| e.path().to_path_buf())
+            .collect()
+    } else {
+        HashSet::new()
+    };
+
+    // Hashing reads every file in full, so it runs in parallel before printing.
+    let checksums: HashMap<PathBuf, String> = if let Some(algo) = args.checksum {
+        entries
+            .par_iter()
+            .filter(|e| !e.file_type().is_some_and(|ft| ft.is_dir()) && is_listed(args, e))
+            .filter_map(|e| {
+                let too_large = e.metadata().is_ok_and(|md| md.len() > args.checksum_max_size);
+                let checksum = if too_large {
+                    "too large".to_string()
+                } else {
+                    algo.hash_file(e.path()).ok()?
+                };
+                Some((e.path().to_path_buf(), checksum))
+            })
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    // `None` marks a binary file; unreadable files get no entry.
+    let line_counts: HashMap<PathBuf, Option<usize>> = if args.line_count {
+        entries
+            .par_iter()
+            .filter(|e| !e.file_type().is_some_and(|ft| ft.is_dir()) && is_listed(args, e))
+            .filter_map(|e| Some((e.path().to_path_buf(), utils::count_lines(e.path()).ok()?)))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    let mimes: HashMap<PathBuf, String> = if args.mime {
+        entries
+            .par_iter()
+            .filter(|e| !e.file_type().is_some_and(|ft| ft.is_dir()) && is_listed(args, e))
+            .filter_map(|e| Some((e.path().to_path_buf(), utils::detect_mime(e.path())?)))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    let ignored_counts: HashMap<PathBuf, usize> = if args.git_show_ignored_count {
+        entries
+            .par_iter()
+            .filter(|e| e.file_type().is_some_and(|ft| ft.is_dir()))
+            .map(|e| (e.path().to_path_buf(), git::count_ignored_children(e.path(), args.all)))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    let status_cache = git_repo_status.as_ref().map(|s| &s.cache);
+    let repo_root = git_repo_status.as_ref().map(|s| &s.root);
+    let mut previous_parent: Option<PathBuf> = None;
+    let child_counts: HashMap<PathBuf, usize> = if args.no_branch_for_single_child {
+        count_children(entries.iter().map(|e| e.path()))
+    } else {
+        HashMap::new()
+    };
+
+    let mut symlink_count = 0;
+    let now = SystemTime::now();
+    let owners = if args.owner { owner_labels(&entries) } else { HashMap::new() };
+    let owner_width = owners.values().map(|label| label.chars().count()).max().unwrap_or(0);
+    let inodes: HashMap<PathBuf, u64> = if args.inode {
+        entries
+            .iter()
+            .filter_map(|e| Some((e.path().to_path_buf(), utils::inode(&e.metadata().ok()?))))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    let inode_width = inodes.values().max().map_or(0, |max| max.to_string().len());
+    let link_counts: HashMap<PathBuf, u64> = if args.nlinks {
+        entries
+            .iter()
+            .filter_map(|e| Some((e.path().to_path_buf(), utils::link_count(&e.metadata().ok()?))))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    let nlinks_width = link_counts.values().max().map_or(0, |max| max.to_string().len());
     for entry in entries {
         let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
-        if args.dirs_only && !is_dir {
+        if args.hides_kind(is_dir) {
+            continue;
+        }
+        // A display filter only: the walk still descends into shallower directories.
+        if args.min_depth.is_some_and(|min| entry.depth() < min) {
+            continue;
+        }
+        if args.file_type.is_some_and(|filter| !filter.keeps(entry.file_type())) {
+            continue;
+        }
+        // Directories kept only for structure when filtering by another type.
+        let is_structural = is_dir
+            && args.file_type.is_some_and(|filter| filter != utils::FileTypeFilter::Directory);
+        let is_conflicting = conflicting_files.contains(entry.path());
+        if args.report_duplicate_extensions_only
+            && !is_conflicting
+            && !(is_dir && conflict_dirs.contains(entry.path()))
+        {
+            continue;
+        }
+        let sparse_sizes = sparse_files.get(entry.path());
+        if args.report_sparse_only
+            && sparse_sizes.is_none()
+            && !(is_dir && sparse_dirs.contains(entry.path()))
+        {
+            continue;
+        }
+        // `--count` only needs the totals, so nothing else is computed.
+        if args.count_only {
+            if is_dir {
+                dir_count += 1;
+            } else {
+                file_count += 1;
+            }
+            symlink_count += usize::from(entry.path_is_symlink());
             continue;
         }
 
@@ -92,6 +645,7 @@ pub fn run(args: &ViewArgs, ls_colors: &LsColors) -> anyhow::Result<()> {
                                 git::FileStatus::Deleted => colored::Color::Red,
                                 git::FileStatus::Conflicted => colored::Color::BrightRed,
                                 git::FileStatus::Untracked => colored::Color::Magenta,
+                                git::FileStatus::Submodule => colored::Color::Cyan,
                             };
                             format!("{status_char} ").color(color).to_string()
                         })
@@ -106,6 +660,33 @@ pub fn run(args: &ViewArgs, ls_colors: &LsColors) -> anyhow::Result<()> {
             String::new()
         };
 
+        let commit_count_str = if args.count_commits && git_repo_status.is_some() {
+            match sort_options.commit_counts.get(entry.path()) {
+                Some(&count) => format!("{:>5} ", git::format_commit_count(count)),
+                None => " ".repeat(6),
+            }
+        } else {
+            String::new()
+        };
+
+        // The diff stat is colored, so its display width is tracked separately.
+        let (diff_stat_str, diff_stat_width) = if args.git_diff_stat && git_repo_status.is_some() {
+            match diff_stats.get(entry.path()) {
+                Some(stat) => {
+                    let (insertions, deletions) = stat.parts(diff_stat_scale);
+                    let width = insertions.len() + deletions.len();
+                    let padding = " ".repeat(git::DIFF_STAT_WIDTH.saturating_sub(width));
+                    (
+                        format!("{padding}{}{} ", insertions.green(), deletions.red()),
+                        padding.len() + width + 1,
+                    )
+                }
+                None => (" ".repeat(git::DIFF_STAT_WIDTH + 1), git::DIFF_STAT_WIDTH + 1),
+            }
+        } else {
+            (String::new(), 0)
+        };
+
         let metadata = if args.size || args.permissions { entry.metadata().ok() } else { None };
         let permissions_str = if args.permissions {
             let perms = if let Some(md) = &metadata {
@@ -115,14 +696,25 @@ pub fn run(args: &ViewArgs, ls_colors: &LsColors) -> anyhow::Result<()> {
                     // Use 'md' for Unix-specific logic
                     let mode = md.permissions().mode();
                     let file_type_char = if md.is_dir() { 'd' } else { '-' };
-                    format!("{}{}", file_type_char, utils::format_permissions(mode))
+                    let octal = if args.octal {
+                        format!(" ({})", utils::format_octal_permissions(mode))
+                    } else {
+                        String::new()
+                    };
+                    format!("{}{}{octal}", file_type_char, utils::format_permissions(mode))
                 }
                 #[cfg(not(unix))]
                 {
                     // This line tells the compiler we've intentionally not used 'md' on non-Unix systems
                     let _ = md;
-                    "----------".to_string()
+                    if args.octal {
+                        "---------- (----)".to_string()
+                    } else {
+                        "----------".to_string()
+                    }
                 }
+            } else if args.octal {
+                "---------- (----)".to_string()
             } else {
                 "----------".to_string()
             };
@@ -131,26 +723,103 @@ pub fn run(args: &ViewArgs, ls_colors: &LsColors) -> anyhow::Result<()> {
             String::new()
         };
 
-        let indent = "    ".repeat(entry.depth().saturating_sub(1));
+        let inode_str = match inodes.get(entry.path()) {
+            Some(inode) => format!("{inode:>inode_width$} "),
+            None if args.inode => " ".repeat(inode_width + 1),
+            None => String::new(),
+        };
+        let (nlinks_str, nlinks_len) = match link_counts.get(entry.path()) {
+            // Files with several names are easy to miss, so they stand out.
+            Some(&count) if count > 1 && !is_dir => {
+                (format!("{count:>nlinks_width$} ").yellow().to_string(), nlinks_width + 1)
+            }
+            Some(count) => {
+                (format!("{count:>nlinks_width$} ").dimmed().to_string(), nlinks_width + 1)
+            }
+            None if args.nlinks => (" ".repeat(nlinks_width + 1), nlinks_width + 1),
+            None => (String::new(), 0),
+        };
+        let owner_str = match owners.get(entry.path()) {
+            Some(label) => format!("{label:<owner_width$} "),
+            None if !owners.is_empty() => " ".repeat(owner_width + 1),
+            None => String::new(),
+        };
+
         let name = entry.file_name().to_string_lossy();
-        let icon_str = if args.icons {
+        let mut icon_str = if args.shows_icon(entry.path(), is_dir) {
             let (icon, color) = icons::get_icon_for_path(entry.path(), is_dir);
             format!("{} ", icon.color(color))
         } else {
             String::new()
         };
-        let size_str = if args.size && !is_dir {
+        let has_conflict_markers = conflict_marker_files.contains(entry.path());
+        if has_conflict_markers {
+            icon_str.push_str("⚡ ");
+        }
+        let dir_summary = dir_summaries.get(entry.path());
+        let size_str = if let Some((files, bytes)) = dir_summary {
+            format!(" ({files} files, {})", utils::format_size(*bytes))
+        } else if args.size && !is_dir {
             metadata
                 .as_ref()
                 .map(|m| format!(" ({})", utils::format_size(m.len())))
                 .unwrap_or_default()
+        } else if let Some(size) = dir_sizes.get(entry.path()).filter(|_| is_dir) {
+            format!(" ({})", utils::format_size(*size))
         } else {
             String::new()
         };
+        let mut annotations = match file_tags.get(entry.path()) {
+            Some(tag) => format!(" {}", tag.bright_black()),
+            None => String::new(),
+        };
+        if is_conflicting {
+            annotations.push_str(&format!(" {}", "[dup-ext]".bright_yellow()));
+        }
+        annotations.push_str(&access_annotations(entry.path(), &unreadable, &unwritable));
+        if let Some(&count) = ignored_counts.get(entry.path()) {
+            annotations.push_str(&ignored_annotation(count));
+        }
+        if has_conflict_markers {
+            annotations.push_str(&format!(" {}", "[CONFLICT]".bright_red()));
+        }
+        if let Some((allocated, apparent)) = sparse_sizes {
+            let label = format!(
+                "[sparse {}/{}]",
+                utils::format_size(*allocated),
+                utils::format_size(*apparent)
+            );
+            annotations.push_str(&format!(" {}", label.cyan()));
+        }
+        if let Some(checksum) = checksums.get(entry.path()) {
+            annotations.push_str(&format!(" {}", format!("({checksum})").dimmed()));
+        }
+        if let Some(lines) = line_counts.get(entry.path()) {
+            let label = match lines {
+                Some(1) => "1 line".to_string(),
+                Some(lines) => format!("{lines} lines"),
+                None => "binary".to_string(),
+            };
+            annotations.push_str(&format!(" {}", format!("({label})").dimmed()));
+        }
+        if let Some(mime) = mimes.get(entry.path()) {
+            annotations.push_str(&format!(" {}", format!("({mime})").dimmed()));
+        }
+        if args.atime {
+            // Some filesystems do not record access times at all.
+            let accessed = entry
+                .metadata()
+                .ok()
+                .and_then(|m| m.accessed().ok())
+                .map_or_else(|| "—".to_string(), |time| utils::format_relative_time(time, now));
+            annotations.push_str(&format!(" {}", format!("(accessed {accessed})").bright_black()));
+        }
 
         // --- Corrected Logic Block ---
         let ls_style = ls_colors.style_for_path(entry.path()).cloned().unwrap_or_default();
         let mut styled_name = name.to_string().normal();
+        let mut indexed_color = None;
+        let mut rgb_color = None;
 
         if let Some(fg) = ls_style.foreground {
             use lscolors::Color as LsColor;
@@ -172,9 +841,21 @@ pub fn run(args: &ViewArgs, ls_colors: &LsColors) -> anyhow::Result<()> {
                 LsColor::BrightCyan => colored::Color::BrightCyan,
                 LsColor::BrightWhite => colored::Color::BrightWhite,
                 LsColor::Fixed(_) => colored::Color::White,
-                LsColor::RGB(r, g, b) => colored::Color::TrueColor { r, g, b },
+                LsColor::RGB(r, g, b) => match color_mode {
+                    ColorMode::Ansi8 => utils::nearest_basic_color(r, g, b),
+                    ColorMode::Ansi256 => {
+                        indexed_color = Some(utils::nearest_ansi256_index(r, g, b));
+                        colored::Color::White
+                    }
+                    _ => {
+                        rgb_color = Some((r, g, b));
+                        colored::Color::White
+                    }
+                },
             };
-            styled_name = styled_name.color(color);
+            if indexed_color.is_none() && rgb_color.is_none() {
+                styled_name = styled_name.color(color);
+            }
         }
 
         if ls_style.font_style.bold {
@@ -186,21 +867,59 @@ pub fn run(args: &ViewArgs, ls_colors: &LsColors) -> anyhow::Result<()> {
         if ls_style.font_style.underline {
             styled_name = styled_name.underline();
         }
+        if is_structural {
+            styled_name = styled_name.dimmed();
+        }
+        if would_ignore.contains(entry.path()) {
+            styled_name = styled_name.strikethrough().bright_black();
+        }
+        #[cfg(unix)]
+        if args.highlight_special && !is_dir {
+            let mode = entry.metadata().ok().map(|md| md.permissions().mode());
+            if let Some(highlight) = mode.and_then(utils::classify_special_permissions) {
+                styled_name = styled_name.on_color(highlight.color());
+            }
+        }
 
+        let mut styled_name =
+            paint_rgb(paint_indexed(styled_name.to_string(), indexed_color), rgb_color);
+        if dir_summary.is_some() {
+            styled_name.push('/');
+        }
         let final_name = if args.hyperlinks && !is_dir {
             // Canonicalize the path to get an absolute path for the URL
             if let Ok(abs_path) = fs::canonicalize(entry.path()) {
                 if let Ok(url) = Url::from_file_path(abs_path) {
-                    format!("\x1B]8;;{url}\x07{styled_name}\x1B]8;;\x07")
+                    if markdown {
+                        output::markdown::link(&styled_name, &url)
+                    } else {
+                        format!("\x1B]8;;{url}\x07{styled_name}\x1B]8;;\x07")
+                    }
                 } else {
-                    styled_name.to_string()
+                    styled_name
                 }
             } else {
-                styled_name.to_string()
+                styled_name
             }
         } else {
-            styled_name.to_string()
+            styled_name
         };
+        let mut final_name = final_name;
+        if entry.path_is_symlink() {
+            symlink_count += 1;
+            if args.dereference || (args.follow_links && is_dir) {
+                final_name.push_str(&symlink_target_label(entry.path()));
+            }
+        }
+
+        // Width of the columns printed before the tree, used to align virtual entries.
+        let columns_width = inode_str.len()
+            + if git_status_str.is_empty() { 0 } else { 2 }
+            + commit_count_str.len()
+            + diff_stat_width
+            + permissions_str.len()
+            + nlinks_len
+            + owner_str.chars().count();
 
         if is_dir {
             dir_count += 1;
@@ -208,25 +927,1328 @@ pub fn run(args: &ViewArgs, ls_colors: &LsColors) -> anyhow::Result<()> {
             file_count += 1;
         }
 
-        if writeln!(
-            io::stdout(),
-            "{}{}{}└── {}{}{}",
+        let parent = entry.path().parent().map(Path::to_path_buf);
+        let is_only_child = parent.as_ref().and_then(|p| child_counts.get(p)) == Some(&1);
+        let connector = if is_only_child { SINGLE_CHILD_CONNECTOR } else { CONNECTOR };
+        if args.breadcrumb && entry.depth() > 2 && parent != previous_parent {
+            if let Some(parent) = &parent {
+                if writeln!(out, "{}", build_breadcrumb(parent, &args.path).dimmed()).is_err() {
+                    break;
+                }
+            }
+        }
+        previous_parent = parent;
+
+        let columns = format!(
+            "{}{}{}{}{}{}{}",
+            inode_str.dimmed(),
             git_status_str,
+            commit_count_str.cyan(),
+            diff_stat_str,
             permissions_str.dimmed(),
-            indent,
-            icon_str,
-            //styled_name,
-            final_name,
-            size_str.dimmed()
-        )
-        .is_err()
+            nlinks_str,
+            owner_str.dimmed(),
+        );
+        let branch = branch(args, entry.depth(), connector);
+        // A list item must start with its marker, so the columns follow it.
+        let prefix = if args.prints_markdown_list() {
+            format!("{branch} {columns}")
+        } else {
+            format!("{columns}{branch} ")
+        };
+        if writeln!(out, "{prefix}{icon_str}{final_name}{}{annotations}", size_str.dimmed())
+            .is_err()
+        {
+            break;
+        }
+
+        if args.include_archives
+            && !is_dir
+            && archive::archive_kind(entry.path()).is_some()
+            && print_archive_contents(out, args, &entry, columns_width).is_err()
         {
             break;
         }
     }
 
-    let summary = format!("\n{dir_count} directories, {file_count} files");
-    _ = writeln!(io::stdout(), "{summary}");
+    let blank_line = if args.count_only { "" } else { "\n" };
+    let mut summary = format!("{blank_line}{dir_count} directories, {file_count} files");
+    if args.dereference {
+        summary.push_str(&format!(", {symlink_count} symlinks"));
+    }
+    if args.verbose && suppressed_permission_errors > 0 {
+        summary.push_str(&format!(", {suppressed_permission_errors} unreadable entries skipped"));
+    }
+    if let Some(pattern) = &args.dry_run_gitignore {
+        summary
+            .push_str(&format!(", {} entries would be ignored by '{pattern}'", would_ignore.len()));
+    }
+    if !conflicting_files.is_empty() {
+        summary.push_str(&format!(
+            ", {} files with duplicate-extension conflicts",
+            conflicting_files.len()
+        ));
+    }
+    if args.access_check {
+        summary.push_str(&format!(", {} unreadable files", unreadable.len()));
+    }
+    if args.write_check {
+        summary.push_str(&format!(", {} unwritable files", unwritable.len()));
+    }
+    if args.report_sparse_files || args.report_sparse_only {
+        let saved: u64 =
+            sparse_files.values().map(|(allocated, apparent)| apparent - allocated).sum();
+        summary.push_str(&format!(
+            ", {} sparse files (saving {})",
+            sparse_files.len(),
+            utils::format_size(saved)
+        ));
+    }
+    if args.git_conflict_markers {
+        summary.push_str(&format!(", {} files with conflict markers", conflict_marker_files.len()));
+    }
+    out.finish_tree()?;
+    _ = writeln!(out, "{summary}");
+    if walk_limit.is_truncated() {
+        _ = writeln!(out, "... (truncated)");
+    }
+
+    if let Some(most_recent) = most_recent {
+        print_last_modified(out, args, &most_recent, git_repo_status.as_ref());
+    }
+
+    if extension_stats.is_some() {
+        _ = writeln!(out);
+        print_extension_chart(out, args, extension_stats.as_ref());
+    }
+
+    if !permission_fixes.is_empty() {
+        _ = writeln!(out);
+        print_lines(out, &permission_fixes);
+    }
+
+    // A non-zero exit lets pre-commit hooks reject unresolved merges.
+    if !conflict_marker_files.is_empty() {
+        anyhow::bail!("{} files still contain conflict markers", conflict_marker_files.len());
+    }
 
     Ok(())
 }
+
+/// Converts the walked entries into the format-independent model of `--format`.
+///
+/// Honors the same display filters as the text view: `--dirs-only`,
+/// `--min-depth` and `--type`.
+fn collect_tree_entries(
+    args: &ViewArgs,
+    entries: &[ignore::DirEntry],
+    git_repo_status: Option<&git::GitRepoStatus>,
+) -> Vec<output::TreeEntry> {
+    entries
+        .iter()
+        .filter(|entry| is_listed(args, entry))
+        .map(|entry| {
+            let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+            let metadata = entry.metadata().ok();
+            let git_status = git_repo_status.and_then(|status| {
+                let relative = entry.path().canonicalize().ok()?;
+                let relative = relative.strip_prefix(&status.root).ok()?;
+                status.cache.get(relative).map(|s| s.name().to_string())
+            });
+            output::TreeEntry {
+                path: entry
+                    .path()
+                    .strip_prefix(&args.path)
+                    .unwrap_or(entry.path())
+                    .display()
+                    .to_string(),
+                depth: entry.depth(),
+                is_dir,
+                size: metadata.as_ref().filter(|_| !is_dir).map(|m| m.len()),
+                permissions: metadata.as_ref().and_then(permissions_string),
+                git_status,
+            }
+        })
+        .collect()
+}
+
+/// Prints the absolute path of each listed entry for `--no-tree` and `--print0`.
+///
+/// Paths are written as raw bytes, so names that are not valid UTF-8 survive
+/// the round trip through `xargs`.
+fn print_flat_paths(out: &mut dyn Write, args: &ViewArgs, entries: &[ignore::DirEntry]) {
+    let separator = if args.print0 { b'\0' } else { b'\n' };
+    for entry in entries.iter().filter(|entry| is_listed(args, entry)) {
+        let path = fs::canonicalize(entry.path()).unwrap_or_else(|_| entry.path().to_path_buf());
+        if out.write_all(path.as_os_str().as_encoded_bytes()).is_err()
+            || out.write_all(&[separator]).is_err()
+        {
+            break;
+        }
+    }
+}
+
+/// Returns true if `--dirs-only`, `--min-depth` and `--type` keep the entry.
+fn is_listed(args: &ViewArgs, entry: &ignore::DirEntry) -> bool {
+    let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+    !args.hides_kind(is_dir)
+        && args.min_depth.is_none_or(|min| entry.depth() >= min)
+        && args.file_type.is_none_or(|filter| filter.keeps(entry.file_type()))
+}
+
+/// Converts the walked entries into the lines of `--format html`, with their
+/// `LS_COLORS` and icon colors resolved to CSS.
+fn collect_html_entries(
+    args: &ViewArgs,
+    ls_colors: &LsColors,
+    entries: &[ignore::DirEntry],
+) -> Vec<output::html::HtmlEntry> {
+    entries
+        .iter()
+        .filter(|entry| is_listed(args, entry))
+        .map(|entry| {
+            let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+            let style = ls_colors.style_for_path(entry.path());
+            let icon = args.shows_icon(entry.path(), is_dir).then(|| {
+                let (icon, color) = icons::get_icon_for_path(entry.path(), is_dir);
+                (icon, output::html::colored_css(color))
+            });
+            let href = (args.hyperlinks && !is_dir)
+                .then(|| fs::canonicalize(entry.path()).ok())
+                .flatten()
+                .and_then(|path| Url::from_file_path(path).ok())
+                .map(String::from);
+            output::html::HtmlEntry {
+                depth: entry.depth(),
+                name: entry.file_name().to_string_lossy().into_owned(),
+                color: style
+                    .and_then(|style| style.foreground.as_ref())
+                    .map(output::html::ls_color_css),
+                bold: style.is_some_and(|style| style.font_style.bold),
+                icon,
+                href,
+                size: (args.size && !is_dir)
+                    .then(|| entry.metadata().ok().map(|m| m.len()))
+                    .flatten(),
+            }
+        })
+        .collect()
+}
+
+/// Formats the permissions of an entry in `ls -l` notation, such as `drwxr-xr-x`.
+#[cfg(unix)]
+fn permissions_string(metadata: &fs::Metadata) -> Option<String> {
+    let file_type_char = if metadata.is_dir() { 'd' } else { '-' };
+    Some(format!("{file_type_char}{}", utils::format_permissions(metadata.permissions().mode())))
+}
+
+/// Permissions are only available on Unix.
+#[cfg(not(unix))]
+fn permissions_string(_metadata: &fs::Metadata) -> Option<String> {
+    None
+}
+
+/// Decides which directories `--summarize-dir` and `--summarize-depth` collapse
+/// into a one-line summary.
+#[derive(Clone)]
+pub struct DirSummarizer {
+    patterns: GlobSet,
+    min_depth: Option<usize>,
+}
+
+impl DirSummarizer {
+    pub fn new(globs: &[String], min_depth: Option<usize>) -> anyhow::Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for glob in globs {
+            builder.add(
+                Glob::new(glob)
+                    .map_err(|e| anyhow::anyhow!("Invalid --summarize-dir glob '{glob}': {e}"))?,
+            );
+        }
+        Ok(Self { patterns: builder.build()?, min_depth })
+    }
+
+    /// Returns true if any directory can be summarized.
+    pub fn is_active(&self) -> bool {
+        !self.patterns.is_empty() || self.min_depth.is_some()
+    }
+
+    /// Returns true if the entry is a directory whose contents should be summarized.
+    pub fn should_summarize(&self, entry: &ignore::DirEntry) -> bool {
+        entry.file_type().is_some_and(|ft| ft.is_dir()) && self.matches(entry.path(), entry.depth())
+    }
+
+    /// Returns true if the entry lives directly in a summarized directory.
+    fn summarizes_parent_of(&self, entry: &ignore::DirEntry) -> bool {
+        match entry.path().parent() {
+            Some(parent) if entry.depth() > 1 => self.matches(parent, entry.depth() - 1),
+            _ => false,
+        }
+    }
+
+    /// The root itself is never summarized.
+    fn matches(&self, path: &Path, depth: usize) -> bool {
+        depth > 0
+            && (self.min_depth.is_some_and(|min| depth >= min)
+                || path.file_name().is_some_and(|name| self.patterns.is_match(name)))
+    }
+}
+
+/// Counts the children of each directory among the given paths.
+pub fn count_children<'a>(paths: impl Iterator<Item = &'a Path>) -> HashMap<PathBuf, usize> {
+    let mut counts: HashMap<PathBuf, usize> = HashMap::new();
+    for path in paths {
+        if let Some(parent) = path.parent() {
+            *counts.entry(parent.to_path_buf()).or_default() += 1;
+        }
+    }
+    counts
+}
+
+/// Counts every directory and file below `path`, ignoring `--level`.
+///
+/// Honors the same hidden, gitignore, exclude, glob, symlink and filesystem
+/// settings as the main walk. Walk errors are returned unless
+/// `--ignore-permission-errors` suppresses them; symlink loops are skipped,
+/// since the main walk already warns about them.
+fn scan_full_for_stats(path: &Path, args: &ViewArgs) -> anyhow::Result<(usize, usize)> {
+    let excludes = utils::build_exclude_matcher(path, &args.exclude_from)?;
+    let glob_filter = utils::GlobFilter::new(path, &args.include, &args.exclude)?;
+    let root_filesystem = if args.one_filesystem { utils::filesystem_id(path) } else { None };
+    let error_policy = args.to_error_policy();
+
+    let mut builder = WalkBuilder::new(path);
+    builder.hidden(!args.all).git_ignore(args.gitignore).follow_links(args.follow_links);
+    let follow_links = args.follow_links;
+    let prune_filter = glob_filter.clone();
+    builder.filter_entry(move |entry| {
+        !excludes.as_ref().is_some_and(|matcher| utils::is_excluded(matcher, entry))
+            && !prune_filter.prunes(entry)
+            && (!follow_links || !skips_symlink_cycle(entry))
+            && root_filesystem
+                .as_ref()
+                .is_none_or(|root| utils::filesystem_id(entry.path()).is_none_or(|id| id == *root))
+    });
+
+    let (mut dirs, mut files) = (0, 0);
+    for result in builder.build() {
+        let entry = match result {
+            Ok(entry) => entry,
+            Err(err) if utils::symlink_loop_path(&err).is_some() => continue,
+            Err(err) if error_policy.is_suppressed(&err) => continue,
+            Err(err) => {
+                return Err(anyhow::anyhow!(
+                    "Failed to count entries in '{}': {err}",
+                    path.display()
+                ))
+            }
+        };
+        if entry.depth() == 0 || !glob_filter.keeps(&entry) {
+            continue;
+        }
+        if entry.file_type().is_some_and(|ft| ft.is_dir()) {
+            dirs += 1;
+        } else {
+            files += 1;
+        }
+    }
+    Ok((dirs, files))
+}
+
+/// Counts the files below a directory and their total size, honoring the
+/// same hidden and gitignore settings as the main walk.
+fn summarize_directory(args: &ViewArgs, path: &Path) -> (usize, u64) {
+    WalkBuilder::new(path)
+        .hidden(!args.all)
+        .git_ignore(args.gitignore)
+        .build()
+        .flatten()
+        .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()))
+        .fold((0, 0), |(files, bytes), e| (files + 1, bytes + e.metadata().map_or(0, |m| m.len())))
+}
+
+/// A node of the tree reconstructed from a list of paths by `--input`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    /// Path relative to the common ancestor of the list.
+    pub path: PathBuf,
+    /// Depth below the common ancestor, starting at 1.
+    pub depth: usize,
+    pub is_dir: bool,
+    /// Directories that are implied by a listed path but not listed themselves.
+    pub is_virtual: bool,
+}
+
+/// Reads the `--input` path list from a file, or from stdin when `input` is `-`.
+fn read_path_list(input: &Path, null_separated: bool) -> anyhow::Result<Vec<PathBuf>> {
+    let mut data = Vec::new();
+    if input == Path::new("-") {
+        io::stdin().read_to_end(&mut data)?;
+    } else {
+        data = fs::read(input)
+            .map_err(|e| anyhow::anyhow!("Failed to read '{}': {e}", input.display()))?;
+    }
+    let text = String::from_utf8_lossy(&data);
+    let separator = if null_separated { '\0' } else { '\n' };
+    Ok(text
+        .split(separator)
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty())
+        // Drop `.` components so `./src/main.rs` and `src/main.rs` are the same path.
+        .map(|line| Path::new(line).components().filter(|c| *c != Component::CurDir).collect())
+        .filter(|path: &PathBuf| !path.as_os_str().is_empty())
+        .collect())
+}
+
+/// Returns the deepest directory containing every path in the list.
+pub fn common_ancestor(paths: &[PathBuf]) -> PathBuf {
+    let mut parents = paths.iter().map(|p| p.parent().unwrap_or(Path::new("")));
+    let Some(first) = parents.next() else {
+        return PathBuf::new();
+    };
+    let mut common: Vec<Component> = first.components().collect();
+    for parent in parents {
+        let shared = common.iter().zip(parent.components()).take_while(|(a, b)| **a == *b).count();
+        common.truncate(shared);
+    }
+    common.iter().collect()
+}
+
+/// Reconstructs the directory hierarchy described by a list of paths.
+///
+/// Every directory between the common ancestor and a listed path gets its own
+/// node, marked virtual unless it was listed too. Nodes are ordered so each
+/// directory comes right before its children, with siblings sorted by name.
+pub fn build_tree_from_path_list(paths: &[PathBuf]) -> Vec<TreeNode> {
+    let root = common_ancestor(paths);
+    let mut nodes: BTreeMap<PathBuf, TreeNode> = BTreeMap::new();
+    for path in paths {
+        let Ok(relative) = path.strip_prefix(&root) else {
+            continue;
+        };
+        let components: Vec<Component> = relative.components().collect();
+        let mut current = PathBuf::new();
+        for (index, component) in components.iter().enumerate() {
+            current.push(component);
+            let is_leaf = index + 1 == components.len();
+            let node = nodes.entry(current.clone()).or_insert_with(|| TreeNode {
+                path: current.clone(),
+                depth: index + 1,
+                is_dir: !is_leaf,
+                is_virtual: !is_leaf,
+            });
+            if is_leaf {
+                node.is_virtual = false;
+            } else {
+                node.is_dir = true;
+            }
+        }
+    }
+    nodes.into_values().collect()
+}
+
+/// Prints the tree reconstructed from an `--input` path list.
+fn print_path_list(out: &mut TreeWriter, args: &ViewArgs, paths: &[PathBuf]) -> anyhow::Result<()> {
+    let root = common_ancestor(paths);
+    let root_label = args.display_root.clone().unwrap_or_else(|| {
+        if root.as_os_str().is_empty() {
+            ".".to_string()
+        } else {
+            root.display().to_string()
+        }
+    });
+    let nodes = build_tree_from_path_list(paths);
+    let files: Vec<PathBuf> = if args.access_check || args.write_check {
+        nodes.iter().filter(|n| !n.is_dir).map(|n| root.join(&n.path)).collect()
+    } else {
+        Vec::new()
+    };
+    let (unreadable, unwritable) = check_access(args, &files)?;
+
+    if writeln!(out, "{}", root_label.blue().bold()).is_err() {
+        return Ok(());
+    }
+
+    let (mut dir_count, mut file_count) = (0, 0);
+    for node in nodes {
+        if args.effective_level().is_some_and(|level| node.depth > level)
+            || args.min_depth.is_some_and(|min| node.depth < min)
+            || args.hides_kind(node.is_dir)
+        {
+            continue;
+        }
+        let name = node.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let name = if node.is_dir { name.blue().bold() } else { name.normal() };
+        let branch = branch(args, node.depth, CONNECTOR);
+        let branch = if node.is_virtual { branch.bright_black() } else { branch.normal() };
+        let annotations = access_annotations(&root.join(&node.path), &unreadable, &unwritable);
+        if writeln!(out, "{branch} {name}{annotations}").is_err() {
+            return Ok(());
+        }
+        if node.is_dir {
+            dir_count += 1;
+        } else {
+            file_count += 1;
+        }
+    }
+    let mut summary = format!("\n{dir_count} directories, {file_count} files");
+    if args.access_check {
+        summary.push_str(&format!(", {} unreadable files", unreadable.len()));
+    }
+    if args.write_check {
+        summary.push_str(&format!(", {} unwritable files", unwritable.len()));
+    }
+    out.finish_tree()?;
+    _ = writeln!(out, "{summary}");
+    Ok(())
+}
+
+/// Applies an xterm-256 foreground color, which `colored` cannot express.
+pub fn paint_indexed(text: String, index: Option<u8>) -> String {
+    match index {
+        Some(index) if control::SHOULD_COLORIZE.should_colorize() => {
+            format!("\x1B[38;5;{index}m{text}\x1B[39m")
+        }
+        _ => text,
+    }
+}
+
+/// Applies a 24-bit foreground color. `colored` downgrades RGB colors unless
+/// `COLORTERM` advertises truecolor, and `--color-mode truecolor` must not depend on it.
+pub fn paint_rgb(text: String, rgb: Option<(u8, u8, u8)>) -> String {
+    match rgb {
+        Some((r, g, b)) if control::SHOULD_COLORIZE.should_colorize() => {
+            format!("\x1B[38;2;{r};{g};{b}m{text}\x1B[39m")
+        }
+        _ => text,
+    }
+}
+
+/// Runs the `--access-check` and `--write-check` checks over `files`.
+///
+/// Returns the unreadable and unwritable paths, or an error at the first
+/// unreadable file with `--access-check-fail-fast`.
+fn check_access(
+    args: &ViewArgs,
+    files: &[PathBuf],
+) -> anyhow::Result<(HashSet<PathBuf>, HashSet<PathBuf>)> {
+    if args.access_check_fail_fast {
+        for path in files {
+            if let Err(err) = fs::File::open(path) {
+                anyhow::bail!("'{}' is not readable: {err}", path.display());
+            }
+        }
+    }
+    let failed = |enabled: bool, check: fn(&[PathBuf]) -> Vec<(PathBuf, io::Error)>| {
+        if enabled {
+            check(files).into_iter().map(|(path, _)| path).collect()
+        } else {
+            HashSet::new()
+        }
+    };
+    Ok((
+        failed(args.access_check, utils::check_readability),
+        failed(args.write_check, utils::check_writability),
+    ))
+}
+
+/// Builds the `(+N ignored)` annotation of a directory, empty when nothing is ignored.
+fn ignored_annotation(count: usize) -> String {
+    if count == 0 {
+        return String::new();
+    }
+    format!(" {}", format!("(+{count} ignored)").bright_black())
+}
+
+/// Builds the `[unreadable]` and `[unwritable]` annotations of a path.
+fn access_annotations(
+    path: &Path,
+    unreadable: &HashSet<PathBuf>,
+    unwritable: &HashSet<PathBuf>,
+) -> String {
+    let mut annotations = String::new();
+    if unreadable.contains(path) {
+        annotations.push_str(&format!(" {}", "[unreadable]".bright_red()));
+    }
+    if unwritable.contains(path) {
+        annotations.push_str(&format!(" {}", "[unwritable]".bright_red()));
+    }
+    annotations
+}
+
+/// Writes lines to `out`, stopping at the first write error.
+fn print_lines(out: &mut dyn Write, lines: &[String]) {
+    for line in lines {
+        if writeln!(out, "{line}").is_err() {
+            break;
+        }
+    }
+}
+
+/// Builds the `chmod` commands suggested by `--permission-fix`.
+///
+/// Symlinks are skipped since `chmod` would change their target instead.
+/// Paths are made absolute so the commands can be run from anywhere.
+#[cfg(unix)]
+fn permission_fix_commands(args: &ViewArgs, entries: &[ignore::DirEntry]) -> Vec<String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if args.permission_fix.is_empty() {
+        return Vec::new();
+    }
+    let mut commands = Vec::new();
+    for entry in entries.iter().filter(|e| !e.path_is_symlink()) {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let mode = metadata.permissions().mode();
+        let fixes =
+            utils::PermissionIssue::fixes_for(&args.permission_fix, mode, metadata.is_dir());
+        if fixes.is_empty() {
+            continue;
+        }
+        let path = std::path::absolute(entry.path()).unwrap_or_else(|_| entry.path().to_path_buf());
+        commands.extend(fixes.into_iter().map(|expr| utils::suggest_chmod(&path, mode, expr)));
+    }
+    commands
+}
+
+/// Unix permission bits are not available on this platform.
+#[cfg(not(unix))]
+fn permission_fix_commands(_args: &ViewArgs, _entries: &[ignore::DirEntry]) -> Vec<String> {
+    Vec::new()
+}
+
+/// Writes the suggested `chmod` commands to an executable shell script.
+fn write_permission_fix_script(path: &Path, commands: &[String]) -> anyhow::Result<()> {
+    let mut script = String::from("#!/bin/sh\n");
+    for command in commands {
+        script.push_str(command);
+        script.push('\n');
+    }
+    fs::write(path, script)?;
+    #[cfg(unix)]
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+/// A single change to apply to the terminal when refreshing a watched tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalUpdate {
+    /// Clear the whole screen.
+    Clear,
+    /// Replace the contents of a (0-based) screen row.
+    SetLine(usize, String),
+}
+
+/// Computes the terminal updates that turn the `prev` render into `next`.
+///
+/// Only changed rows are rewritten. When the number of lines changes the rows
+/// no longer line up, so the screen is cleared and fully redrawn instead.
+pub fn in_place_update(prev: &[String], next: &[String]) -> Vec<TerminalUpdate> {
+    if prev.len() != next.len() {
+        return full_redraw(next);
+    }
+    prev.iter()
+        .zip(next)
+        .enumerate()
+        .filter(|(_, (old, new))| old != new)
+        .map(|(row, (_, new))| TerminalUpdate::SetLine(row, new.clone()))
+        .collect()
+}
+
+/// Returns the updates that clear the screen and draw every line of `lines`.
+fn full_redraw(lines: &[String]) -> Vec<TerminalUpdate> {
+    std::iter::once(TerminalUpdate::Clear)
+        .chain(
+            lines.iter().enumerate().map(|(row, line)| TerminalUpdate::SetLine(row, line.clone())),
+        )
+        .collect()
+}
+
+/// Returns the lines of `next` that were not present in `prev`.
+pub fn changed_lines<'a>(prev: &[String], next: &'a [String]) -> Vec<&'a String> {
+    let previous: HashSet<&String> = prev.iter().collect();
+    next.iter().filter(|line| !previous.contains(line)).collect()
+}
+
+/// Writes terminal updates as cursor-positioning escape sequences.
+fn apply_updates(out: &mut dyn Write, updates: &[TerminalUpdate]) -> io::Result<()> {
+    for update in updates {
+        match update {
+            TerminalUpdate::Clear => write!(out, "\x1B[2J")?,
+            TerminalUpdate::SetLine(row, line) => write!(out, "\x1B[{};1H{line}\x1B[K", row + 1)?,
+        }
+    }
+    out.flush()
+}
+
+/// Renders the tree into lines instead of printing it.
+fn render_to_lines(args: &ViewArgs, ls_colors: &LsColors) -> anyhow::Result<Vec<String>> {
+    let mut buffer = Vec::new();
+    render(args, ls_colors, &mut buffer)?;
+    Ok(String::from_utf8_lossy(&buffer).lines().map(str::to_string).collect())
+}
+
+/// Keeps the tree on screen and refreshes the changed lines in place.
+///
+/// Rescans are driven by the same debounced filesystem events as `--watch`.
+/// With `--watch-diff-only`, the tree is printed once and each rescan only
+/// prints the lines that changed since the previous scan. Runs until interrupted.
+fn watch_persistent(args: &ViewArgs, ls_colors: &LsColors) -> anyhow::Result<()> {
+    let (_watcher, rx) = watch_root(args)?;
+    let debounce = Duration::from_millis(args.watch_interval);
+
+    let mut stdout = io::stdout();
+    let mut prev_lines: Vec<String> = Vec::new();
+    let mut first_scan = true;
+    loop {
+        let next_lines = render_to_lines(args, ls_colors)?;
+        let result = if args.watch_diff_only && !first_scan {
+            changed_lines(&prev_lines, &next_lines)
+                .into_iter()
+                .try_for_each(|line| writeln!(stdout, "{line}"))
+        } else if args.watch_diff_only {
+            next_lines.iter().try_for_each(|line| writeln!(stdout, "{line}"))
+        } else {
+            // Rows past the bottom of the screen cannot be addressed, so a tree
+            // taller than the terminal is always redrawn (and scrolls).
+            let updates = if next_lines.len() >= utils::terminal_height() {
+                full_redraw(&next_lines)
+            } else {
+                in_place_update(&prev_lines, &next_lines)
+            };
+            apply_updates(&mut stdout, &updates)
+        };
+        if result.is_err() {
+            return Ok(());
+        }
+
+        prev_lines = next_lines;
+        first_scan = false;
+        if !wait_for_change(&rx, debounce) {
+            return Ok(());
+        }
+    }
+}
+
+/// Returns the indentation and connector drawn before an entry at `depth`.
+///
+/// As a Markdown list, each level is indented by two spaces, which nests the
+/// item below the `- ` marker of its parent.
+fn branch(args: &ViewArgs, depth: usize, connector: &str) -> String {
+    let depth = depth.saturating_sub(1);
+    if args.prints_markdown_list() {
+        format!("{}-", "  ".repeat(depth))
+    } else {
+        format!("{}{connector}", " ".repeat(args.indent * depth))
+    }
+}
+
+/// Returns true for the events that should redraw a watched tree.
+///
+/// Reading the tree can update access times, so those changes are ignored to
+/// avoid redrawing in a loop.
+pub fn is_watched_event(kind: &EventKind) -> bool {
+    match kind {
+        EventKind::Modify(ModifyKind::Metadata(MetadataKind::AccessTime)) => false,
+        EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(_) => true,
+        EventKind::Any | EventKind::Access(_) | EventKind::Other => false,
+    }
+}
+
+/// Redraws the whole tree each time the filesystem below the root changes.
+///
+/// Changes are debounced: after the first event, the redraw waits until no
+/// event arrived for `--watch-interval` milliseconds, so bulk operations only
+/// redraw once. The terminal modes are never changed, so interrupting with
+/// Ctrl-C leaves it as it was.
+fn watch(args: &ViewArgs, ls_colors: &LsColors) -> anyhow::Result<()> {
+    let (_watcher, rx) = watch_root(args)?;
+    let debounce = Duration::from_millis(args.watch_interval);
+
+    let mut stdout = io::stdout();
+    loop {
+        if write!(stdout, "\x1B[2J\x1B[H").is_err() {
+            return Ok(());
+        }
+        render(args, ls_colors, &mut stdout)?;
+        if !wait_for_change(&rx, debounce) {
+            return Ok(());
+        }
+    }
+}
+
+/// Starts watching the root recursively. The watcher must be kept alive for
+/// events to keep arriving on the returned channel.
+fn watch_root(
+    args: &ViewArgs,
+) -> anyhow::Result<(notify::RecommendedWatcher, mpsc::Receiver<notify::Result<notify::Event>>)> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| anyhow::anyhow!("Failed to start watching: {e}"))?;
+    watcher
+        .watch(&args.path, RecursiveMode::Recursive)
+        .map_err(|e| anyhow::anyhow!("Failed to watch '{}': {e}", args.path.display()))?;
+    Ok((watcher, rx))
+}
+
+/// Blocks until a relevant change, then lets the burst of events settle for
+/// `debounce`. Returns false once the watcher has stopped.
+fn wait_for_change(rx: &mpsc::Receiver<notify::Result<notify::Event>>, debounce: Duration) -> bool {
+    loop {
+        match rx.recv() {
+            Ok(Ok(event)) if is_watched_event(&event.kind) => break,
+            Ok(_) => continue,
+            Err(_) => return false,
+        }
+    }
+    while rx.recv_timeout(debounce).is_ok() {}
+    true
+}
+
+/// Builds a line such as `── src / utils / helpers ──` spanning the terminal width.
+///
+/// The components of `path` relative to `root` are joined with ` / `.
+pub fn build_breadcrumb(path: &Path, root: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let label = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" / ");
+    let label = format!(" {label} ");
+
+    let padding = utils::terminal_width().saturating_sub(label.chars().count()).max(4);
+    let left = padding / 2;
+    format!("{}{label}{}", "─".repeat(left), "─".repeat(padding - left))
+}
+
+/// Maps each sparse file to its allocated and apparent size, with `--report-sparse-files`.
+fn find_sparse_files(
+    args: &ViewArgs,
+    entries: &[ignore::DirEntry],
+) -> HashMap<PathBuf, (u64, u64)> {
+    if !(args.report_sparse_files || args.report_sparse_only) {
+        return HashMap::new();
+    }
+    #[cfg(unix)]
+    {
+        entries
+            .iter()
+            .filter_map(|e| Some((e.path(), e.metadata().ok()?)))
+            .filter(|(_, metadata)| utils::is_sparse_file(metadata))
+            .map(|(path, metadata)| {
+                (path.to_path_buf(), (utils::allocated_bytes(&metadata), metadata.len()))
+            })
+            .collect()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = entries;
+        HashMap::new()
+    }
+}
+
+/// Collects the files flagged by `--report-duplicate-extensions` and its variants.
+fn find_conflicting_files(args: &ViewArgs, entries: &[ignore::DirEntry]) -> HashSet<PathBuf> {
+    if !(args.report_duplicate_extensions
+        || args.report_duplicate_extensions_only
+        || args.report_stem_conflicts)
+    {
+        return HashSet::new();
+    }
+
+    let per_directory = !args.report_stem_conflicts;
+    let groups = if per_directory {
+        utils::find_duplicate_extensions(entries)
+    } else {
+        utils::find_stem_conflicts(entries)
+    };
+    entries
+        .iter()
+        .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()))
+        .filter(|e| {
+            utils::stem_key(e.path(), per_directory).is_some_and(|key| groups.contains_key(&key))
+        })
+        .map(|e| e.path().to_path_buf())
+        .collect()
+}
+
+/// Prints the most recently modified files, relative to the scanned path.
+///
+/// With Git status enabled, untracked files are flagged since they are easy to
+/// forget when committing.
+fn print_last_modified(
+    out: &mut dyn Write,
+    args: &ViewArgs,
+    most_recent: &[(ignore::DirEntry, SystemTime)],
+    git_repo_status: Option<&git::GitRepoStatus>,
+) {
+    let now = SystemTime::now();
+    let describe = |(entry, modified): &(ignore::DirEntry, SystemTime)| {
+        let path = entry.path();
+        let rel_path = path.strip_prefix(&args.path).unwrap_or(path);
+        let is_untracked = git_repo_status.is_some_and(|status| {
+            path.canonicalize()
+                .ok()
+                .and_then(|p| status.cache.get(p.strip_prefix(&status.root).ok()?).copied())
+                == Some(git::FileStatus::Untracked)
+        });
+        let untracked =
+            if is_untracked { " [untracked]".yellow().to_string() } else { String::new() };
+        format!(
+            "{} ({}){untracked}",
+            rel_path.display(),
+            utils::format_relative_time(*modified, now)
+        )
+    };
+
+    match most_recent {
+        [] => {}
+        [single] if args.last_modified_n.is_none() => {
+            _ = writeln!(out, "Last modified: {}", describe(single));
+        }
+        files => {
+            _ = writeln!(out, "Last modified:");
+            for file in files {
+                if writeln!(out, "  {}", describe(file)).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Prints the extension bar chart, sized to the terminal width.
+fn print_extension_chart(
+    out: &mut dyn Write,
+    args: &ViewArgs,
+    extension_stats: Option<&stats::ExtensionStats>,
+) {
+    let Some(extension_stats) = extension_stats else {
+        return;
+    };
+    let chart = stats::render_extension_chart(
+        extension_stats,
+        args.extension_stats_by_size,
+        utils::terminal_width(),
+    );
+    for line in chart {
+        if writeln!(out, "{line}").is_err() {
+            break;
+        }
+    }
+}
+
+/// Prints the contents of an archive as a virtual subtree below its entry.
+///
+/// Virtual entries are prefixed with `⊂` and do not count towards the summary.
+/// Listing errors are reported to stderr; only write errors are returned.
+fn print_archive_contents(
+    out: &mut dyn Write,
+    args: &ViewArgs,
+    entry: &ignore::DirEntry,
+    columns_width: usize,
+) -> io::Result<()> {
+    let contents = match archive::list_archive_contents(entry.path()) {
+        Ok(contents) => contents,
+        Err(err) => {
+            eprintln!("fstree: ERROR: {}: {err}", entry.path().display());
+            return Ok(());
+        }
+    };
+
+    // Keep the tree aligned with the optional columns printed before it. List
+    // items start with their marker instead.
+    let columns_pad =
+        if args.prints_markdown_list() { String::new() } else { " ".repeat(columns_width) };
+
+    let contents: Vec<_> = contents
+        .into_iter()
+        .filter(|virtual_entry| {
+            args.effective_level().is_none_or(|level| virtual_entry.depth() <= level)
+                && !args.hides_kind(virtual_entry.is_dir)
+        })
+        .collect();
+    let child_counts = if args.no_branch_for_single_child {
+        count_children(contents.iter().map(|e| e.path.as_path()))
+    } else {
+        HashMap::new()
+    };
+
+    for virtual_entry in contents {
+        let is_only_child =
+            virtual_entry.path.parent().and_then(|p| child_counts.get(p)) == Some(&1);
+        let connector = if is_only_child { SINGLE_CHILD_CONNECTOR } else { CONNECTOR };
+
+        let branch = branch(args, entry.depth() + virtual_entry.depth(), connector);
+        let name = virtual_entry
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let icon_str = if args.shows_icon(&virtual_entry.path, virtual_entry.is_dir) {
+            let (icon, color) = icons::get_icon_for_path(&virtual_entry.path, virtual_entry.is_dir);
+            format!("{} ", icon.color(color))
+        } else {
+            String::new()
+        };
+        let size_str = if args.size && !virtual_entry.is_dir {
+            format!(" ({})", utils::format_size(virtual_entry.size))
+        } else {
+            String::new()
+        };
+        let styled_name = if virtual_entry.is_dir { name.blue() } else { name.normal() };
+
+        writeln!(
+            out,
+            "{}{} {} {}{}{}",
+            columns_pad,
+            branch,
+            "⊂".cyan(),
+            icon_str,
+            styled_name,
+            size_str.dimmed()
+        )?;
+    }
+    Ok(())
+}
+
+/// Resolves the `user:group` label of `--owner` for each entry.
+#[cfg(unix)]
+pub fn owner_labels(entries: &[ignore::DirEntry]) -> HashMap<PathBuf, String> {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut names = utils::OwnerNames::default();
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let user = names.user_name(metadata.uid()).to_string();
+            let label = format!("{user}:{}", names.group_name(metadata.gid()));
+            Some((entry.path().to_path_buf(), label))
+        })
+        .collect()
+}
+
+/// File ownership is only available on Unix, so `--owner` shows nothing.
+#[cfg(not(unix))]
+pub fn owner_labels(_entries: &[ignore::DirEntry]) -> HashMap<PathBuf, String> {
+    eprintln!("fstree: WARNING: --owner is only supported on Unix.");
+    HashMap::new()
+}
+
+/// Returns true, after warning on stderr, if `entry` is a symlink back into its own ancestors.
+///
+/// The walker only detects links to the directories it walked itself, so this
+/// also catches links to directories above the root.
+pub fn skips_symlink_cycle(entry: &ignore::DirEntry) -> bool {
+    let is_cycle = entry.path_is_symlink() && utils::is_symlink_cycle(entry.path());
+    if is_cycle {
+        warn_symlink_loop(entry.path());
+    }
+    is_cycle
+}
+
+/// Warns that the symlink at `path` is not followed because it loops.
+fn warn_symlink_loop(path: &Path) {
+    eprintln!("fstree: WARNING: Not following symlink loop '{}'", path.display());
+}
+
+/// Formats the ` -> target` annotation of `--dereference`, in red if the link is broken.
+fn symlink_target_label(path: &Path) -> String {
+    let Ok(target) = fs::read_link(path) else {
+        return String::new();
+    };
+    let target = target.display().to_string();
+    if fs::metadata(path).is_ok() {
+        format!(" -> {target}")
+    } else {
+        format!(" -> {}", target.red())
+    }
+}
+
+/// Prints every symlink with the chain of targets it resolves through.
+///
+/// The final target is green when it exists and red when the chain is broken
+/// or loops back on itself.
+fn print_symlink_chains(out: &mut dyn Write, args: &ViewArgs, entries: &[ignore::DirEntry]) {
+    let relative =
+        |path: &Path| path.strip_prefix(&args.path).unwrap_or(path).display().to_string();
+
+    let (mut symlinks, mut broken, mut loops) = (0, 0, 0);
+    for entry in entries.iter().filter(|e| e.path_is_symlink()) {
+        let chain = utils::resolve_symlink_chain(entry.path());
+        symlinks += 1;
+        broken += usize::from(chain.is_broken);
+        loops += usize::from(chain.is_loop);
+
+        let mut line = relative(entry.path());
+        if let Some((last, hops)) = chain.hops.split_last() {
+            for hop in hops {
+                line.push_str(&format!(" -> {}", relative(hop)));
+            }
+            let last = relative(last);
+            let last = if chain.is_loop {
+                format!("{} (loop)", last.red())
+            } else if chain.is_broken {
+                last.red().to_string()
+            } else {
+                last.green().to_string()
+            };
+            line.push_str(&format!(" -> {last}"));
+        }
+        if writeln!(out, "{line}").is_err() {
+            return;
+        }
+    }
+    _ = writeln!(out, "\n{symlinks} symlinks, {broken} broken, {loops} loops");
+}
+
+/// The entries owned by a single user (or group, with `--by-owner-gid`).
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct OwnerGroup {
+    /// The user or group id.
+    pub id: u32,
+    /// The resolved user or group name.
+    pub name: String,
+    pub entries: Vec<ignore::DirEntry>,
+}
+
+/// Groups entries by owning user, or by owning group when `by_gid` is set.
+///
+/// Groups are sorted by name, while entries keep their relative order.
+#[cfg(unix)]
+pub fn group_by_owner(entries: Vec<ignore::DirEntry>, by_gid: bool) -> Vec<OwnerGroup> {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut names = utils::OwnerNames::default();
+    let mut groups: Vec<OwnerGroup> = Vec::new();
+    for entry in entries {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let id = if by_gid { metadata.gid() } else { metadata.uid() };
+        match groups.iter_mut().find(|group| group.id == id) {
+            Some(group) => group.entries.push(entry),
+            None => {
+                let name = if by_gid { names.group_name(id) } else { names.user_name(id) };
+                groups.push(OwnerGroup { id, name: name.to_string(), entries: vec![entry] });
+            }
+        }
+    }
+    groups.sort_by(|a, b| a.name.cmp(&b.name));
+    groups
+}
+
+/// Prints each owner's entries under a `── name ──` header.
+///
+/// Entries are listed by their path relative to the root, since an owner's files
+/// are usually scattered across the tree.
+#[cfg(unix)]
+fn print_owner_groups(
+    out: &mut dyn Write,
+    args: &ViewArgs,
+    entries: Vec<ignore::DirEntry>,
+) -> anyhow::Result<()> {
+    let mut dir_count = 0;
+    let mut file_count = 0;
+    for group in group_by_owner(entries, args.by_owner_gid) {
+        let total_size = args.size.then(|| {
+            group
+                .entries
+                .iter()
+                .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()))
+                .filter_map(|e| e.metadata().ok())
+                .map(|m| m.len())
+                .sum::<u64>()
+        });
+        let size_str =
+            total_size.map(|size| format!(" ({})", utils::format_size(size))).unwrap_or_default();
+        if writeln!(out, "── {}{} ──", group.name.bold(), size_str.dimmed()).is_err() {
+            return Ok(());
+        }
+
+        for entry in &group.entries {
+            let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+            if args.hides_kind(is_dir) {
+                continue;
+            }
+            if is_dir {
+                dir_count += 1;
+            } else {
+                file_count += 1;
+            }
+            let path = entry.path();
+            let rel_path = path.strip_prefix(&args.path).unwrap_or(path).display().to_string();
+            let name = if is_dir { rel_path.blue().bold() } else { rel_path.normal() };
+            if writeln!(out, "└── {name}").is_err() {
+                return Ok(());
+            }
+        }
+    }
+    _ = writeln!(out, "\n{dir_count} directories, {file_count} files");
+    Ok(())
+}
+
+/// File ownership is only available on Unix.
+#[cfg(not(unix))]
+fn print_owner_groups(
+    _out: &mut dyn Write,
+    _args: &ViewArgs,
+    _entries: Vec<ignore::DirEntry>,
+) -> anyhow::Result<()> {
+    anyhow::bail!("--by-owner is only supported on Unix.")
+}
+
+/// Prints the inode map of all files followed by its legend.
+#[cfg(unix)]
+fn print_inode_map(
+    out: &mut dyn Write,
+    args: &ViewArgs,
+    entries: &[ignore::DirEntry],
+) -> anyhow::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let inodes: Vec<(u64, PathBuf)> = entries
+        .iter()
+        .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()))
+        .filter_map(|e| Some((e.metadata().ok()?.ino(), e.path().to_path_buf())))
+        .collect();
+
+    let map = stats::InodesMap { files_per_cell: args.inode_map_scale };
+    for line in map.render(&inodes, utils::terminal_width()) {
+        if writeln!(out, "{line}").is_err() {
+            return Ok(());
+        }
+    }
+    _ = writeln!(out);
+    for line in stats::InodesMap::legend(&inodes) {
+        if writeln!(out, "{line}").is_err() {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// Inode numbers are not available on this platform.
+#[cfg(not(unix))]
+fn print_inode_map(
+    _out: &mut dyn Write,
+    _args: &ViewArgs,
+    _entries: &[ignore::DirEntry],
+) -> anyhow::Result<()> {
+    anyhow::bail!("--inode-map is only supported on Unix.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn test_build_tree_from_path_list() {
+        let nodes = build_tree_from_path_list(&paths(&[
+            "src/view.rs",
+            "README.md",
+            "src/app/args.rs",
+            "src/app",
+        ]));
+        let summary: Vec<(&str, usize, bool, bool)> = nodes
+            .iter()
+            .map(|n| (n.path.to_str().unwrap(), n.depth, n.is_dir, n.is_virtual))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("README.md", 1, false, false),
+                ("src", 1, true, true),
+                ("src/app", 2, true, false),
+                ("src/app/args.rs", 3, false, false),
+                ("src/view.rs", 2, false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_common_ancestor() {
+        assert_eq!(common_ancestor(&paths(&["a/b/c.txt", "a/b/d/e.txt"])), PathBuf::from("a/b"));
+        assert_eq!(common_ancestor(&paths(&["a/b/c.txt", "a/x.txt"])), PathBuf::from("a"));
+        assert_eq!(common_ancestor(&paths(&["a/b.txt", "c.txt"])), PathBuf::new());
+        assert_eq!(common_ancestor(&[]), PathBuf::new());
+
+        // Paths are shown relative to the common ancestor.
+        let nodes = build_tree_from_path_list(&paths(&["/srv/app/a.txt", "/srv/app/lib/b.txt"]));
+        assert_eq!(nodes[0].path, PathBuf::from("a.txt"));
+        assert_eq!(nodes[1].path, PathBuf::from("lib"));
+        assert!(nodes[1].is_virtual);
+    }
+
+    #[test]
+    fn test_in_place_update_changed_rows() {
+        let prev = lines(&["root", "└── a.txt", "└── b.txt", "", "0 directories, 2 files"]);
+        let next = lines(&["root", "└── a.txt", "└── c.txt", "", "0 directories, 2 files"]);
+        assert_eq!(
+            in_place_update(&prev, &next),
+            vec![TerminalUpdate::SetLine(2, "└── c.txt".to_string())]
+        );
+        assert!(in_place_update(&next, &next).is_empty());
+    }
+
+    #[test]
+    fn test_in_place_update_redraws_on_line_count_change() {
+        let prev = lines(&["root", "└── a.txt"]);
+        let next = lines(&["root", "└── a.txt", "└── b.txt"]);
+        assert_eq!(
+            in_place_update(&prev, &next),
+            vec![
+                TerminalUpdate::Clear,
+                TerminalUpdate::SetLine(0, "root".to_string()),
+                TerminalUpdate::SetLine(1, "└── a.txt".to_string()),
+                TerminalUpdate::SetLine(2, "└── b.txt".to_string()),
+            ]
+        );
+        // The first render has nothing to diff against.
+        assert_eq!(in_place_update(&[], &prev)[0], TerminalUpdate::Clear);
+    }
+
+    #[test]
+    fn test_changed_lines() {
+        let prev = lines(&["root", "└── a.txt", "0 directories, 1 files"]);
+        let next = lines(&["root", "└── a.txt", "└── b.txt", "0 directories, 2 files"]);
+        assert_eq!(changed_lines(&prev, &next), vec!["└── b.txt", "0 directories, 2 files"]);
+    }
+
+    #[test]
+    fn test_is_watched_event() {
+        use notify::event::{AccessKind, CreateKind, RemoveKind, RenameMode};
+
+        assert!(is_watched_event(&EventKind::Create(CreateKind::File)));
+        assert!(is_watched_event(&EventKind::Remove(RemoveKind::Folder)));
+        assert!(is_watched_event(&EventKind::Modify(ModifyKind::Name(RenameMode::Both))));
+        assert!(is_watched_event(&EventKind::Modify(ModifyKind::Metadata(
+            MetadataKind::Permissions
+        ))));
+        assert!(!is_watched_event(&EventKind::Modify(ModifyKind::Metadata(
+            MetadataKind::AccessTime
+        ))));
+        assert!(!is_watched_event(&EventKind::Access(AccessKind::Read)));
+    }
+
+    #[test]
+    fn test_apply_updates() {
+        let mut out = Vec::new();
+        let updates = [TerminalUpdate::Clear, TerminalUpdate::SetLine(1, "x".to_string())];
+        apply_updates(&mut out, &updates).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\x1B[2J\x1B[2;1Hx\x1B[K");
+    }
+}
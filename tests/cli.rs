@@ -8,9 +8,19 @@ use tempfile::tempdir;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 
+/// Builds a command for the binary that ignores the developer's own config file.
+///
+/// Tests that need a config file point `XDG_CONFIG_HOME` at their own directory.
+fn fstree() -> Result<Command, assert_cmd::cargo::CargoError> {
+    let mut cmd = Command::cargo_bin("fstree")?;
+    cmd.env("XDG_CONFIG_HOME", concat!(env!("CARGO_TARGET_TMPDIR"), "/no-config"))
+        .env_remove("HOME");
+    Ok(cmd)
+}
+
 #[test]
 fn test_nonexistent_path() -> Result<(), Box<dyn std::error::Error>> {
-    let mut cmd = Command::cargo_bin("fstree")?;
+    let mut cmd = fstree()?;
     cmd.arg("nonexistent/path/for/testing");
     cmd.assert().failure().stderr(predicate::str::contains("is not a directory"));
     Ok(())
@@ -23,7 +33,7 @@ fn test_simple_view() -> Result<(), Box<dyn std::error::Error>> {
     fs::create_dir(temp_dir.path().join("dir1"))?;
     fs::File::create(temp_dir.path().join("dir1/b.txt"))?;
 
-    let mut cmd = Command::cargo_bin("fstree")?;
+    let mut cmd = fstree()?;
     cmd.arg(temp_dir.path());
     cmd.assert()
         .success()
@@ -38,11 +48,11 @@ fn test_all_flag() -> Result<(), Box<dyn std::error::Error>> {
     let temp_dir = tempdir()?;
     fs::File::create(temp_dir.path().join(".hidden"))?;
 
-    let mut cmd_no_all = Command::cargo_bin("fstree")?;
+    let mut cmd_no_all = fstree()?;
     cmd_no_all.arg(temp_dir.path());
     cmd_no_all.assert().success().stdout(predicate::str::contains(".hidden").not());
 
-    let mut cmd_with_all = Command::cargo_bin("fstree")?;
+    let mut cmd_with_all = fstree()?;
     cmd_with_all.arg("-a").arg(temp_dir.path());
     cmd_with_all.assert().success().stdout(predicate::str::contains(".hidden"));
     Ok(())
@@ -54,7 +64,7 @@ fn test_depth_flag() -> Result<(), Box<dyn std::error::Error>> {
     fs::create_dir(temp_dir.path().join("dir1"))?;
     fs::File::create(temp_dir.path().join("dir1/b.txt"))?;
 
-    let mut cmd = Command::cargo_bin("fstree")?;
+    let mut cmd = fstree()?;
     cmd.arg("-L").arg("1").arg(temp_dir.path());
     cmd.assert()
         .success()
@@ -98,7 +108,7 @@ fn test_gitignore_flag() -> Result<(), Box<dyn std::error::Error>> {
 
     // 4. Run fstree, passing the temp path as an argument. This is more robust
     // than relying on `current_dir` for this specific test.
-    let mut cmd = Command::cargo_bin("fstree")?;
+    let mut cmd = fstree()?;
     cmd.arg("-g").arg(temp_path);
 
     // 5. Assert that the correct files are included and excluded.
@@ -121,13 +131,55 @@ fn test_permissions_flag() -> Result<(), Box<dyn std::error::Error>> {
     let perms = fs::Permissions::from_mode(0o550);
     fs::set_permissions(&file_path, perms)?;
 
-    let mut cmd = Command::cargo_bin("fstree")?;
+    let mut cmd = fstree()?;
     cmd.arg("-p").arg(temp_dir.path());
     cmd.assert().success().stdout(predicate::str::contains("-r-xr-x---"));
 
     Ok(())
 }
 
+#[test]
+fn test_octal_permissions() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let file_path = temp_dir.path().join("setuid_file");
+    fs::File::create(&file_path)?;
+    fs::set_permissions(&file_path, fs::Permissions::from_mode(0o4755))?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["-p", "--octal"]).arg(temp_dir.path());
+    cmd.assert().success().stdout(predicate::str::contains("-rwxr-xr-x (4755) "));
+
+    // `--octal` only extends `--permissions`.
+    let mut cmd = fstree()?;
+    cmd.arg("--octal").arg(temp_dir.path());
+    cmd.assert().failure().stderr(predicate::str::contains("--permissions"));
+
+    Ok(())
+}
+
+#[test]
+fn test_highlight_special() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    for (name, mode) in
+        [("plain", 0o644), ("setuid", 0o4755), ("setgid", 0o2755), ("shared", 0o666)]
+    {
+        let path = temp_dir.path().join(name);
+        fs::File::create(&path)?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode))?;
+    }
+
+    let mut cmd = fstree()?;
+    cmd.args(["--color", "always", "--highlight-special"]).arg(temp_dir.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"\x1b\[41(;\d+)*msetuid").unwrap())
+        .stdout(predicate::str::is_match(r"\x1b\[45(;\d+)*msetgid").unwrap())
+        .stdout(predicate::str::is_match(r"\x1b\[43(;\d+)*mshared").unwrap())
+        .stdout(predicate::str::contains(" plain"));
+
+    Ok(())
+}
+
 #[test]
 fn test_git_status_flag() -> Result<(), Box<dyn std::error::Error>> {
     let temp_dir = tempdir()?;
@@ -152,7 +204,7 @@ fn test_git_status_flag() -> Result<(), Box<dyn std::error::Error>> {
     Command::new("git").args(["add", "staged.txt"]).current_dir(temp_path).output()?;
     fs::write(temp_path.join("untracked.txt"), "untracked")?;
 
-    let mut cmd = Command::cargo_bin("fstree")?;
+    let mut cmd = fstree()?;
     cmd.arg("-G").arg("-a").arg(temp_path);
 
     cmd.assert()
@@ -164,6 +216,283 @@ fn test_git_status_flag() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn test_git_status_submodule() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    let git = |dir: &std::path::Path, args: &[&str]| {
+        Command::new("git")
+            .args(["-c", "user.name=Test User", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+    };
+
+    // A nested repository becomes a submodule when added in place.
+    let sub_path = temp_path.join("vendored");
+    fs::create_dir(&sub_path)?;
+    git(&sub_path, &["init"])?;
+    fs::write(sub_path.join("lib.txt"), "lib")?;
+    git(&sub_path, &["add", "."])?;
+    git(&sub_path, &["commit", "-m", "initial commit"])?;
+    git(temp_path, &["init"])?;
+    fs::write(temp_path.join("main.txt"), "main")?;
+    git(temp_path, &["submodule", "add", "./vendored", "vendored"])?;
+    git(temp_path, &["add", "."])?;
+    git(temp_path, &["commit", "-m", "add submodule"])?;
+
+    let mut cmd = fstree()?;
+    cmd.arg("-G").arg(temp_path);
+    let output = cmd.output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+
+    assert!(output.status.success());
+    assert!(stdout.lines().any(|line| line.starts_with("S ") && line.ends_with("vendored")));
+    // Clean files, including those inside the submodule, get no marker.
+    assert!(stdout.lines().any(|line| line.starts_with("   ") && line.ends_with("lib.txt")));
+
+    Ok(())
+}
+
+#[test]
+fn test_only_git_changed() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(["-c", "user.name=Test User", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(temp_path)
+            .output()
+    };
+
+    git(&["init"])?;
+    fs::create_dir(temp_path.join("src"))?;
+    fs::write(temp_path.join("src/clean.rs"), "clean")?;
+    fs::write(temp_path.join("src/changed.rs"), "initial")?;
+    fs::create_dir(temp_path.join("docs"))?;
+    fs::write(temp_path.join("docs/clean.md"), "clean")?;
+    git(&["add", "."])?;
+    git(&["commit", "-m", "initial commit"])?;
+    fs::write(temp_path.join("src/changed.rs"), "modified")?;
+    fs::write(temp_path.join("new.txt"), "untracked")?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["-G", "--only-git-changed"]).arg(temp_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"M\s+.*changed\.rs").unwrap())
+        .stdout(predicate::str::is_match(r"\?\s+.*new\.txt").unwrap())
+        // Directories are kept, even without changes below them.
+        .stdout(predicate::str::contains("src"))
+        .stdout(predicate::str::contains("docs"))
+        .stdout(predicate::str::contains("clean.").not());
+
+    let mut cmd = fstree()?;
+    cmd.arg("--only-git-changed").arg(temp_path);
+    cmd.assert().failure().stderr(predicate::str::contains("--git-status"));
+
+    Ok(())
+}
+
+#[test]
+fn test_only_untracked() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(["-c", "user.name=Test User", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(temp_path)
+            .output()
+    };
+
+    git(&["init"])?;
+    fs::write(temp_path.join("changed.txt"), "initial")?;
+    git(&["add", "."])?;
+    git(&["commit", "-m", "initial commit"])?;
+    fs::write(temp_path.join("changed.txt"), "modified")?;
+    fs::create_dir_all(temp_path.join("notes/drafts"))?;
+    fs::write(temp_path.join("notes/drafts/forgotten.md"), "untracked")?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["-G", "--only-untracked"]).arg(temp_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"\?\s+.*forgotten\.md").unwrap())
+        // The directories leading to the untracked file are kept.
+        .stdout(predicate::str::contains("notes"))
+        .stdout(predicate::str::contains("drafts"))
+        .stdout(predicate::str::contains("changed.txt").not());
+
+    let mut cmd = fstree()?;
+    cmd.args(["-G", "--only-untracked", "--only-git-changed"]).arg(temp_path);
+    cmd.assert().failure().stderr(predicate::str::contains("cannot be used with"));
+
+    Ok(())
+}
+
+#[test]
+fn test_git_status_branch_name() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(["-c", "user.name=Test User", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(temp_path)
+            .output()
+    };
+    let first_line = || -> Result<String, Box<dyn std::error::Error>> {
+        let output = fstree()?.arg("-G").arg(temp_path).output()?;
+        let stdout = String::from_utf8(output.stdout)?;
+        Ok(stdout.lines().next().unwrap_or_default().to_string())
+    };
+
+    git(&["init"])?;
+    fs::write(temp_path.join("file.txt"), "content")?;
+    git(&["add", "."])?;
+    git(&["commit", "-m", "initial commit"])?;
+    git(&["checkout", "-b", "feature"])?;
+    assert_eq!(first_line()?, format!("{} (feature)", temp_path.display()));
+
+    // A detached HEAD shows the short commit hash.
+    git(&["checkout", "--detach"])?;
+    let hash = String::from_utf8(git(&["rev-parse", "--short", "HEAD"])?.stdout)?;
+    assert_eq!(first_line()?, format!("{} ({})", temp_path.display(), hash.trim()));
+
+    // Without --git-status the header is unchanged.
+    let mut cmd = fstree()?;
+    cmd.arg(temp_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(format!("{}\n", temp_path.display())));
+
+    Ok(())
+}
+
+#[test]
+fn test_count_only() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir(temp_path.join("src"))?;
+    for name in ["src/main.rs", "src/lib.rs", "src/notes.txt", "build.rs", "README.md"] {
+        fs::write(temp_path.join(name), "")?;
+    }
+
+    let mut cmd = fstree()?;
+    cmd.arg("--count").arg(temp_path);
+    cmd.assert().success().stdout("1 directories, 5 files\n");
+
+    let mut cmd = fstree()?;
+    cmd.args(["--count", "--filter-ext", "rs"]).arg(temp_path);
+    cmd.assert().success().stdout("1 directories, 3 files\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_max_files_and_dirs() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    for dir in ["a", "b", "c"] {
+        fs::create_dir(temp_path.join(dir))?;
+        for file in ["1.txt", "2.txt", "3.txt"] {
+            fs::write(temp_path.join(dir).join(file), "")?;
+        }
+    }
+
+    let output = fstree()?.args(["--max-files", "4"]).arg(temp_path).output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(stdout.matches(".txt").count(), 4);
+    assert!(stdout.ends_with(", 4 files\n... (truncated)\n"), "{stdout}");
+
+    let output = fstree()?.args(["--max-dirs", "1"]).arg(temp_path).output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.ends_with("1 directories, 3 files\n... (truncated)\n"), "{stdout}");
+
+    // A limit the tree stays within prints no marker.
+    let mut cmd = fstree()?;
+    cmd.args(["--count", "--max-files", "9", "--max-dirs", "3"]).arg(temp_path);
+    cmd.assert().success().stdout("3 directories, 9 files\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_mime_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir(temp_path.join("assets"))?;
+    // No extensions, so the types come from the file signatures.
+    fs::write(temp_path.join("assets/logo"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR")?;
+    fs::write(temp_path.join("bundle"), b"PK\x03\x04\x14\0\0\0\0\0")?;
+    fs::write(temp_path.join("main.rs"), "fn main() {}")?;
+
+    let mut cmd = fstree()?;
+    cmd.arg("--mime").arg(temp_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("logo (image/png)"))
+        .stdout(predicate::str::contains("bundle (application/zip)"))
+        .stdout(predicate::str::contains("main.rs (text/x-rust)"))
+        .stdout(predicate::str::contains("assets ").not());
+
+    Ok(())
+}
+
+#[test]
+fn test_checksum_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("hello.txt"), "hello\n")?;
+    fs::create_dir(temp_path.join("empty_dir"))?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--checksum", "sha256"]).arg(temp_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "hello.txt (5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03)",
+        ))
+        .stdout(predicate::str::contains("empty_dir (").not());
+
+    let mut cmd = fstree()?;
+    cmd.args(["--checksum", "md5"]).arg(temp_path);
+    cmd.assert().success().stdout(predicate::str::contains("(b1946ac92492d2347c6235b4d2611184)"));
+
+    let mut cmd = fstree()?;
+    cmd.args(["--checksum", "sha1"]).arg(temp_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("(f572d396fae9206628714fb2ce00f72e94f2258f)"));
+
+    let mut cmd = fstree()?;
+    cmd.args(["--checksum", "sha256", "--checksum-max-size", "4"]).arg(temp_path);
+    cmd.assert().success().stdout(predicate::str::contains("hello.txt (too large)"));
+
+    Ok(())
+}
+
+#[test]
+fn test_line_count_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("poem.txt"), "one\ntwo\nthree\nfour\nfive\n")?;
+    fs::write(temp_path.join("single.txt"), "only")?;
+    fs::write(temp_path.join("program.bin"), b"\x7fELF\x02\x01\x01\0\0\0")?;
+
+    let mut cmd = fstree()?;
+    cmd.arg("--line-count").arg(temp_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("poem.txt (5 lines)"))
+        .stdout(predicate::str::contains("single.txt (1 line)"))
+        .stdout(predicate::str::contains("program.bin (binary)"));
+
+    Ok(())
+}
+
 #[test]
 fn test_sort_by_name() -> Result<(), Box<dyn std::error::Error>> {
     let temp_dir = tempdir()?;
@@ -171,7 +500,7 @@ fn test_sort_by_name() -> Result<(), Box<dyn std::error::Error>> {
     fs::File::create(temp_dir.path().join("apple.txt"))?;
     fs::File::create(temp_dir.path().join("banana.txt"))?;
 
-    let mut cmd = Command::cargo_bin("fstree")?;
+    let mut cmd = fstree()?;
     cmd.arg("--sort").arg("name").arg(temp_dir.path());
 
     let output = cmd.output()?;
@@ -188,13 +517,30 @@ fn test_sort_by_name() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn test_sort_keeps_children_under_parent() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::File::create(temp_dir.path().join("a.txt"))?;
+    fs::create_dir(temp_dir.path().join("dir1"))?;
+    fs::File::create(temp_dir.path().join("dir1/b.txt"))?;
+    fs::File::create(temp_dir.path().join("m.txt"))?;
+
+    let mut cmd = fstree()?;
+    cmd.arg(temp_dir.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("└── a.txt\n└── dir1\n    └── b.txt\n└── m.txt\n"));
+
+    Ok(())
+}
+
 #[test]
 fn test_dirs_first_sorting() -> Result<(), Box<dyn std::error::Error>> {
     let temp_dir = tempdir()?;
     fs::File::create(temp_dir.path().join("aaa_file.txt"))?;
     fs::create_dir(temp_dir.path().join("zzz_dir"))?;
 
-    let mut cmd = Command::cargo_bin("fstree")?;
+    let mut cmd = fstree()?;
     cmd.arg("--dirs-first").arg(temp_dir.path());
 
     let output = cmd.output()?;
@@ -216,7 +562,7 @@ fn test_natural_sorting() -> Result<(), Box<dyn std::error::Error>> {
     fs::File::create(temp_dir.path().join("file10.txt"))?;
     fs::File::create(temp_dir.path().join("file2.txt"))?;
 
-    let mut cmd = Command::cargo_bin("fstree")?;
+    let mut cmd = fstree()?;
     cmd.arg("--natural-sort").arg(temp_dir.path());
 
     let output = cmd.output()?;
@@ -239,7 +585,7 @@ fn test_reverse_sorting() -> Result<(), Box<dyn std::error::Error>> {
     fs::File::create(temp_dir.path().join("apple.txt"))?;
     fs::File::create(temp_dir.path().join("zebra.txt"))?;
 
-    let mut cmd = Command::cargo_bin("fstree")?;
+    let mut cmd = fstree()?;
     cmd.arg("--reverse").arg(temp_dir.path());
 
     let output = cmd.output()?;
@@ -261,7 +607,7 @@ fn test_case_sensitive_sorting() -> Result<(), Box<dyn std::error::Error>> {
     fs::File::create(temp_dir.path().join("banana.txt"))?;
 
     // Test case-sensitive (Apple should come before banana in ASCII)
-    let mut cmd = Command::cargo_bin("fstree")?;
+    let mut cmd = fstree()?;
     cmd.arg("--case-sensitive").arg(temp_dir.path());
 
     let output = cmd.output()?;
@@ -283,7 +629,7 @@ fn test_sort_by_extension() -> Result<(), Box<dyn std::error::Error>> {
     fs::File::create(temp_dir.path().join("file.aaa"))?;
     fs::File::create(temp_dir.path().join("file.bbb"))?;
 
-    let mut cmd = Command::cargo_bin("fstree")?;
+    let mut cmd = fstree()?;
     cmd.arg("--sort").arg("extension").arg(temp_dir.path());
 
     let output = cmd.output()?;
@@ -318,7 +664,7 @@ fn test_default_sort_order() -> Result<(), Box<dyn std::error::Error>> {
     assert!(file_a_path.exists(), "Upper.txt was not created");
     assert!(file_a_lower_path.exists(), "lower.txt was not created");
 
-    let mut cmd = Command::cargo_bin("fstree")?;
+    let mut cmd = fstree()?;
     cmd.arg("--case-sensitive").arg(temp_dir.path());
 
     let output = cmd.output()?;
@@ -350,7 +696,7 @@ fn test_dotfiles_first_sorting() -> Result<(), Box<dyn std::error::Error>> {
     fs::create_dir(temp_dir.path().join("folder"))?;
     fs::create_dir(temp_dir.path().join(".dotfolder"))?;
 
-    let mut cmd = Command::cargo_bin("fstree")?;
+    let mut cmd = fstree()?;
     cmd.arg("--dotfiles-first").arg("-a").arg(temp_dir.path());
 
     let output = cmd.output()?;
@@ -369,3 +715,1930 @@ fn test_dotfiles_first_sorting() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_include_archives_flag() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let temp_dir = tempdir()?;
+    let zip_file = fs::File::create(temp_dir.path().join("bundle.zip"))?;
+    let mut writer = zip::ZipWriter::new(zip_file);
+    let options = zip::write::SimpleFileOptions::default();
+    writer.start_file("docs/guide.md", options)?;
+    writer.write_all(b"# Guide")?;
+    writer.start_file("top.txt", options)?;
+    writer.write_all(b"top")?;
+    writer.finish()?;
+
+    let mut cmd_without = fstree()?;
+    cmd_without.arg(temp_dir.path());
+    cmd_without.assert().success().stdout(predicate::str::contains("guide.md").not());
+
+    let mut cmd = fstree()?;
+    cmd.arg("--include-archives").arg("--color").arg("never").arg(temp_dir.path());
+
+    // The archive sits at depth 1, so its contents start one indent level deeper.
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("└── bundle.zip"))
+        .stdout(predicate::str::contains("\n    └── ⊂ docs\n"))
+        .stdout(predicate::str::contains("\n        └── ⊂ guide.md\n"))
+        .stdout(predicate::str::contains("\n    └── ⊂ top.txt\n"))
+        .stdout(predicate::str::contains("0 directories, 1 files"));
+
+    // `guide.md` is the only entry in `docs`, unlike `docs` and `top.txt` at the top.
+    let mut cmd = fstree()?;
+    cmd.args(["--include-archives", "--no-branch-for-single-child", "--color", "never"]);
+    cmd.arg(temp_dir.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\n    └── ⊂ docs\n"))
+        .stdout(predicate::str::contains("\n        ── ⊂ guide.md\n"));
+
+    Ok(())
+}
+
+#[test]
+fn test_count_commits_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+
+    Command::new("git").arg("init").current_dir(temp_path).output()?;
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(temp_path)
+        .output()?;
+    Command::new("git")
+        .args(["config", "user.name", "Test User"])
+        .current_dir(temp_path)
+        .output()?;
+
+    // `busy.txt` is touched by three commits, `quiet.txt` by one.
+    for (i, files) in
+        [vec!["busy.txt", "quiet.txt"], vec!["busy.txt"], vec!["busy.txt"]].iter().enumerate()
+    {
+        for file in files {
+            fs::write(temp_path.join(file), format!("revision {i}"))?;
+        }
+        Command::new("git").args(["add", "."]).current_dir(temp_path).output()?;
+        Command::new("git")
+            .args(["commit", "-m", &format!("commit {i}")])
+            .current_dir(temp_path)
+            .output()?;
+    }
+
+    let mut cmd = fstree()?;
+    cmd.args(["-G", "--count-commits", "--sort", "commits", "--color", "never"]).arg(temp_path);
+    let output = cmd.output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+
+    assert!(stdout.contains("   3c └── busy.txt"), "unexpected output: {stdout}");
+    assert!(stdout.contains("   1c └── quiet.txt"), "unexpected output: {stdout}");
+    assert!(stdout.find("busy.txt").unwrap() < stdout.find("quiet.txt").unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn test_count_commits_requires_git_status() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let mut cmd = fstree()?;
+    cmd.arg("--count-commits").arg(temp_dir.path());
+    cmd.assert().failure().stderr(predicate::str::contains("--git-status"));
+    Ok(())
+}
+
+#[test]
+fn test_sort_commits_requires_count_commits() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    for args in [
+        &["--sort", "commits"][..],
+        &["--sort2", "commits"],
+        &["interactive", "--headless", "--sort", "commits"],
+    ] {
+        let mut cmd = fstree()?;
+        cmd.args(args).arg(temp_dir.path());
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("--sort commits requires --count-commits"));
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_ignore_permission_errors_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let locked_dir = temp_dir.path().join("locked");
+    fs::create_dir(&locked_dir)?;
+    fs::File::create(locked_dir.join("secret.txt"))?;
+    fs::File::create(temp_dir.path().join("visible.txt"))?;
+    fs::set_permissions(&locked_dir, fs::Permissions::from_mode(0o000))?;
+
+    // Elevated privileges bypass permission checks, so there is nothing to test.
+    if fs::read_dir(&locked_dir).is_ok() {
+        fs::set_permissions(&locked_dir, fs::Permissions::from_mode(0o755))?;
+        return Ok(());
+    }
+
+    let mut cmd_default = fstree()?;
+    cmd_default.arg(temp_dir.path());
+    cmd_default.assert().success().stderr(predicate::str::contains("Permission denied"));
+
+    let mut cmd = fstree()?;
+    cmd.arg("--ignore-permission-errors").arg(temp_dir.path());
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::contains("visible.txt"))
+        .stdout(predicate::str::contains("skipped").not());
+
+    let mut cmd_verbose = fstree()?;
+    cmd_verbose.args(["--ignore-permission-errors", "--verbose"]).arg(temp_dir.path());
+    cmd_verbose.assert().success().stdout(predicate::str::contains("1 unreadable entries skipped"));
+
+    fs::set_permissions(&locked_dir, fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+#[test]
+fn test_extension_stats_only_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("a.rs"), "fn main() {}")?;
+    fs::write(temp_dir.path().join("b.rs"), "")?;
+    fs::write(temp_dir.path().join("notes.md"), "")?;
+
+    let mut cmd = fstree()?;
+    cmd.arg("--extension-stats-only").arg(temp_dir.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("│ 2 files"))
+        .stdout(predicate::str::contains(".md"))
+        .stdout(predicate::str::contains("directories").not());
+
+    Ok(())
+}
+
+#[test]
+fn test_last_modified_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let files =
+        [("old.txt", 1_000_000_000), ("newest.txt", 1_700_000_000), ("mid.txt", 1_500_000_000)];
+    for (name, mtime) in files {
+        let path = temp_dir.path().join(name);
+        fs::write(&path, name)?;
+        filetime::set_file_mtime(&path, filetime::FileTime::from_unix_time(mtime, 0))?;
+    }
+
+    let mut cmd = fstree()?;
+    cmd.arg("--last-modified").arg(temp_dir.path());
+    cmd.assert().success().stdout(predicate::str::contains("Last modified: newest.txt ("));
+
+    let mut cmd_n = fstree()?;
+    cmd_n.args(["--last-modified-n", "2"]).arg(temp_dir.path());
+    let output = cmd_n.output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    let listing = stdout.split("Last modified:").nth(1).unwrap();
+    let newest_pos = listing.find("newest.txt").unwrap();
+    let mid_pos = listing.find("mid.txt").unwrap();
+    assert!(newest_pos < mid_pos);
+    assert!(!listing.contains("old.txt"));
+
+    Ok(())
+}
+
+#[test]
+fn test_virtual_root_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::File::create(temp_dir.path().join("file.txt"))?;
+    let real_root = temp_dir.path().display().to_string();
+
+    let mut cmd = fstree()?;
+    cmd.args(["--virtual-root", "myproject/"]).arg(temp_dir.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("myproject/\n"))
+        .stdout(predicate::str::contains("file.txt"))
+        .stdout(predicate::str::contains(real_root).not());
+
+    Ok(())
+}
+
+#[test]
+fn test_report_duplicate_extensions_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("other"))?;
+    for path in ["config.json", "config.yaml", "readme.md", "other/notes.txt"] {
+        fs::File::create(temp_dir.path().join(path))?;
+    }
+
+    let mut cmd = fstree()?;
+    cmd.arg("--report-duplicate-extensions").arg(temp_dir.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("config.json [dup-ext]"))
+        .stdout(predicate::str::contains("config.yaml [dup-ext]"))
+        .stdout(predicate::str::contains("readme.md [dup-ext]").not())
+        .stdout(predicate::str::contains("2 files with duplicate-extension conflicts"));
+
+    let mut cmd_only = fstree()?;
+    cmd_only.arg("--report-duplicate-extensions-only").arg(temp_dir.path());
+    cmd_only
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("config.json"))
+        .stdout(predicate::str::contains("readme.md").not())
+        .stdout(predicate::str::contains("other").not());
+
+    Ok(())
+}
+
+#[test]
+fn test_breadcrumb_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let deepest = temp_dir.path().join("a").join("b").join("c");
+    fs::create_dir_all(&deepest)?;
+    fs::File::create(temp_dir.path().join("a").join("b").join("in_b.txt"))?;
+    fs::File::create(deepest.join("in_c.txt"))?;
+
+    let mut cmd = fstree()?;
+    cmd.arg("--breadcrumb").arg(temp_dir.path());
+    let output = cmd.output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    let lines: Vec<&str> = stdout.lines().collect();
+    let position = |needle: &str| lines.iter().position(|line| line.contains(needle)).unwrap();
+
+    // Top-level children and their direct children get no breadcrumb.
+    assert!(!stdout.contains("─ a ─"));
+    assert_eq!(position("─ a / b ─") + 1, position("└── c"));
+    assert_eq!(position("─ a / b / c ─") + 1, position("in_c.txt"));
+    // Returning to `a/b` after the `c` block repeats its breadcrumb.
+    assert_eq!(lines.iter().filter(|line| line.contains("─ a / b ─")).count(), 2);
+    assert!(position("in_c.txt") < position("in_b.txt"));
+
+    Ok(())
+}
+
+#[test]
+fn test_type_filter() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("subdir"))?;
+    fs::File::create(temp_dir.path().join("subdir").join("file.txt"))?;
+    #[cfg(unix)]
+    std::os::unix::fs::symlink("subdir/file.txt", temp_dir.path().join("link.txt"))?;
+
+    let mut cmd_file = fstree()?;
+    cmd_file.args(["--type", "f"]).arg(temp_dir.path());
+    cmd_file
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("subdir"))
+        .stdout(predicate::str::contains("file.txt"))
+        .stdout(predicate::str::contains("link.txt").not());
+
+    let mut cmd_dir = fstree()?;
+    cmd_dir.args(["--type", "d"]).arg(temp_dir.path());
+    cmd_dir
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("subdir"))
+        .stdout(predicate::str::contains(".txt").not());
+
+    #[cfg(unix)]
+    {
+        let mut cmd_link = fstree()?;
+        cmd_link.args(["--type", "l"]).arg(temp_dir.path());
+        cmd_link
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("link.txt"))
+            .stdout(predicate::str::contains("file.txt").not());
+    }
+
+    let mut cmd_invalid = fstree()?;
+    cmd_invalid.args(["--type", "x"]).arg(temp_dir.path());
+    cmd_invalid.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn test_git_diff_stat_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+
+    Command::new("git").arg("init").current_dir(temp_path).output()?;
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(temp_path)
+        .output()?;
+    Command::new("git")
+        .args(["config", "user.name", "Test User"])
+        .current_dir(temp_path)
+        .output()?;
+
+    fs::write(temp_path.join("edited.txt"), "one\ntwo\nthree\nfour\n")?;
+    fs::write(temp_path.join("clean.txt"), "unchanged\n")?;
+    Command::new("git").args(["add", "."]).current_dir(temp_path).output()?;
+    Command::new("git").args(["commit", "-m", "initial"]).current_dir(temp_path).output()?;
+
+    // Replace two lines and append three: 5 insertions, 2 deletions.
+    fs::write(temp_path.join("edited.txt"), "one\nTWO\nTHREE\nfour\nfive\nsix\nseven\n")?;
+    fs::write(temp_path.join("new.txt"), "a\nb\n")?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["-G", "--git-diff-stat", "--color", "never"]).arg(temp_path);
+    let output = cmd.output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("M       +5-2 └── edited.txt"), "unexpected output: {stdout}");
+    assert!(stdout.contains("?       +2-0 └── new.txt"), "unexpected output: {stdout}");
+    assert!(stdout.contains("             └── clean.txt"), "unexpected output: {stdout}");
+
+    let mut cmd_bar = fstree()?;
+    cmd_bar.args(["-G", "--git-diff-stat", "--git-diff-stat-bar", "--color", "never"]);
+    let output = cmd_bar.arg(temp_path).output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("M   ++++++-- └── edited.txt"), "unexpected output: {stdout}");
+    assert!(stdout.contains("?        +++ └── new.txt"), "unexpected output: {stdout}");
+
+    Ok(())
+}
+
+#[test]
+fn test_dry_run_gitignore_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir(temp_path.join("logs"))?;
+    fs::File::create(temp_path.join("logs").join("today.txt"))?;
+    fs::File::create(temp_path.join("debug.log"))?;
+    fs::File::create(temp_path.join("main.rs"))?;
+    fs::File::create(temp_path.join(".hidden.log"))?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--dry-run-gitignore", "*.log", "--color", "always"]).arg(temp_path);
+    let output = cmd.output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+
+    // Strikethrough is SGR 9; hidden files are scanned too.
+    let flagged = |name: &str| {
+        stdout.lines().find(|line| line.contains(name)).is_some_and(|line| line.contains("\x1b[9"))
+    };
+    assert!(flagged("debug.log"));
+    assert!(flagged(".hidden.log"));
+    assert!(!flagged("main.rs"));
+    assert!(!flagged("today.txt"));
+    assert!(stdout.contains("2 entries would be ignored by '*.log'"));
+
+    // A directory pattern also hides everything inside it.
+    let mut cmd_dir = fstree()?;
+    cmd_dir.args(["--dry-run-gitignore", "logs/"]).arg(temp_path);
+    cmd_dir.assert().success().stdout(predicate::str::contains("2 entries would be ignored"));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_by_owner_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let mine = temp_dir.path().join("mine.txt");
+    let theirs = temp_dir.path().join("theirs.txt");
+    fs::write(&mine, "mine")?;
+    fs::write(&theirs, "theirs")?;
+
+    // Changing ownership requires elevated privileges, so there is nothing to test otherwise.
+    if std::os::unix::fs::chown(&theirs, Some(65534), Some(65534)).is_err() {
+        return Ok(());
+    }
+
+    let mut cmd = fstree()?;
+    cmd.args(["--by-owner", "-s", "--color", "never"]).arg(temp_dir.path());
+    let output = cmd.output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    let root_header = lines.iter().position(|l| l.starts_with("── root (4 B) ──")).unwrap();
+    let other_header = lines.iter().position(|l| l.contains("(6 B) ──")).unwrap();
+    assert_eq!(lines[root_header + 1], "└── mine.txt");
+    assert_eq!(lines[other_header + 1], "└── theirs.txt");
+    assert!(stdout.contains("0 directories, 2 files"));
+
+    let mut cmd_gid = fstree()?;
+    cmd_gid.args(["--by-owner-gid", "--color", "never"]).arg(temp_dir.path());
+    cmd_gid.assert().success().stdout(predicate::str::contains("── root ──"));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_scan_symlinks_only_flag() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::symlink;
+
+    let temp_dir = tempdir()?;
+    let dir = temp_dir.path();
+    fs::File::create(dir.join("target.txt"))?;
+    symlink("target.txt", dir.join("hop2"))?;
+    symlink("hop2", dir.join("hop1"))?;
+    symlink("missing.txt", dir.join("dangling"))?;
+    symlink("loop_b", dir.join("loop_a"))?;
+    symlink("loop_a", dir.join("loop_b"))?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--scan-symlinks-only", "--color", "never"]).arg(dir);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("hop1 -> hop2 -> target.txt\n"))
+        .stdout(predicate::str::contains("dangling -> missing.txt\n"))
+        .stdout(predicate::str::contains("loop_a -> loop_b -> loop_a (loop)\n"))
+        .stdout(predicate::str::contains("\ntarget.txt").not())
+        .stdout(predicate::str::contains("5 symlinks, 1 broken, 2 loops"));
+
+    Ok(())
+}
+
+#[test]
+fn test_large_dirs_command() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("big").join("nested"))?;
+    fs::create_dir(temp_dir.path().join("small"))?;
+    fs::write(temp_dir.path().join("big").join("nested").join("a.bin"), vec![0u8; 3000])?;
+    fs::write(temp_dir.path().join("small").join("b.bin"), vec![0u8; 1000])?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["large-dirs", "-n", "2"]).arg(temp_dir.path());
+    let output = cmd.output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[1], "   2.9 KiB   75.0%  big");
+    assert_eq!(lines[2], "   2.9 KiB   75.0%  big/nested");
+    assert!(!stdout.contains("small"));
+
+    let mut cmd_json = fstree()?;
+    cmd_json.args(["large-dirs", "--large-dirs-depth", "1", "--large-dirs-json"]);
+    cmd_json
+        .arg(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"total\": 4000"))
+        .stdout(predicate::str::contains("\"path\": \"small\""))
+        .stdout(predicate::str::contains("nested").not());
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_permission_fix_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let open_file = temp_dir.path().join("open.log");
+    let safe_file = temp_dir.path().join("safe.log");
+    fs::File::create(&open_file)?;
+    fs::File::create(&safe_file)?;
+    fs::set_permissions(&open_file, fs::Permissions::from_mode(0o666))?;
+    fs::set_permissions(&safe_file, fs::Permissions::from_mode(0o644))?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--permission-fix", "all", "--permission-fix-only"]).arg(temp_dir.path());
+    let output = cmd.output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    let commands: Vec<&str> = stdout.lines().filter(|l| l.starts_with("chmod")).collect();
+    assert_eq!(commands.len(), 1);
+    assert!(commands[0].starts_with("chmod o-w '"));
+    assert!(commands[0].contains("open.log' # was 0666"));
+    assert!(!stdout.contains("└──"));
+    // Nothing is actually changed.
+    assert_eq!(fs::metadata(&open_file)?.permissions().mode() & 0o777, 0o666);
+
+    let script = temp_dir.path().join("fix.sh");
+    let mut cmd_script = fstree()?;
+    cmd_script.args(["--permission-fix", "world-writable", "--permission-fix-script"]);
+    cmd_script.arg(&script).arg(temp_dir.path());
+    cmd_script.assert().success().stdout(predicate::str::contains("└── open.log"));
+    let contents = fs::read_to_string(&script)?;
+    assert!(contents.starts_with("#!/bin/sh\nchmod o-w '"));
+
+    Ok(())
+}
+
+#[test]
+fn test_color_mode_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::File::create(temp_dir.path().join("main.rs"))?;
+
+    let run = |mode: &str| -> Result<String, Box<dyn std::error::Error>> {
+        let mut cmd = fstree()?;
+        cmd.env("LS_COLORS", "*.rs=38;2;250;10;10").env_remove("COLORTERM");
+        cmd.args(["--color", "always", "--color-mode", mode]).arg(temp_dir.path());
+        Ok(String::from_utf8(cmd.output()?.stdout)?)
+    };
+
+    assert!(run("8")?.contains("\x1b[31mmain.rs"));
+    assert!(run("256")?.contains("\x1b[38;5;196mmain.rs"));
+    assert!(run("truecolor")?.contains("\x1b[38;2;250;10;10mmain.rs"));
+
+    Ok(())
+}
+
+#[test]
+fn test_input_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let list = temp_dir.path().join("files.txt");
+    fs::write(&list, "./src/main.rs\nsrc/lib/mod.rs\nREADME.md\n")?;
+
+    let mut cmd = fstree()?;
+    cmd.arg("--input").arg(&list);
+    cmd.assert().success().stdout(
+        predicate::str::contains(
+            ".\n└── README.md\n└── src\n    └── lib\n        └── mod.rs\n    └── main.rs",
+        )
+        .and(predicate::str::contains("2 directories, 3 files")),
+    );
+
+    let null_list = temp_dir.path().join("files.bin");
+    fs::write(&null_list, "docs/a.md\0docs/b.md\0")?;
+    let mut cmd_null = fstree()?;
+    cmd_null.arg("--input").arg(&null_list).arg("--input-null");
+    cmd_null.assert().success().stdout(predicate::str::starts_with("docs\n└── a.md\n└── b.md\n"));
+
+    let mut cmd_conflict = fstree()?;
+    cmd_conflict.arg("--input").arg(&list).arg(temp_dir.path());
+    cmd_conflict.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn test_min_depth_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("outer/inner/deepest"))?;
+    fs::File::create(temp_dir.path().join("top.txt"))?;
+    fs::File::create(temp_dir.path().join("outer/middle.txt"))?;
+    fs::File::create(temp_dir.path().join("outer/inner/deepest/bottom.txt"))?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--min-depth", "2"]).arg(temp_dir.path());
+    cmd.assert().success().stdout(
+        predicate::str::contains("└── top.txt")
+            .not()
+            .and(predicate::str::contains("└── outer").not())
+            .and(predicate::str::contains("    └── middle.txt"))
+            .and(predicate::str::contains("└── bottom.txt")),
+    );
+
+    // Combined with --level, only a window of depths is shown.
+    let mut cmd_window = fstree()?;
+    cmd_window.args(["--min-depth", "2", "--level", "3"]).arg(temp_dir.path());
+    cmd_window.assert().success().stdout(
+        predicate::str::contains("└── deepest")
+            .and(predicate::str::contains("└── middle.txt"))
+            .and(predicate::str::contains("bottom.txt").not()),
+    );
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_access_check_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let locked = temp_dir.path().join("locked.txt");
+    fs::write(&locked, "secret")?;
+    fs::File::create(temp_dir.path().join("open.txt"))?;
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000))?;
+    if fs::File::open(&locked).is_ok() {
+        // Running as root, permissions are not enforced.
+        return Ok(());
+    }
+
+    let mut cmd = fstree()?;
+    cmd.args(["--access-check", "--write-check"]).arg(temp_dir.path());
+    cmd.assert().success().stdout(
+        predicate::str::contains("└── locked.txt [unreadable] [unwritable]")
+            .and(predicate::str::contains("└── open.txt\n"))
+            .and(predicate::str::contains("1 unreadable files, 1 unwritable files")),
+    );
+
+    let mut cmd_fail_fast = fstree()?;
+    cmd_fail_fast.args(["--access-check", "--access-check-fail-fast"]).arg(temp_dir.path());
+    cmd_fail_fast.assert().code(1).stderr(predicate::str::contains("locked.txt"));
+
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o644))?;
+    Ok(())
+}
+
+#[test]
+fn test_summarize_dir_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let vendor = temp_dir.path().join("vendor");
+    fs::create_dir_all(vendor.join("lib"))?;
+    fs::write(vendor.join("a.txt"), vec![0u8; 1000])?;
+    fs::write(vendor.join("lib/b.txt"), vec![0u8; 1048])?;
+    fs::create_dir(temp_dir.path().join("src"))?;
+    fs::File::create(temp_dir.path().join("src/main.rs"))?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--summarize-dir", "vend*"]).arg(temp_dir.path());
+    cmd.assert().success().stdout(
+        predicate::str::contains("└── vendor/ (2 files, 2.0 KiB)")
+            .and(predicate::str::contains("a.txt").not())
+            .and(predicate::str::contains("    └── main.rs")),
+    );
+
+    // With --level, every top-level directory collapses into a flat summary.
+    let mut cmd_depth = fstree()?;
+    cmd_depth.args(["--summarize-depth", "1", "--level", "1"]).arg(temp_dir.path());
+    cmd_depth.assert().success().stdout(
+        predicate::str::contains("└── src/ (1 files, 0 B)")
+            .and(predicate::str::contains("└── vendor/ (2 files, 2.0 KiB)")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_git_show_ignored_count_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    Command::new("git").arg("init").current_dir(temp_path).output()?;
+    fs::write(temp_path.join(".gitignore"), "*.log\nbuild/\n")?;
+
+    fs::create_dir_all(temp_path.join("src/build"))?;
+    fs::File::create(temp_path.join("src/main.rs"))?;
+    fs::File::create(temp_path.join("src/debug.log"))?;
+    fs::File::create(temp_path.join("src/trace.log"))?;
+    fs::create_dir(temp_path.join("docs"))?;
+    fs::File::create(temp_path.join("docs/guide.md"))?;
+    fs::File::create(temp_path.join("root.log"))?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["-g", "--git-show-ignored-count"]).arg(temp_path);
+    cmd.assert().success().stdout(
+        predicate::str::contains("(+1 ignored)\n")
+            .and(predicate::str::contains("└── src (+3 ignored)"))
+            .and(predicate::str::contains("└── docs\n"))
+            .and(predicate::str::contains("debug.log").not()),
+    );
+
+    let mut cmd_requires = fstree()?;
+    cmd_requires.arg("--git-show-ignored-count").arg(temp_path);
+    cmd_requires.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn test_sort_locale_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    for name in ["Zebra.txt", "Äpfel.txt", "Ofen.txt", "Öl.txt"] {
+        fs::File::create(temp_dir.path().join(name))?;
+    }
+
+    let names = |locale: &str| -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut cmd = fstree()?;
+        cmd.args(["--sort-locale", locale]).arg(temp_dir.path());
+        let stdout = String::from_utf8(cmd.output()?.stdout)?;
+        Ok(stdout.lines().filter_map(|l| l.strip_prefix("└── ")).map(String::from).collect())
+    };
+
+    assert_eq!(names("de_DE")?, ["Äpfel.txt", "Ofen.txt", "Öl.txt", "Zebra.txt"]);
+    assert_eq!(names("C")?, ["Ofen.txt", "Zebra.txt", "Äpfel.txt", "Öl.txt"]);
+
+    let mut cmd_invalid = fstree()?;
+    cmd_invalid.args(["--sort-locale", "???"]).arg(temp_dir.path());
+    cmd_invalid.assert().failure().stderr(predicate::str::contains("Unsupported locale"));
+
+    Ok(())
+}
+
+#[test]
+fn test_icon_scope_flags() -> Result<(), Box<dyn std::error::Error>> {
+    const DIR_ICON: &str = "\u{f115}";
+    const RUST_ICON: &str = "\u{e68b}";
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("src"))?;
+    fs::File::create(temp_dir.path().join("src/main.rs"))?;
+    fs::File::create(temp_dir.path().join("notes.txt"))?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--icons", "--no-icons-for-dirs"]).arg(temp_dir.path());
+    cmd.assert().success().stdout(
+        predicate::str::contains(DIR_ICON)
+            .not()
+            .and(predicate::str::contains("└── src\n"))
+            .and(predicate::str::contains(format!("{RUST_ICON} main.rs"))),
+    );
+
+    let mut cmd_files = fstree()?;
+    cmd_files.args(["--icons", "--no-icons-for-files"]).arg(temp_dir.path());
+    cmd_files.assert().success().stdout(
+        predicate::str::contains(format!("{DIR_ICON} src"))
+            .and(predicate::str::contains("└── main.rs"))
+            .and(predicate::str::contains("└── notes.txt")),
+    );
+
+    let mut cmd_ext = fstree()?;
+    cmd_ext.args(["--icons", "--icons-only-for-ext", "rs"]).arg(temp_dir.path());
+    cmd_ext.assert().success().stdout(
+        predicate::str::contains(format!("{RUST_ICON} main.rs"))
+            .and(predicate::str::contains("└── notes.txt"))
+            .and(predicate::str::contains("└── src\n")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_top_level_only_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("src/nested"))?;
+    fs::File::create(temp_dir.path().join("README.md"))?;
+    fs::File::create(temp_dir.path().join("src/main.rs"))?;
+    fs::File::create(temp_dir.path().join("src/nested/deep.rs"))?;
+
+    let mut cmd = fstree()?;
+    cmd.arg("--top-level-only").arg(temp_dir.path());
+    cmd.assert().success().stdout(
+        predicate::str::contains("(2 dirs, 3 files total)\n")
+            .and(predicate::str::contains("└── src\n"))
+            .and(predicate::str::contains("└── README.md\n"))
+            .and(predicate::str::contains("main.rs").not())
+            .and(predicate::str::contains("1 directories, 1 files")),
+    );
+
+    let mut cmd_summary = fstree()?;
+    cmd_summary.arg("--top-level-summary-only").arg(temp_dir.path());
+    let output = cmd_summary.output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.ends_with(" (2 dirs, 3 files total)\n"));
+
+    // The totals honor the same filters as the main walk.
+    let mut cmd_filtered = fstree()?;
+    cmd_filtered.arg("--top-level-only").arg("--exclude").arg("src/nested").arg(temp_dir.path());
+    cmd_filtered.assert().success().stdout(predicate::str::contains("(1 dirs, 2 files total)\n"));
+
+    Ok(())
+}
+
+#[test]
+fn test_no_branch_for_single_child_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("one"))?;
+    fs::create_dir_all(temp_dir.path().join("two"))?;
+    fs::File::create(temp_dir.path().join("one/only.txt"))?;
+    fs::File::create(temp_dir.path().join("two/first.txt"))?;
+    fs::File::create(temp_dir.path().join("two/second.txt"))?;
+
+    let mut cmd = fstree()?;
+    cmd.arg("--no-branch-for-single-child").arg(temp_dir.path());
+    cmd.assert().success().stdout(
+        predicate::str::contains("\n    ── only.txt\n")
+            .and(predicate::str::contains("\n    └── first.txt\n"))
+            .and(predicate::str::contains("\n    └── second.txt\n"))
+            .and(predicate::str::contains("\n└── one\n")),
+    );
+
+    let mut cmd_default = fstree()?;
+    cmd_default.arg(temp_dir.path());
+    cmd_default.assert().success().stdout(predicate::str::contains("\n    └── only.txt\n"));
+
+    Ok(())
+}
+
+#[test]
+fn test_git_tag_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    let git = |args: &[&str]| Command::new("git").args(args).current_dir(temp_path).output();
+
+    git(&["init"])?;
+    git(&["config", "user.email", "test@example.com"])?;
+    git(&["config", "user.name", "Test User"])?;
+
+    fs::write(temp_path.join("old.txt"), "one")?;
+    fs::write(temp_path.join("edited.txt"), "one")?;
+    git(&["add", "."])?;
+    git(&["commit", "-m", "first"])?;
+    git(&["tag", "-a", "v1.0.0", "-m", "release 1.0.0"])?;
+
+    fs::write(temp_path.join("edited.txt"), "two")?;
+    git(&["commit", "-am", "second"])?;
+    git(&["tag", "-a", "v1.1.0", "-m", "release 1.1.0"])?;
+
+    fs::write(temp_path.join("other.txt"), "three")?;
+    git(&["add", "."])?;
+    git(&["commit", "-m", "third"])?;
+
+    // Only changed files are described by default.
+    fs::write(temp_path.join("old.txt"), "changed")?;
+    fs::write(temp_path.join("edited.txt"), "changed")?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["-G", "--git-tag", "--color", "never"]).arg(temp_path);
+    cmd.assert().success().stdout(
+        predicate::str::contains("└── old.txt v1.0.0\n")
+            .and(predicate::str::contains("└── edited.txt v1.1.0\n"))
+            .and(predicate::str::contains("└── other.txt\n")),
+    );
+
+    let mut cmd_all = fstree()?;
+    cmd_all.args(["-G", "--git-tag", "--git-tag-all", "--git-tag-format", "describe"]);
+    cmd_all.arg(temp_path);
+    cmd_all.assert().success().stdout(
+        predicate::str::contains("└── other.txt v1.1.0-1-g")
+            .and(predicate::str::contains("└── edited.txt v1.1.0\n")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_walk_strategy_bottomup_dir_sizes() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir_all(temp_path.join("a/b"))?;
+    fs::write(temp_path.join("a/b/file"), vec![0u8; 100])?;
+    fs::write(temp_path.join("a/file2"), vec![0u8; 50])?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["-s", "--walk-strategy", "bottomup", "--color", "never"]).arg(temp_path);
+    cmd.assert().success().stdout(
+        predicate::str::contains("a (150 B)\n")
+            .and(predicate::str::contains("b (100 B)\n"))
+            .and(predicate::str::contains("file (100 B)\n")),
+    );
+
+    // Top-down keeps directories without a size.
+    let mut cmd = fstree()?;
+    cmd.args(["-s", "--color", "never"]).arg(temp_path);
+    cmd.assert().success().stdout(predicate::str::contains("a (150 B)").not());
+
+    Ok(())
+}
+
+#[test]
+fn test_color_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = fstree()?;
+    cmd.args(["color-test", "--color-test-ascii"]);
+    cmd.env("TERM", "xterm-256color").env_remove("COLORTERM");
+    cmd.assert().success().stdout(
+        predicate::str::contains("COLORTERM: (unset)\n")
+            .and(predicate::str::contains("TERM: xterm-256color\n"))
+            .and(predicate::str::contains("xterm-256 palette:"))
+            .and(predicate::str::contains("240 ")),
+    );
+
+    let mut cmd = fstree()?;
+    cmd.args(["interactive", "--color-test", "--headless"]);
+    cmd.assert().success().stdout(predicate::str::contains("24-bit gradient"));
+
+    Ok(())
+}
+
+#[test]
+fn test_exclude_from() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    let tree = temp_path.join("tree");
+    fs::create_dir_all(tree.join("target/debug"))?;
+    fs::write(tree.join("target/debug/app"), "")?;
+    fs::write(tree.join("main.rs"), "")?;
+    fs::write(tree.join("notes.tmp"), "")?;
+    fs::write(tree.join("cache.bin"), "")?;
+    fs::write(temp_path.join("build.exclude"), "# build output\ntarget/\n\n*.tmp\n")?;
+    fs::write(temp_path.join("cache.exclude"), "*.bin\n")?;
+
+    let mut cmd = fstree()?;
+    cmd.arg("--exclude-from").arg(temp_path.join("build.exclude"));
+    cmd.arg("--exclude-from").arg(temp_path.join("cache.exclude"));
+    cmd.args(["--color", "never"]).arg(&tree);
+    cmd.assert().success().stdout(
+        predicate::str::contains("main.rs")
+            .and(predicate::str::contains("target").not())
+            .and(predicate::str::contains("notes.tmp").not())
+            .and(predicate::str::contains("cache.bin").not()),
+    );
+
+    let mut missing = fstree()?;
+    missing.arg("--exclude-from").arg(temp_path.join("missing.exclude")).arg(&tree);
+    missing.assert().failure().stderr(predicate::str::contains("missing.exclude"));
+
+    Ok(())
+}
+
+#[test]
+fn test_git_conflict_markers() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("merged.rs"), "fn main() {}\n")?;
+    fs::write(temp_path.join("conflict.rs"), "<<<<<<< HEAD\na\n=======\nb\n>>>>>>> topic\n")?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--git-conflict-markers", "--color", "never"]).arg(temp_path);
+    cmd.assert()
+        .failure()
+        .stdout(
+            predicate::str::contains("└── ⚡ conflict.rs [CONFLICT]\n")
+                .and(predicate::str::contains("└── merged.rs\n"))
+                .and(predicate::str::contains("1 files with conflict markers")),
+        )
+        .stderr(predicate::str::contains("1 files still contain conflict markers"));
+
+    fs::write(temp_path.join("conflict.rs"), "b\n")?;
+    let mut resolved = fstree()?;
+    resolved.args(["--git-conflict-markers", "--color", "never"]).arg(temp_path);
+    resolved.assert().success().stdout(predicate::str::contains("[CONFLICT]").not());
+
+    Ok(())
+}
+
+#[test]
+fn test_print_config() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let config_home = temp_dir.path().join("config");
+    fs::create_dir_all(config_home.join("fstree"))?;
+    fs::write(
+        config_home.join("fstree/config.toml"),
+        "sort = \"size\"\nicons = true\nlevel = 1\n\n[profile.deep]\nlevel = 3\nicons = true\n",
+    )?;
+    let tree = temp_dir.path().join("tree");
+    fs::create_dir_all(tree.join("nested"))?;
+    fs::write(tree.join("nested/deep.txt"), "")?;
+
+    let mut cmd = fstree()?;
+    cmd.env("XDG_CONFIG_HOME", &config_home).env_remove("NO_COLOR").env_remove("CLICOLOR_FORCE");
+    cmd.args(["--sort", "modified", "print-config"]);
+    cmd.assert().success().stdout(
+        predicate::str::contains("sort:        modified (from CLI)\n")
+            .and(predicate::str::contains("icons:       true (from config file)\n"))
+            .and(predicate::str::contains("color:       auto (default)\n")),
+    );
+
+    let mut json = fstree()?;
+    json.env("XDG_CONFIG_HOME", &config_home).args(["print-config", "--format", "json"]);
+    json.assert().success().stdout(predicate::str::contains("\"source\": \"config-file\""));
+
+    // The config file's `level = 1` applies unless overridden on the command line.
+    let mut view = fstree()?;
+    view.env("XDG_CONFIG_HOME", &config_home).args(["--color", "never"]).arg(&tree);
+    view.assert().success().stdout(predicate::str::contains("deep.txt").not());
+
+    let mut deeper = fstree()?;
+    deeper.env("XDG_CONFIG_HOME", &config_home).args(["-L", "2", "--color", "never"]).arg(&tree);
+    deeper.assert().success().stdout(predicate::str::contains("deep.txt"));
+
+    // A flag given with its default value still overrides the config file.
+    fs::write(tree.join("a.txt"), "")?;
+    fs::write(tree.join("big.txt"), "x".repeat(1024))?;
+    let mut by_name = fstree()?;
+    by_name.env("XDG_CONFIG_HOME", &config_home);
+    by_name.args(["--sort", "name", "--no-icons", "--color", "never"]).arg(&tree);
+    by_name
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("└── a.txt\n└── big.txt\n└── nested\n"));
+
+    let mut sources = fstree()?;
+    sources.env("XDG_CONFIG_HOME", &config_home);
+    sources.args(["--sort", "name", "--no-icons", "--color", "auto", "print-config"]);
+    sources.assert().success().stdout(
+        predicate::str::contains("sort:        name (from CLI)\n")
+            .and(predicate::str::contains("icons:       false (from CLI)\n"))
+            .and(predicate::str::contains("color:       auto (from CLI)\n")),
+    );
+
+    // Settings set by the selected profile are attributed to it.
+    let mut profile = fstree()?;
+    profile.env("XDG_CONFIG_HOME", &config_home).args(["--profile", "deep", "print-config"]);
+    profile.assert().success().stdout(
+        predicate::str::contains("level:       3 (from profile deep)\n")
+            .and(predicate::str::contains("icons:       true (from profile deep)\n"))
+            .and(predicate::str::contains("sort:        size (from config file)\n")),
+    );
+    let mut profile_json = fstree()?;
+    profile_json.env("XDG_CONFIG_HOME", &config_home);
+    profile_json.args(["--profile", "deep", "print-config", "--format", "json"]);
+    profile_json.assert().success().stdout(
+        predicate::str::contains("\"source\": \"profile\"")
+            .and(predicate::str::contains("\"detail\": \"deep\"")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_config_file_icons_and_no_config() -> Result<(), Box<dyn std::error::Error>> {
+    const RUST_ICON: &str = "\u{e68b}";
+    let temp_dir = tempdir()?;
+    let config_home = temp_dir.path().join("config");
+    fs::create_dir_all(config_home.join("fstree"))?;
+    fs::write(config_home.join("fstree/config.toml"), "icons = true\n")?;
+    let tree = temp_dir.path().join("tree");
+    fs::create_dir(&tree)?;
+    fs::write(tree.join("main.rs"), "")?;
+
+    // The config file turns icons on without `--icons`.
+    let mut cmd = fstree()?;
+    cmd.env("XDG_CONFIG_HOME", &config_home).args(["--color", "never"]).arg(&tree);
+    cmd.assert().success().stdout(predicate::str::contains(format!("{RUST_ICON} main.rs")));
+
+    let mut no_config = fstree()?;
+    no_config.env("XDG_CONFIG_HOME", &config_home).args(["--no-config", "--color", "never"]);
+    no_config.arg(&tree).assert().success().stdout(predicate::str::contains("└── main.rs"));
+
+    // The interactive mode takes its defaults from the same file.
+    fs::write(config_home.join("fstree/config.toml"), "icons = true\nall = true\n")?;
+    fs::write(tree.join(".hidden"), "")?;
+    let mut interactive = fstree()?;
+    interactive.env("XDG_CONFIG_HOME", &config_home).args(["interactive", "--headless"]);
+    interactive
+        .arg(&tree)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".hidden").and(predicate::str::contains(RUST_ICON)));
+
+    // Commands that do not use the file are not stopped by a broken one.
+    fs::write(config_home.join("fstree/config.toml"), "sort = \n")?;
+    let mut color_test = fstree()?;
+    color_test.env("XDG_CONFIG_HOME", &config_home).args(["color-test", "--ascii"]);
+    color_test.assert().success();
+    let mut view = fstree()?;
+    view.env("XDG_CONFIG_HOME", &config_home).arg(&tree);
+    view.assert().failure().stderr(predicate::str::contains("Invalid TOML in config file"));
+
+    Ok(())
+}
+
+#[test]
+fn test_config_flag() -> Result<(), Box<dyn std::error::Error>> {
+    const RUST_ICON: &str = "\u{e68b}";
+    let temp_dir = tempdir()?;
+    let config = temp_dir.path().join("fstree.toml");
+    fs::write(&config, "icons = true\n")?;
+    let tree = temp_dir.path().join("tree");
+    fs::create_dir(&tree)?;
+    fs::write(tree.join("main.rs"), "")?;
+
+    let mut cmd = fstree()?;
+    cmd.arg("--config").arg(&config).args(["--color", "never"]).arg(&tree);
+    cmd.assert().success().stdout(predicate::str::contains(format!("{RUST_ICON} main.rs")));
+
+    fs::write(&config, "icons = [true\n")?;
+    let mut invalid = fstree()?;
+    invalid.arg("--config").arg(&config).arg(&tree);
+    invalid.assert().failure().stderr(
+        predicate::str::contains("Invalid TOML")
+            .and(predicate::str::contains(config.display().to_string())),
+    );
+
+    let missing = temp_dir.path().join("missing.toml");
+    let mut not_found = fstree()?;
+    not_found.arg("--config").arg(&missing).arg(&tree);
+    not_found
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!("Config file '{}' not found", missing.display())));
+
+    Ok(())
+}
+
+#[test]
+fn test_config_profile() -> Result<(), Box<dyn std::error::Error>> {
+    const RUST_ICON: &str = "\u{e68b}";
+    let temp_dir = tempdir()?;
+    let config = temp_dir.path().join("fstree.toml");
+    fs::write(&config, "sort = \"size\"\n\n[profile.fancy]\nicons = true\nsort = \"name\"\n")?;
+    let tree = temp_dir.path().join("tree");
+    fs::create_dir(&tree)?;
+    fs::write(tree.join("main.rs"), "fn main() {}")?;
+    fs::write(tree.join("zero.rs"), "")?;
+
+    let listed = |stdout: &str| -> Vec<String> {
+        stdout.lines().filter_map(|line| line.strip_prefix("└── ")).map(String::from).collect()
+    };
+
+    let mut base = fstree()?;
+    base.arg("--config").arg(&config).args(["--color", "never"]).arg(&tree);
+    let stdout = String::from_utf8(base.assert().success().get_output().stdout.clone())?;
+    assert_eq!(listed(&stdout), ["zero.rs", "main.rs"]);
+
+    let mut fancy = fstree()?;
+    fancy.arg("--config").arg(&config).args(["--profile", "fancy", "--color", "never"]);
+    let stdout =
+        String::from_utf8(fancy.arg(&tree).assert().success().get_output().stdout.clone())?;
+    assert_eq!(listed(&stdout), [format!("{RUST_ICON} main.rs"), format!("{RUST_ICON} zero.rs")]);
+
+    let mut missing = fstree()?;
+    missing.arg("--config").arg(&config).args(["--profile", "nope"]).arg(&tree);
+    missing.assert().failure().stderr(predicate::str::contains("Unknown profile 'nope'"));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_report_sparse_files() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::MetadataExt;
+
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir(temp_path.join("images"))?;
+    let sparse = temp_path.join("images/disk.img");
+    fs::File::create(&sparse)?.set_len(64 * 1024 * 1024)?;
+    fs::write(temp_path.join("notes.txt"), "dense")?;
+    if fs::metadata(&sparse)?.blocks() * 512 >= 64 * 1024 * 1024 {
+        // The file system does not support holes.
+        return Ok(());
+    }
+
+    let mut cmd = fstree()?;
+    cmd.args(["--report-sparse-only", "--color", "never"]).arg(temp_path);
+    cmd.assert().success().stdout(
+        predicate::str::contains("└── images\n")
+            .and(predicate::str::contains("disk.img [sparse "))
+            .and(predicate::str::contains("/64.0 MiB]"))
+            .and(predicate::str::contains("notes.txt").not())
+            .and(predicate::str::contains("1 sparse files (saving ")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_list_extensions() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    for file in ["a.rs", "b.rs", "c.rs", "d.md", "e.md", "f.toml", "g.txt", "h.json", "README"] {
+        fs::write(temp_path.join(file), "x")?;
+    }
+
+    let mut cmd = fstree()?;
+    cmd.arg("list-extensions").arg(temp_path);
+    cmd.assert()
+        .success()
+        .stdout("  .rs    (3)\n  .md    (2)\n  .json  (1)\n  .toml  (1)\n  .txt   (1)\n");
+
+    let mut json = fstree()?;
+    json.args(["list-extensions", "--list-extensions-include-no-ext", "--list-extensions-json"]);
+    json.arg(temp_path);
+    json.assert().success().stdout(
+        predicate::str::contains("\"extension\": \"[none]\"")
+            .and(predicate::str::contains("\"extension\": \".rs\"")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_output_file() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir(temp_path.join("src"))?;
+    fs::write(temp_path.join("src/main.rs"), "")?;
+    let output = temp_path.join("tree.txt");
+
+    let mut cmd = fstree()?;
+    cmd.args(["--color", "always", "-o"]).arg(&output).arg(temp_path);
+    cmd.assert().success().stdout(
+        predicate::str::contains("\x1b[").and(predicate::str::contains("1 directories, 1 files")),
+    );
+
+    // The copy is plain text, stops before the summary and does not list itself.
+    let written = fs::read_to_string(&output)?;
+    assert!(!written.contains('\x1b'), "{written:?}");
+    assert!(written.ends_with("└── src\n    └── main.rs\n"), "{written:?}");
+    assert!(!written.contains("tree.txt") && !written.contains("directories"));
+
+    let mut missing = fstree()?;
+    missing.arg("-o").arg(temp_path.join("no/such/dir/tree.txt")).arg(temp_path);
+    missing.assert().failure().stderr(predicate::str::contains("Failed to create"));
+
+    Ok(())
+}
+
+#[test]
+fn test_filter_ext() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir(temp_path.join("src"))?;
+    fs::write(temp_path.join("src/main.RS"), "")?;
+    fs::write(temp_path.join("Cargo.toml"), "")?;
+    fs::write(temp_path.join("README.md"), "")?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--filter-ext", "rs,toml"]).arg(temp_path);
+    cmd.assert().success().stdout(
+        predicate::str::contains("main.RS")
+            .and(predicate::str::contains("Cargo.toml"))
+            .and(predicate::str::contains("src"))
+            .and(predicate::str::contains("README.md").not()),
+    );
+
+    let mut excluded = fstree()?;
+    excluded.args(["--exclude-ext", "md"]).arg(temp_path);
+    excluded.assert().success().stdout(
+        predicate::str::contains("Cargo.toml").and(predicate::str::contains("README.md").not()),
+    );
+
+    let mut both = fstree()?;
+    both.args(["--filter-ext", "rs", "--exclude-ext", "md"]).arg(temp_path);
+    both.assert().failure().stderr(predicate::str::contains("cannot be used with"));
+
+    Ok(())
+}
+
+#[test]
+fn test_include_exclude_globs() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir_all(temp_path.join("target/debug"))?;
+    fs::create_dir(temp_path.join("src"))?;
+    fs::write(temp_path.join("src/lib.rs"), "")?;
+    fs::write(temp_path.join("target/debug/build.rs"), "")?;
+    fs::write(temp_path.join("notes.txt"), "")?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--include", "*.{rs,toml}", "--exclude", "target/**"]).arg(temp_path);
+    cmd.assert().success().stdout(
+        predicate::str::contains("lib.rs")
+            .and(predicate::str::contains("target").not())
+            .and(predicate::str::contains("notes.txt").not())
+            .and(predicate::str::contains("1 directories, 1 files")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_filter_regex() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir(temp_path.join("tests"))?;
+    fs::write(temp_path.join("tests/test_cli.py"), "")?;
+    fs::write(temp_path.join("setup.py"), "")?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--filter-regex", "^test_.*\\.py$"]).arg(temp_path);
+    cmd.assert().success().stdout(
+        predicate::str::contains("test_cli.py")
+            .and(predicate::str::contains("tests"))
+            .and(predicate::str::contains("setup.py").not()),
+    );
+
+    let mut invalid = fstree()?;
+    invalid.args(["--filter-regex", "(unclosed"]).arg(temp_path);
+    invalid
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --filter-regex pattern '(unclosed'"));
+
+    Ok(())
+}
+
+#[test]
+fn test_min_max_size() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("small.bin"), vec![0u8; 100])?;
+    fs::write(temp_path.join("medium.bin"), vec![0u8; 2048])?;
+    fs::write(temp_path.join("large.bin"), vec![0u8; 8192])?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--min-size", "1K", "--max-size", "4KiB", "-s"]).arg(temp_path);
+    cmd.assert().success().stdout(
+        predicate::str::contains("medium.bin (2.0 KiB)")
+            .and(predicate::str::contains("small.bin").not())
+            .and(predicate::str::contains("large.bin").not()),
+    );
+
+    let mut invalid = fstree()?;
+    invalid.args(["--min-size", "10X"]).arg(temp_path);
+    invalid.assert().failure().stderr(predicate::str::contains("invalid size suffix"));
+
+    Ok(())
+}
+
+#[test]
+fn test_newer_older_than() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    let dated =
+        [("old.txt", 1_577_836_800), ("mid.txt", 1_672_531_200), ("new.txt", 1_735_689_600)];
+    for (name, seconds) in dated {
+        let path = temp_path.join(name);
+        fs::write(&path, "")?;
+        filetime::set_file_mtime(&path, filetime::FileTime::from_unix_time(seconds, 0))?;
+    }
+
+    let mut cmd = fstree()?;
+    cmd.args(["--newer-than", "2022-01-01", "--older-than", "2024-01-01"]).arg(temp_path);
+    cmd.assert().success().stdout(
+        predicate::str::contains("mid.txt")
+            .and(predicate::str::contains("old.txt").not())
+            .and(predicate::str::contains("new.txt").not()),
+    );
+
+    let mut invalid = fstree()?;
+    invalid.args(["--newer-than", "01/02/2024"]).arg(temp_path);
+    invalid.assert().failure().stderr(predicate::str::contains("invalid date '01/02/2024'"));
+
+    Ok(())
+}
+
+#[test]
+fn test_files_only() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir_all(temp_path.join("assets/images"))?;
+    fs::write(temp_path.join("assets/images/logo.png"), "png")?;
+    fs::write(temp_path.join("index.html"), "")?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--files-only", "-s", "--sort", "size", "--reverse"]).arg(temp_path);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output)?;
+    let entries: Vec<&str> = stdout.lines().filter(|line| line.contains("└──")).collect();
+    assert_eq!(entries.len(), 2, "{stdout}");
+    assert!(entries.iter().all(|line| !line.contains("assets") && !line.contains("images")));
+    assert!(stdout.contains("0 directories, 2 files"), "{stdout}");
+
+    let mut both = fstree()?;
+    both.args(["--files-only", "--dirs-only"]).arg(temp_path);
+    both.assert().failure().stderr(predicate::str::contains("cannot be used with"));
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_dereference() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir(temp_path.join("real"))?;
+    fs::write(temp_path.join("real/data.txt"), "")?;
+    std::os::unix::fs::symlink("real", temp_path.join("alias"))?;
+    std::os::unix::fs::symlink("missing.txt", temp_path.join("dangling"))?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--dereference", "--color", "never"]).arg(temp_path);
+    cmd.assert().success().stdout(
+        predicate::str::contains("alias -> real")
+            .and(predicate::str::contains("dangling -> missing.txt"))
+            .and(predicate::str::contains("1 directories, 3 files, 2 symlinks")),
+    );
+
+    let mut colored = fstree()?;
+    colored.args(["--dereference", "--color", "always"]).arg(temp_path);
+    colored.assert().success().stdout(predicate::str::contains(" -> \x1b[31mmissing.txt"));
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_owner() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::MetadataExt;
+
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("owned.txt"), "")?;
+    let metadata = fs::metadata(temp_path.join("owned.txt"))?;
+    let user = uzers::get_user_by_uid(metadata.uid())
+        .map_or(metadata.uid().to_string(), |u| u.name().to_string_lossy().into_owned());
+    let group = uzers::get_group_by_gid(metadata.gid())
+        .map_or(metadata.gid().to_string(), |g| g.name().to_string_lossy().into_owned());
+
+    let mut cmd = fstree()?;
+    cmd.args(["--owner", "--color", "never"]).arg(temp_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(format!("{user}:{group} └── owned.txt")));
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_inode() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::MetadataExt;
+
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("original.txt"), "")?;
+    fs::hard_link(temp_path.join("original.txt"), temp_path.join("link.txt"))?;
+    let inode = fs::metadata(temp_path.join("original.txt"))?.ino();
+
+    let mut cmd = fstree()?;
+    cmd.args(["--inode", "--color", "never"]).arg(temp_path);
+    cmd.assert().success().stdout(
+        predicate::str::contains(format!("{inode} └── link.txt"))
+            .and(predicate::str::contains(format!("{inode} └── original.txt")))
+            .and(predicate::str::ends_with("\n0 directories, 2 files\n")),
+    );
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_nlinks() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("header.h"), "")?;
+    fs::hard_link(temp_path.join("header.h"), temp_path.join("copy.h"))?;
+    fs::hard_link(temp_path.join("header.h"), temp_path.join("other.h"))?;
+    fs::write(temp_path.join("single.c"), "")?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--nlinks", "--color", "never"]).arg(temp_path);
+    cmd.assert().success().stdout(
+        predicate::str::contains("3 └── header.h").and(predicate::str::contains("1 └── single.c")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_atime() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    let path = temp_path.join("read.log");
+    fs::write(&path, "")?;
+    let two_weeks_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(15 * 86400);
+    filetime::set_file_atime(&path, filetime::FileTime::from_system_time(two_weeks_ago))?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--atime", "--color", "never"]).arg(temp_path);
+    cmd.assert().success().stdout(predicate::str::contains("read.log (accessed 2 weeks ago)"));
+
+    Ok(())
+}
+
+#[test]
+fn test_indent() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir_all(temp_path.join("outer/inner"))?;
+    fs::write(temp_path.join("outer/inner/leaf.txt"), "")?;
+
+    let column_of = |indent: &str, name: &str| -> Result<usize, Box<dyn std::error::Error>> {
+        let mut cmd = fstree()?;
+        cmd.args(["--indent", indent, "--color", "never"]).arg(temp_path);
+        let stdout = String::from_utf8(cmd.assert().success().get_output().stdout.clone())?;
+        let line = stdout.lines().find(|line| line.ends_with(name)).ok_or("missing entry")?;
+        Ok(line.chars().position(|c| c == '└').ok_or("missing connector")?)
+    };
+    assert_eq!(column_of("4", "inner")?, 4);
+    assert_eq!(column_of("4", "leaf.txt")?, 8);
+    assert_eq!(column_of("2", "inner")?, 2);
+    assert_eq!(column_of("2", "leaf.txt")?, 4);
+
+    let mut zero = fstree()?;
+    zero.args(["--indent", "0"]).arg(temp_path);
+    zero.assert().failure().stderr(predicate::str::contains("at least 1"));
+
+    Ok(())
+}
+
+#[test]
+fn test_no_tree_and_print0() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir(temp_path.join("src"))?;
+    fs::write(temp_path.join("src/main.rs"), "")?;
+    fs::write(temp_path.join("build.rs"), "")?;
+    let root = fs::canonicalize(temp_path)?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--no-tree", "--files-only", "--color", "always"]).arg(temp_path);
+    cmd.assert().success().stdout(format!(
+        "{}\n{}\n",
+        root.join("build.rs").display(),
+        root.join("src/main.rs").display()
+    ));
+
+    let mut nul = fstree()?;
+    nul.arg("--print0").arg(temp_path);
+    nul.assert().success().stdout(format!(
+        "{}\0{}\0{}\0",
+        root.join("build.rs").display(),
+        root.join("src").display(),
+        root.join("src/main.rs").display()
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_du() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir_all(temp_path.join("big/nested"))?;
+    fs::write(temp_path.join("big/a.bin"), vec![0u8; 1024])?;
+    fs::write(temp_path.join("big/nested/b.bin"), vec![0u8; 1024])?;
+    fs::create_dir(temp_path.join("small"))?;
+    fs::write(temp_path.join("small/c.bin"), vec![0u8; 10])?;
+    fs::write(temp_path.join("medium.bin"), vec![0u8; 100])?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--du", "--sort", "size", "--reverse", "--color", "never"]).arg(temp_path);
+    let stdout = String::from_utf8(cmd.assert().success().get_output().stdout.clone())?;
+    let top_level: Vec<&str> = stdout.lines().filter(|line| line.starts_with("└──")).collect();
+    assert_eq!(top_level, ["└── big (2.0 KiB)", "└── medium.bin", "└── small (10 B)"]);
+    assert!(stdout.contains("    └── nested (1.0 KiB)"), "{stdout}");
+
+    Ok(())
+}
+
+#[test]
+fn test_sort_created() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    // Created in the order b, c, a, but modified in the reverse order.
+    let names = ["b_first.txt", "c_second.txt", "a_third.txt"];
+    for (i, name) in names.iter().enumerate() {
+        fs::write(temp_path.join(name), "")?;
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let mtime = filetime::FileTime::from_unix_time(2_000_000_000 - i as i64 * 1000, 0);
+        filetime::set_file_mtime(temp_path.join(name), mtime)?;
+    }
+    // Without birth times, the sort falls back to the modification time.
+    let mut expected = names.to_vec();
+    if fs::metadata(temp_path.join(names[0]))?.created().is_err() {
+        expected.reverse();
+    }
+
+    let mut cmd = fstree()?;
+    cmd.args(["--sort", "created", "--color", "never"]).arg(temp_path);
+    let stdout = String::from_utf8(cmd.assert().success().get_output().stdout.clone())?;
+    let listed: Vec<&str> = stdout.lines().filter_map(|line| line.strip_prefix("└── ")).collect();
+    assert_eq!(listed, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_sort2_breaks_ties() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    for name in ["delta.txt", "alpha.txt", "charlie.txt", "bravo.txt"] {
+        fs::File::create(temp_dir.path().join(name))?;
+    }
+
+    // All files are empty, so the default secondary sort by name decides.
+    let mut cmd = fstree()?;
+    cmd.args(["--sort", "size", "--color", "never"]).arg(temp_dir.path());
+    let stdout = String::from_utf8(cmd.output()?.stdout)?;
+    let positions: Vec<usize> = ["alpha.txt", "bravo.txt", "charlie.txt", "delta.txt"]
+        .iter()
+        .map(|name| stdout.find(name).unwrap())
+        .collect();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]), "{stdout}");
+
+    // An explicit secondary sort by extension puts `.bin` first.
+    fs::File::create(temp_dir.path().join("zulu.bin"))?;
+    let mut cmd = fstree()?;
+    cmd.args(["--sort", "size", "--sort2", "extension", "--color", "never"]).arg(temp_dir.path());
+    let stdout = String::from_utf8(cmd.output()?.stdout)?;
+    assert!(stdout.find("zulu.bin").unwrap() < stdout.find("alpha.txt").unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn test_sort_accessed() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    // Accessed in the order b, c, a, with equal sizes.
+    let names = ["b_first.txt", "c_second.txt", "a_third.txt"];
+    for (i, name) in names.iter().enumerate() {
+        fs::write(temp_path.join(name), "")?;
+        let atime = filetime::FileTime::from_unix_time(1_000_000_000 + i as i64 * 1000, 0);
+        filetime::set_file_atime(temp_path.join(name), atime)?;
+    }
+
+    let mut cmd = fstree()?;
+    cmd.args(["--sort", "accessed", "--color", "never"]).arg(temp_path);
+    let stdout = String::from_utf8(cmd.assert().success().get_output().stdout.clone())?;
+    let listed: Vec<&str> = stdout.lines().filter_map(|line| line.strip_prefix("└── ")).collect();
+    assert_eq!(listed, names);
+
+    // The access time also works as the secondary sort key.
+    let mut cmd = fstree()?;
+    cmd.args(["--sort", "size", "--sort2", "accessed", "--color", "never"]).arg(temp_path);
+    let stdout = String::from_utf8(cmd.assert().success().get_output().stdout.clone())?;
+    let listed: Vec<&str> = stdout.lines().filter_map(|line| line.strip_prefix("└── ")).collect();
+    assert_eq!(listed, names);
+
+    Ok(())
+}
+
+#[test]
+fn test_sort_random() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    for i in 0..10 {
+        fs::File::create(temp_dir.path().join(format!("file{i}.txt")))?;
+    }
+
+    let run = || -> Result<String, Box<dyn std::error::Error>> {
+        let mut cmd = fstree()?;
+        cmd.args(["--sort", "random", "--color", "never"]).arg(temp_dir.path());
+        Ok(String::from_utf8(cmd.assert().success().get_output().stdout.clone())?)
+    };
+
+    // Ten entries have 10! orders, so five identical runs are practically impossible.
+    let first = run()?;
+    let mut differs = false;
+    for _ in 0..5 {
+        if run()? != first {
+            differs = true;
+            break;
+        }
+    }
+    assert!(differs, "--sort random produced the same order every time:\n{first}");
+
+    Ok(())
+}
+
+#[test]
+fn test_sort_children() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    for (dir, count) in [("few", 1), ("many", 3), ("some", 2)] {
+        fs::create_dir(temp_path.join(dir))?;
+        for i in 0..count {
+            fs::File::create(temp_path.join(dir).join(format!("{i}.txt")))?;
+        }
+    }
+    fs::File::create(temp_path.join("file.txt"))?;
+
+    // Children are counted on disk, even below the level limit.
+    let mut cmd = fstree()?;
+    cmd.args(["--sort", "children", "-L", "1", "--color", "never"]).arg(temp_path);
+    let stdout = String::from_utf8(cmd.assert().success().get_output().stdout.clone())?;
+    let listed: Vec<&str> = stdout.lines().filter_map(|line| line.strip_prefix("└── ")).collect();
+    assert_eq!(listed, ["many", "some", "few", "file.txt"]);
+
+    let mut cmd = fstree()?;
+    cmd.args(["--sort", "children", "--reverse", "-L", "1", "--color", "never"]).arg(temp_path);
+    let stdout = String::from_utf8(cmd.assert().success().get_output().stdout.clone())?;
+    let listed: Vec<&str> = stdout.lines().filter_map(|line| line.strip_prefix("└── ")).collect();
+    assert_eq!(listed, ["file.txt", "few", "some", "many"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_watch_redraws_on_change() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Read;
+    use std::process::Stdio;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("before.txt"), "")?;
+
+    let mut child = fstree()?
+        .args(["--watch", "--watch-interval", "50", "--color", "never"])
+        .arg(temp_dir.path())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let output = Arc::new(Mutex::new(String::new()));
+    let mut stdout = child.stdout.take().unwrap();
+    let reader_output = Arc::clone(&output);
+    std::thread::spawn(move || {
+        let mut buffer = [0; 4096];
+        while let Ok(n @ 1..) = stdout.read(&mut buffer) {
+            reader_output.lock().unwrap().push_str(&String::from_utf8_lossy(&buffer[..n]));
+        }
+    });
+    let wait_for = |text: &str| {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !output.lock().unwrap().contains(text) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    };
+
+    wait_for("before.txt");
+    fs::write(temp_dir.path().join("after.txt"), "")?;
+    wait_for("after.txt");
+    child.kill()?;
+
+    let output = output.lock().unwrap().clone();
+    let redraws: Vec<&str> = output.split("\x1B[2J\x1B[H").skip(1).collect();
+    assert!(redraws.len() >= 2, "{output:?}");
+    assert!(!redraws[0].contains("after.txt"));
+    assert!(redraws.last().unwrap().contains("after.txt"));
+
+    Ok(())
+}
+
+#[test]
+fn test_parallel_matches_serial() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    for dir in ["a", "b/c", "b/d", "e"] {
+        fs::create_dir_all(temp_dir.path().join(dir))?;
+        for i in 0..5 {
+            fs::write(temp_dir.path().join(dir).join(format!("file{i}.txt")), "x".repeat(i))?;
+        }
+    }
+    fs::write(temp_dir.path().join("b/.hidden.txt"), "")?;
+
+    let run = |extra: &[&str]| -> Result<String, Box<dyn std::error::Error>> {
+        let mut cmd = fstree()?;
+        cmd.args(["--size", "--color", "never"]).args(extra).arg(temp_dir.path());
+        Ok(String::from_utf8(cmd.assert().success().get_output().stdout.clone())?)
+    };
+
+    let serial = run(&[])?;
+    assert!(!serial.contains(".hidden.txt"));
+    for _ in 0..3 {
+        assert_eq!(run(&["--parallel"])?, serial);
+    }
+    assert_eq!(
+        run(&["-P", "--sort", "size", "--dirs-first"])?,
+        run(&["--sort", "size", "--dirs-first"])?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_progress_ignored_when_piped() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("a.txt"), "")?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--progress", "--color", "never"]).arg(temp_dir.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("└── a.txt"))
+        .stderr(predicate::str::is_empty());
+
+    let mut both = fstree()?;
+    both.args(["--progress", "--no-progress"]).arg(temp_dir.path());
+    both.assert().success().stderr(predicate::str::is_empty());
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_follow_links() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::symlink;
+
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path().join("root");
+    fs::create_dir_all(root.join("real"))?;
+    fs::write(root.join("real/inside.txt"), "")?;
+    symlink("real", root.join("linked"))?;
+    symlink("..", root.join("real/loop"))?;
+    // A link above the root is not one of the walked directories.
+    symlink("../..", root.join("real/outside"))?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--follow-links", "--color", "never"]).arg(&root);
+    let assert = cmd.assert().success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone())?;
+    let stderr = String::from_utf8(assert.get_output().stderr.clone())?;
+    assert!(stdout.contains("└── linked -> real\n"), "{stdout}");
+    assert_eq!(stdout.matches("inside.txt").count(), 2, "{stdout}");
+    assert!(!stdout.contains("loop") && !stdout.contains("outside"), "{stdout}");
+    assert!(stderr.contains("WARNING: Not following symlink loop"), "{stderr}");
+    assert!(!stderr.contains("ERROR"), "{stderr}");
+
+    // Without the flag, symlinked directories are listed but not entered.
+    let mut plain = fstree()?;
+    plain.args(["--color", "never"]).arg(&root);
+    let stdout = String::from_utf8(plain.assert().success().get_output().stdout.clone())?;
+    assert_eq!(stdout.matches("inside.txt").count(), 1, "{stdout}");
+
+    Ok(())
+}
+
+#[test]
+fn test_one_filesystem() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("src/nested"))?;
+    fs::write(temp_dir.path().join("src/nested/lib.rs"), "")?;
+    fs::write(temp_dir.path().join("README.md"), "")?;
+
+    // A temporary directory has no mount points, so nothing is skipped.
+    let run = |extra: &[&str]| -> Result<String, Box<dyn std::error::Error>> {
+        let mut cmd = fstree()?;
+        cmd.args(["--color", "never"]).args(extra).arg(temp_dir.path());
+        Ok(String::from_utf8(cmd.assert().success().get_output().stdout.clone())?)
+    };
+    let plain = run(&[])?;
+    assert!(plain.contains("lib.rs"));
+    assert_eq!(run(&["--one-filesystem"])?, plain);
+    assert_eq!(run(&["-x"])?, plain);
+
+    Ok(())
+}
+
+#[test]
+fn test_format_json() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir(temp_path.join("src"))?;
+    fs::write(temp_path.join("src/main.rs"), "fn main() {}")?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--format", "json", "--virtual-root", "project"]).arg(temp_path);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let document: serde_json::Value = serde_json::from_slice(&output)?;
+
+    assert_eq!(document["root"], temp_path.display().to_string());
+    assert_eq!(document["virtual_root"], "project");
+    assert_eq!(document["summary"], serde_json::json!({ "directories": 1, "files": 1 }));
+    let entries = document["entries"].as_array().unwrap();
+    assert_eq!(entries[0]["path"], "src");
+    assert_eq!(entries[0]["is_dir"], true);
+    assert_eq!(entries[1]["path"], "src/main.rs");
+    assert_eq!(entries[1]["depth"], 2);
+    assert_eq!(entries[1]["size"], 12);
+    assert_eq!(entries[1]["git_status"], serde_json::Value::Null);
+
+    Ok(())
+}
+
+#[test]
+fn test_format_csv() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir(temp_path.join("data"))?;
+    fs::write(temp_path.join("data/report.csv"), "a,b\n")?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--format", "csv", "--color", "always"]).arg(temp_path);
+    cmd.assert().success().stdout(
+        predicate::str::starts_with(
+            "path,depth,is_dir,size_bytes,permissions,git_status,extension\n",
+        )
+        .and(predicate::str::is_match(r"\ndata/report\.csv,2,false,4,[-rwx]*,,csv\n")?)
+        .and(predicate::str::contains("\x1b").not()),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_format_markdown() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("notes.md"), "")?;
+
+    let mut cmd = fstree()?;
+    cmd.args(["--format", "markdown", "--color", "always", "--virtual-root", "docs"]);
+    cmd.arg(temp_path);
+    cmd.assert().success().stdout("```\ndocs\n└── notes.md\n```\n\n0 directories, 1 files\n");
+
+    fs::create_dir(temp_path.join("guides"))?;
+    fs::write(temp_path.join("guides/setup.md"), "")?;
+    let mut linked = fstree()?;
+    linked.args(["--format", "markdown", "--hyperlinks", "--virtual-root", "docs"]);
+    linked.arg(temp_path);
+    let output = linked.output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    // Links only render outside a code block, so the tree becomes a nested list.
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[..2], ["docs", "- guides"]);
+    assert!(lines[2].starts_with("  - [setup.md](file:///"));
+    assert!(lines[2].ends_with("/guides/setup.md)"));
+    assert!(lines[3].starts_with("- [notes.md](file:///"));
+    assert!(!stdout.contains("```") && !stdout.contains('\x1b'));
+
+    Ok(())
+}
+
+#[test]
+fn test_format_html() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let temp_path = temp_dir.path().join("site");
+    fs::create_dir(&temp_path)?;
+    fs::write(temp_path.join("index.html"), "")?;
+    let page = temp_dir.path().join("tree.html");
+
+    let mut cmd = fstree()?;
+    cmd.env("LS_COLORS", "*.html=38;5;196");
+    cmd.args(["--format", "html", "--hyperlinks", "-o"]).arg(&page).arg(&temp_path);
+    cmd.assert().success().stdout("");
+
+    let html = fs::read_to_string(&page)?;
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<pre>"));
+    assert!(html.contains("<a href=\"file:///"));
+    assert!(html.contains("<span style=\"color:#ff0000;\">index.html</span></a>"));
+    assert!(html.contains("0 directories, 1 files"));
+
+    Ok(())
+}